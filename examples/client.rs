@@ -55,9 +55,19 @@ async fn main() -> Result<(), Whatever> {
 			_ = &mut period => {
 				tracing::info!("Period");
 				check_error(client.send_command_rc(47, 13, Rcs::Increment, None, None, None).await)?;
-				check_error(client.send_command_sp(47, 14, Spi::On, None, None, None).await)?;
+				if let Some(handle) =
+					check_error(client.send_command_sp(47, 14, Spi::On, None, None, None).await)?
+				{
+					tokio::spawn(async move {
+						match handle.termination().await {
+							Ok(_) => tracing::info!("Single command terminated"),
+							Err(e) => tracing::error!("Single command failed: {e}"),
+						}
+					});
+				}
 				check_error(client.send_command_dp(47, 15, Dpi::On, None, None, None).await)?;
 				check_error(client.send_command_bs(47, 16, 1, None).await)?;
+				check_error(client.send_setpoint_float(47, 17, 42.0, None, None).await)?;
 
 
 				period.as_mut().reset(Instant::now() + Duration::from_secs(1));
@@ -91,18 +101,15 @@ impl OnNewObjects for MyCallback {
 }
 
 /// Check the error to see if it is a critical error
-fn check_error(r: Result<(), ClientError>) -> Result<(), Whatever> {
-	if let Err(e) = r {
-		match e {
-			ClientError::NoWriteChannel { .. } => {
-				whatever!("There is no channel to send commands");
-			}
-			_ => {
-				tracing::error!("Error sending ASDU: {e}");
-				Ok(())
-			}
+fn check_error<T>(r: Result<T, ClientError>) -> Result<Option<T>, Whatever> {
+	match r {
+		Ok(value) => Ok(Some(value)),
+		Err(ClientError::NoWriteChannel { .. }) => {
+			whatever!("There is no channel to send commands");
+		}
+		Err(e) => {
+			tracing::error!("Error sending ASDU: {e}");
+			Ok(None)
 		}
-	} else {
-		Ok(())
 	}
 }
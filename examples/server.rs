@@ -0,0 +1,74 @@
+//! Example server for IEC 60870-5-104
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use iec104::{
+	asdu::Asdu,
+	config::ServerConfig,
+	cot::Cot,
+	server::{OnNewCommand, Server},
+	types::{GenericObject, InformationObjects, MMeNc1},
+};
+use snafu::{ResultExt as _, Whatever};
+use tokio::signal::unix::{SignalKind, signal};
+use tracing_error::ErrorLayer;
+use tracing_subscriber::{
+	Layer as _, filter::EnvFilter, layer::SubscriberExt as _, util::SubscriberInitExt as _,
+};
+
+#[tokio::main]
+async fn main() -> Result<(), Whatever> {
+	let filter = EnvFilter::from("info");
+	let layer = tracing_subscriber::fmt::layer().with_filter(filter);
+	tracing_subscriber::registry()
+		.with(layer)
+		//needed to get the tracing_error working
+		.with(ErrorLayer::default().with_filter(EnvFilter::from("debug")))
+		.init();
+
+	let mut server = Server::new(ServerConfig::default(), MyCallback);
+	server.start().await.whatever_context("Failed to start server")?;
+
+	let mut s1 = signal(SignalKind::interrupt()).whatever_context("Failed to create signal")?;
+	let mut s2 = signal(SignalKind::terminate()).whatever_context("Failed to create signal")?;
+
+	let mut period = tokio::time::interval(Duration::from_millis(700));
+	let mut value = 0.0;
+
+	loop {
+		tokio::select! {
+			_ = s1.recv() => {tracing::info!("SIGINT"); break;},
+			_ = s2.recv() => {tracing::info!("SIGTERM"); break;},
+			_ = period.tick() => {
+				value += 1.0;
+				let objects = InformationObjects::MMeNc1(vec![GenericObject {
+					address: 11,
+					object: MMeNc1 { value, ..Default::default() },
+				}]);
+				if let Err(e) = server.update_points(47, objects, true) {
+					tracing::debug!("Not sending spontaneous data: {e}");
+				}
+			},
+		}
+	}
+
+	tracing::info!("Stopping");
+	server.stop();
+
+	Ok(())
+}
+
+/// Callback for the server
+struct MyCallback;
+
+#[async_trait]
+impl OnNewCommand for MyCallback {
+	async fn on_new_command(&self, asdu: Asdu) -> Vec<Asdu> {
+		tracing::info!("Received command: {asdu:?}");
+		vec![
+			Asdu { cot: Cot::ActivationConfirmation, ..asdu.clone() },
+			Asdu { cot: Cot::ActivationTermination, ..asdu },
+		]
+	}
+}
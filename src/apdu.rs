@@ -2,7 +2,7 @@ use snafu::{OptionExt as _, ResultExt as _};
 use tracing::instrument;
 
 use crate::{
-	asdu::Asdu,
+	asdu::{Asdu, AsduProfile},
 	error::{self, Error, InvalidAsdu, NotEnoughBytes, SizedSlice},
 };
 
@@ -16,8 +16,13 @@ pub struct Apdu {
 }
 
 impl Apdu {
-	#[instrument]
 	pub fn from_bytes(data: &[u8]) -> Result<Self, Error> {
+		Self::from_bytes_with_profile(data, AsduProfile::IEC_104)
+	}
+
+	/// Decodes the APDU, with the ASDU field sizes of the profile.
+	#[instrument]
+	pub fn from_bytes_with_profile(data: &[u8], profile: AsduProfile) -> Result<Self, Error> {
 		// Check if the data is long enough to contain the APDU header
 		if data.len() < 6 {
 			return error::ApduTooShort.fail();
@@ -36,19 +41,24 @@ impl Apdu {
 		let frame = if length == 4_u8 {
 			Frame::from_control_fields(control_fields)
 		} else {
-			Frame::from_asdu(control_fields, data.get(6..).context(NotEnoughBytes)?)
+			Frame::from_asdu(control_fields, data.get(6..).context(NotEnoughBytes)?, profile)
 		}?;
 
 		Ok(Self { length, frame })
 	}
 
 	pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+		self.to_bytes_with_profile(AsduProfile::IEC_104)
+	}
+
+	/// Encodes the APDU, with the ASDU field sizes of the profile.
+	pub fn to_bytes_with_profile(&self, profile: AsduProfile) -> Result<Vec<u8>, Error> {
 		// The total length of the APDU is the length of the frame plus 2 bytes, one for
 		// the header and one for the length
 		let mut bytes = Vec::with_capacity(self.length as usize + 2);
 		bytes.push(TELEGRAN_HEADER);
 		bytes.push(self.length);
-		self.frame.to_bytes(&mut bytes)?;
+		self.frame.to_bytes_with_profile(profile, &mut bytes)?;
 		Ok(bytes)
 	}
 }
@@ -69,22 +79,42 @@ impl Frame {
 		}
 	}
 
-	fn from_asdu(control_fields: [u8; 4], asdu: &[u8]) -> Result<Self, Error> {
-		Ok(Frame::I(IFrame::from_asdu(control_fields, asdu)?))
+	fn from_asdu(
+		control_fields: [u8; 4],
+		asdu: &[u8],
+		profile: AsduProfile,
+	) -> Result<Self, Error> {
+		Ok(Frame::I(IFrame::from_asdu(control_fields, asdu, profile)?))
 	}
 
 	pub fn to_bytes(&self, buffer: &mut Vec<u8>) -> Result<(), Error> {
+		self.to_bytes_with_profile(AsduProfile::IEC_104, buffer)
+	}
+
+	/// Encodes the frame, with the ASDU field sizes of the profile.
+	pub fn to_bytes_with_profile(
+		&self,
+		profile: AsduProfile,
+		buffer: &mut Vec<u8>,
+	) -> Result<(), Error> {
 		match self {
-			Frame::I(i) => i.to_bytes(buffer),
+			Frame::I(i) => i.to_bytes(profile, buffer),
 			Frame::S(s) => s.to_bytes(buffer),
 			Frame::U(u) => u.to_bytes(buffer),
 		}
 	}
+
 	pub fn to_apdu_bytes(&self) -> Result<Vec<u8>, Error> {
+		self.to_apdu_bytes_with_profile(AsduProfile::IEC_104)
+	}
+
+	/// Encodes the frame as a whole APDU, with the ASDU field sizes of the
+	/// profile.
+	pub fn to_apdu_bytes_with_profile(&self, profile: AsduProfile) -> Result<Vec<u8>, Error> {
 		let mut buffer = Vec::new();
 		buffer.push(TELEGRAN_HEADER);
 		buffer.push(0); // length placeholder
-		self.to_bytes(&mut buffer)?;
+		self.to_bytes_with_profile(profile, &mut buffer)?;
 		buffer[1] = (buffer.len() - 2) as u8; // update length
 		Ok(buffer)
 	}
@@ -102,7 +132,11 @@ pub struct IFrame {
 
 impl IFrame {
 	#[instrument]
-	fn from_asdu(control_fields: [u8; 4], asdu: &[u8]) -> Result<Self, Error> {
+	fn from_asdu(
+		control_fields: [u8; 4],
+		asdu: &[u8],
+		profile: AsduProfile,
+	) -> Result<Self, Error> {
 		if (control_fields[0] & 0b0000_0001) != 0 || (control_fields[2] & 0b0000_0001) != 0 {
 			return error::InvalidIFrameControlFields.fail();
 		}
@@ -113,18 +147,18 @@ impl IFrame {
 			receive_sequence_number: u16::from_le_bytes(
 				control_fields[2..4].try_into().context(SizedSlice)?,
 			) >> 1,
-			asdu: Asdu::parse(asdu).context(InvalidAsdu)?,
+			asdu: Asdu::parse_with_profile(asdu, profile).context(InvalidAsdu)?,
 		})
 	}
 
-	fn to_bytes(&self, buffer: &mut Vec<u8>) -> Result<(), Error> {
+	fn to_bytes(&self, profile: AsduProfile, buffer: &mut Vec<u8>) -> Result<(), Error> {
 		let rsn = (self.receive_sequence_number << 1).to_le_bytes();
 		let ssn = (self.send_sequence_number << 1).to_le_bytes();
 		buffer.push(ssn[0]);
 		buffer.push(ssn[1]);
 		buffer.push(rsn[0]);
 		buffer.push(rsn[1]);
-		self.asdu.to_bytes(buffer).context(InvalidAsdu)?;
+		self.asdu.to_bytes_with_profile(profile, buffer).context(InvalidAsdu)?;
 		Ok(())
 	}
 }
@@ -238,6 +272,7 @@ impl UFrame {
 mod tests {
 	use super::*;
 	use crate::{
+		asdu::BROADCAST_ADDRESS,
 		cot::Cot,
 		types::{
 			InformationObjects,
@@ -385,6 +420,118 @@ mod tests {
 		Ok(())
 	}
 
+	#[test]
+	fn test_i_frame_asdu_profile() -> Result<(), Error> {
+		// 1 byte cause of transmission and common address, 2 bytes information object
+		// address
+		let profile = AsduProfile { cot_size: 1, common_address_size: 1, ioa_size: 2 };
+		let bytes = [0x68, 0x0B, 0x4E, 0x14, 0x7C, 0x00, 0x65, 0x01, 0x0A, 0xFF, 0x34, 0x12, 0x05];
+		assert!(Apdu::from_bytes(&bytes).is_err());
+
+		let apdu = Apdu::from_bytes_with_profile(&bytes, profile)?;
+		let Frame::I(i_frame) = &apdu.frame else { panic!("Frame was expected to be an I-frame") };
+		assert_eq!(i_frame.asdu.type_id, TypeId::C_CI_NA_1);
+		assert_eq!(i_frame.asdu.cot, Cot::ActivationTermination);
+		assert_eq!(i_frame.asdu.originator_address, 0);
+		assert_eq!(i_frame.asdu.address_field, BROADCAST_ADDRESS);
+		let InformationObjects::CCiNa1(objects) = &i_frame.asdu.information_objects else {
+			panic!("Information objects were expected to be a CCiNa1")
+		};
+		assert_eq!(objects[0].address, 0x1234);
+
+		assert_eq!(apdu.to_bytes_with_profile(profile)?, bytes);
+
+		let mut too_large = i_frame.clone();
+		too_large.asdu.address_field = 0x100;
+		assert!(Frame::I(too_large).to_apdu_bytes_with_profile(profile).is_err());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_i_frame_directory() -> Result<(), Error> {
+		let bytes = [
+			0x68, 0x1A, 0x00, 0x00, 0x00, 0x00, 0x7E, 0x01, 0x05, 0x00, 0x01, 0x00, 0x01, 0x00,
+			0x00, 0x01, 0x00, 0x10, 0x27, 0x00, 0x20, 0x00, 0x00, 0x1E, 0x0C, 0x0F, 0x06, 0x18,
+		];
+		let apdu = Apdu::from_bytes(&bytes)?;
+
+		let Frame::I(i_frame) = &apdu.frame else { panic!("Frame was expected to be an I-frame") };
+		assert_eq!(i_frame.asdu.type_id, TypeId::F_DR_TA_1);
+		assert_eq!(i_frame.asdu.cot, Cot::Request);
+		let InformationObjects::FDrTa1(objects) = &i_frame.asdu.information_objects else {
+			panic!("Information objects were expected to be a FDrTa1")
+		};
+		assert_eq!(objects.len(), 1);
+		assert_eq!(objects[0].address, 1);
+		assert_eq!(objects[0].object.nof, 1);
+		assert_eq!(objects[0].object.lof, 10000);
+		assert!(objects[0].object.sof.lfd);
+		assert!(!objects[0].object.sof.fa);
+		assert_eq!(objects[0].object.time.hour, 12);
+		assert_eq!(objects[0].object.time.min, 30);
+		assert_eq!(objects[0].object.time.year, 24);
+
+		let apdu_bytes = apdu.to_bytes()?;
+		assert_eq!(apdu_bytes, bytes);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_i_frame_directory_sequence() -> Result<(), Error> {
+		let bytes = [
+			0x68, 0x27, 0x00, 0x00, 0x00, 0x00, 0x7E, 0x82, 0x05, 0x00, 0x01, 0x00, 0x01, 0x00,
+			0x00, 0x01, 0x00, 0x10, 0x27, 0x00, 0x00, 0x00, 0x00, 0x1E, 0x0C, 0x0F, 0x06, 0x18,
+			0x02, 0x00, 0x20, 0x00, 0x00, 0x20, 0x00, 0x00, 0x1E, 0x0C, 0x0F, 0x06, 0x18,
+		];
+		let apdu = Apdu::from_bytes(&bytes)?;
+
+		let Frame::I(i_frame) = &apdu.frame else { panic!("Frame was expected to be an I-frame") };
+		assert!(i_frame.asdu.sequence);
+		let InformationObjects::FDrTa1(objects) = &i_frame.asdu.information_objects else {
+			panic!("Information objects were expected to be a FDrTa1")
+		};
+		assert_eq!(objects.len(), 2);
+		assert_eq!(objects[0].address, 1);
+		assert_eq!(objects[0].object.nof, 1);
+		assert!(!objects[0].object.sof.lfd);
+		assert_eq!(objects[1].address, 2);
+		assert_eq!(objects[1].object.nof, 2);
+		assert_eq!(objects[1].object.lof, 32);
+		assert!(objects[1].object.sof.lfd);
+
+		let apdu_bytes = apdu.to_bytes()?;
+		assert_eq!(apdu_bytes, bytes);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_i_frame_segment() -> Result<(), Error> {
+		let bytes = [
+			0x68, 0x14, 0x00, 0x00, 0x00, 0x00, 0x7D, 0x01, 0x0D, 0x00, 0x01, 0x00, 0x01, 0x00,
+			0x00, 0x01, 0x00, 0x01, 0x03, 0xAA, 0xBB, 0xCC,
+		];
+		let apdu = Apdu::from_bytes(&bytes)?;
+
+		let Frame::I(i_frame) = &apdu.frame else { panic!("Frame was expected to be an I-frame") };
+		assert_eq!(i_frame.asdu.type_id, TypeId::F_SG_NA_1);
+		assert_eq!(i_frame.asdu.cot, Cot::File);
+		let InformationObjects::FSgNa1(objects) = &i_frame.asdu.information_objects else {
+			panic!("Information objects were expected to be a FSgNa1")
+		};
+		assert_eq!(objects.len(), 1);
+		assert_eq!(objects[0].object.nof, 1);
+		assert_eq!(objects[0].object.nos, 1);
+		assert_eq!(objects[0].object.segment, [0xAA, 0xBB, 0xCC]);
+
+		let apdu_bytes = apdu.to_bytes()?;
+		assert_eq!(apdu_bytes, bytes);
+
+		Ok(())
+	}
+
 	#[test]
 	fn test_u_frame() -> Result<(), Error> {
 		let bytes = [0x68, 0x04, 0x01, 0x00, 0x7E, 0x14];
@@ -1,13 +1,81 @@
-use snafu::{OptionExt as _, ResultExt as _, Snafu};
+use serde::{Deserialize, Serialize};
+use snafu::{OptionExt as _, ResultExt as _, Snafu, ensure};
 use tracing::instrument;
 
 use crate::{
+	apdu::APUD_MAX_LENGTH,
 	cot::{Cot, CotError},
 	error::SpanTraceWrapper,
-	types::{InformationObjects, ParseError},
+	types::{ADDRESS_SIZE, InformationObjects, ParseError},
 	types_id::TypeId,
 };
 
+/// Length of the control field of an I frame.
+const CONTROL_FIELD_LENGTH: usize = 4;
+/// The number of objects is coded in 7 bits of the variable structure
+/// qualifier.
+const MAX_NUM_OBJECTS: usize = 127;
+/// Common address addressing every station of the controlled station.
+pub const BROADCAST_ADDRESS: u16 = 0xFFFF;
+
+/// The sizes of the fields of an ASDU that vary between the companion
+/// standards and the vendor profiles.
+///
+/// The default is the IEC 104 one. IEC 101 links commonly use a 1 byte cause
+/// of transmission, a 1 byte common address and a 2 byte information object
+/// address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AsduProfile {
+	/// Size of the cause of transmission: 1, or 2 to include the originator
+	/// address.
+	pub cot_size: u8,
+	/// Size of the common address: 1 or 2. The broadcast address is coded with
+	/// all the bits set whatever the size, and always decoded as
+	/// [`BROADCAST_ADDRESS`].
+	pub common_address_size: u8,
+	/// Size of the information object address: 1, 2 or 3.
+	pub ioa_size: u8,
+}
+
+impl AsduProfile {
+	/// The profile of IEC 60870-5-104.
+	pub const IEC_104: Self =
+		Self { cot_size: 2, common_address_size: 2, ioa_size: ADDRESS_SIZE as u8 };
+
+	/// Length of the data unit identifier: type, variable structure qualifier,
+	/// cause of transmission and common address.
+	#[must_use]
+	pub const fn header_length(&self) -> usize {
+		2 + self.cot_size as usize + self.common_address_size as usize
+	}
+
+	/// The maximum number of objects of the given type that fit in a single
+	/// ASDU when each object carries its own address.
+	#[must_use]
+	pub const fn max_objects(&self, type_id: TypeId) -> usize {
+		let max = (APUD_MAX_LENGTH as usize - CONTROL_FIELD_LENGTH - self.header_length())
+			/ (type_id.size() + self.ioa_size as usize);
+		if max < MAX_NUM_OBJECTS { max } else { MAX_NUM_OBJECTS }
+	}
+
+	fn validate(self) -> Result<(), AsduError> {
+		ensure!(
+			matches!(self.cot_size, 1 | 2)
+				&& matches!(self.common_address_size, 1 | 2)
+				&& matches!(self.ioa_size, 1..=3),
+			InvalidProfile { profile: self }
+		);
+		Ok(())
+	}
+}
+
+impl Default for AsduProfile {
+	fn default() -> Self {
+		Self::IEC_104
+	}
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Asdu {
 	pub type_id: TypeId,
@@ -16,14 +84,28 @@ pub struct Asdu {
 	pub address_field: u16,
 	pub sequence: bool,
 	pub test: bool,
+	/// The P/N bit. Despite the name, `true` means a negative confirmation.
 	pub positive: bool,
 	pub information_objects: InformationObjects,
 }
 
 impl Asdu {
-	#[instrument]
+	/// The maximum number of objects of the given type that fit in a single
+	/// ASDU when each object carries its own address. The IEC 104 field sizes
+	/// are the largest, so the result fits with every profile.
+	#[must_use]
+	pub const fn max_objects(type_id: TypeId) -> usize {
+		AsduProfile::IEC_104.max_objects(type_id)
+	}
+
 	pub fn parse(bytes: &[u8]) -> Result<Self, AsduError> {
+		Self::parse_with_profile(bytes, AsduProfile::IEC_104)
+	}
+
+	#[instrument]
+	pub fn parse_with_profile(bytes: &[u8], profile: AsduProfile) -> Result<Self, AsduError> {
 		tracing::trace!("Parsing ASDU: {:?}", bytes);
+		profile.validate()?;
 		let type_id: TypeId = (*bytes.first().context(NotEnoughBytes)?).into();
 
 		let byte = bytes.get(1).context(NotEnoughBytes)?;
@@ -35,34 +117,54 @@ impl Asdu {
 		let positive = byte & 0b0100_0000 != 0;
 		let cot = (byte & 0b0011_1111).try_into().context(InvalidCot)?;
 
-		let originator_address = *bytes.get(3).context(NotEnoughBytes)?;
+		let mut position = 3;
+		let originator_address = if profile.cot_size == 2 {
+			position += 1;
+			*bytes.get(3).context(NotEnoughBytes)?
+		} else {
+			0
+		};
 
-		let address_field = u16::from_le_bytes([
-			*bytes.get(4).context(NotEnoughBytes)?,
-			*bytes.get(5).context(NotEnoughBytes)?,
-		]);
+		let address_field = if profile.common_address_size == 2 {
+			position += 2;
+			u16::from_le_bytes([
+				*bytes.get(position - 2).context(NotEnoughBytes)?,
+				*bytes.get(position - 1).context(NotEnoughBytes)?,
+			])
+		} else {
+			position += 1;
+			match *bytes.get(position - 1).context(NotEnoughBytes)? {
+				u8::MAX => BROADCAST_ADDRESS,
+				address => address.into(),
+			}
+		};
 
-		let remaining_bytes = bytes.get(6..).context(NotEnoughBytes)?;
-		if type_id.is_standard() {
+		let remaining_bytes = bytes.get(position..).context(NotEnoughBytes)?;
+		if type_id.is_standard() && type_id.has_fixed_size() {
 			let object_size = type_id.size();
+			let ioa_size = profile.ioa_size as usize;
 			let remaining_bytes_size = remaining_bytes.len();
 
 			// Check if the remaining bytes are a multiple of the object size
 			// If it's a sequence we need to know the first address. So the first object has
-			// object_size + 3 bytes for the address. The subsequent chunks only
-			// have the object_size.
+			// object_size + ioa_size bytes. The subsequent chunks only have the
+			// object_size.
 			let is_multiple = if sequence {
-				(remaining_bytes_size - 3) % object_size != 0
+				remaining_bytes_size
+					.checked_sub(ioa_size)
+					.is_none_or(|size| size % object_size != 0)
 			} else {
-				remaining_bytes_size % (object_size + 3) != 0
+				remaining_bytes_size % (object_size + ioa_size) != 0
 			};
 
 			// Check if the number of objects is correct
 			// Here we have the same problem as above.
 			let num_objs_expected = if sequence {
-				(remaining_bytes_size - 3) / object_size != num_objs as usize
+				remaining_bytes_size
+					.checked_sub(ioa_size)
+					.is_none_or(|size| size / object_size != num_objs as usize)
 			} else {
-				remaining_bytes_size / (object_size + 3) != num_objs as usize
+				remaining_bytes_size / (object_size + ioa_size) != num_objs as usize
 			};
 
 			if is_multiple || num_objs_expected {
@@ -75,9 +177,14 @@ impl Asdu {
 			}
 		}
 
-		let information_objects =
-			InformationObjects::from_bytes(type_id, sequence, num_objs, remaining_bytes)
-				.context(InvalidInformationObject)?;
+		let information_objects = InformationObjects::from_bytes(
+			type_id,
+			sequence,
+			num_objs,
+			profile.ioa_size as usize,
+			remaining_bytes,
+		)
+		.context(InvalidInformationObject)?;
 
 		Ok(Self {
 			type_id,
@@ -91,9 +198,18 @@ impl Asdu {
 		})
 	}
 	pub fn to_bytes(&self, buffer: &mut Vec<u8>) -> Result<(), AsduError> {
+		self.to_bytes_with_profile(AsduProfile::IEC_104, buffer)
+	}
+
+	pub fn to_bytes_with_profile(
+		&self,
+		profile: AsduProfile,
+		buffer: &mut Vec<u8>,
+	) -> Result<(), AsduError> {
+		profile.validate()?;
 		buffer.push(self.type_id as u8);
 		let num_objs = self.information_objects.len();
-		if num_objs > 127 {
+		if num_objs > MAX_NUM_OBJECTS {
 			return TooManyObjects { num_objs }.fail();
 		}
 		let mut byte: u8 = num_objs as u8;
@@ -111,13 +227,24 @@ impl Asdu {
 		}
 		buffer.push(byte);
 
-		buffer.push(self.originator_address);
+		if profile.cot_size == 2 {
+			buffer.push(self.originator_address);
+		}
 
-		let address_field = self.address_field.to_le_bytes();
-		buffer.push(address_field[0]);
-		buffer.push(address_field[1]);
+		if profile.common_address_size == 2 {
+			buffer.extend_from_slice(&self.address_field.to_le_bytes());
+		} else if self.address_field == BROADCAST_ADDRESS {
+			buffer.push(u8::MAX);
+		} else {
+			let address = u8::try_from(self.address_field)
+				.ok()
+				.context(CommonAddressTooLarge { address: self.address_field })?;
+			buffer.push(address);
+		}
 
-		self.information_objects.to_bytes(buffer).context(InvalidInformationObject)?;
+		self.information_objects
+			.to_bytes_with_layout(self.sequence, profile.ioa_size as usize, buffer)
+			.context(InvalidInformationObject)?;
 		Ok(())
 	}
 }
@@ -156,6 +283,19 @@ pub enum AsduError {
 		context: Box<SpanTraceWrapper>,
 	},
 
+	#[snafu(display("Invalid ASDU profile {profile:?}"))]
+	InvalidProfile {
+		profile: AsduProfile,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Common address {address} doesn't fit in the profile"))]
+	CommonAddressTooLarge {
+		address: u16,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+
 	#[snafu(display("Not enough bytes"))]
 	NotEnoughBytes {
 		#[snafu(implicit)]
@@ -0,0 +1,106 @@
+//! Prints the APDUs of a pcap or pcapng capture, or of hex dumps read from
+//! stdin, as a tree of their fields.
+//!
+//! Usage: `iec104-dissect [--port <port>] [capture.pcapng]`
+//!
+//! Every line of the standard input is the hex dump of one or more APDUs. The
+//! bytes may be separated by spaces, colons or commas and prefixed by `0x`.
+//! Empty lines and lines starting with `#` are skipped.
+//!
+//! The TCP streams of a capture from or to port 2404, or the port given with
+//! `--port`, are reassembled, so that the APDUs split over several segments
+//! are dissected once complete.
+#![allow(clippy::print_stdout)]
+
+use std::{
+	fs::File,
+	io::{self, BufRead as _, BufReader},
+};
+
+use iec104::{
+	asdu::AsduProfile,
+	capture::{ApduStream, CaptureReader, Direction, IEC104_PORT},
+	dissect::{explain, hex},
+};
+use snafu::{OptionExt as _, ResultExt as _, Whatever, whatever};
+
+/// The command line arguments.
+const USAGE: &str = "Usage: iec104-dissect [--port <port>] [capture.pcapng]";
+
+fn main() -> Result<(), Whatever> {
+	let mut port = IEC104_PORT;
+	let mut path = None;
+	let mut args = std::env::args().skip(1);
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"-h" | "--help" => {
+				println!("{USAGE}");
+				println!("Dissects the hex dumps read from stdin when no capture is given");
+				println!("The controlled stations of a capture listen on port 2404 by default");
+				return Ok(());
+			}
+			"-p" | "--port" => {
+				let value = args.next().whatever_context(USAGE)?;
+				port = value.parse().with_whatever_context(|_| format!("Invalid port {value}"))?;
+			}
+			_ if path.is_none() => path = Some(arg),
+			_ => whatever!("{USAGE}"),
+		}
+	}
+	match path {
+		None => dissect_hex(),
+		Some(path) => dissect_capture(&path, port),
+	}
+}
+
+/// Dissects the hex dumps of the lines of the standard input.
+fn dissect_hex() -> Result<(), Whatever> {
+	for line in io::stdin().lock().lines() {
+		let line = line.whatever_context("Failed to read stdin")?;
+		let line = line.trim();
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+		match parse_hex(line) {
+			Some(bytes) => println!("{}\n", explain(&bytes, AsduProfile::IEC_104)),
+			None => println!("Invalid hex: {line}\n"),
+		}
+	}
+	Ok(())
+}
+
+/// The bytes of a hex dump, or `None` if it's not valid hex.
+fn parse_hex(line: &str) -> Option<Vec<u8>> {
+	let digits: String = line
+		.split([' ', '\t', ':', ','])
+		.map(|byte| byte.trim_start_matches("0x").trim_start_matches("0X"))
+		.collect();
+	if !digits.len().is_multiple_of(2) {
+		return None;
+	}
+	(0..digits.len())
+		.step_by(2)
+		.map(|i| u8::from_str_radix(digits.get(i..i + 2)?, 16).ok())
+		.collect()
+}
+
+/// Dissects the APDUs of the IEC 104 connections of a capture, whose
+/// controlled stations listen on the port.
+fn dissect_capture(path: &str, port: u16) -> Result<(), Whatever> {
+	let file = File::open(path).with_whatever_context(|_| format!("Failed to open {path}"))?;
+	let reader = CaptureReader::new(BufReader::new(file))
+		.with_whatever_context(|_| format!("Failed to read {path}"))?;
+	for captured in ApduStream::new(reader, port, AsduProfile::IEC_104) {
+		let captured = captured.whatever_context("Failed to read packet")?;
+		let (source, destination) = match captured.direction {
+			Direction::Control => (captured.client, captured.server),
+			Direction::Monitor => (captured.server, captured.client),
+		};
+		println!("{}, {source} -> {destination}", captured.timestamp);
+		match captured.apdu {
+			Ok(apdu) => println!("{apdu}\n"),
+			Err(e) => println!("Invalid APDU ({e}): {}\n", hex(&captured.bytes)),
+		}
+	}
+	Ok(())
+}
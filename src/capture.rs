@@ -0,0 +1,187 @@
+//! Packet captures of IEC 104 traffic: reading pcap and pcapng files,
+//! reassembling their TCP streams into APDUs and writing pcapng files.
+
+pub mod reader;
+pub mod stream;
+pub(crate) mod tap;
+pub mod writer;
+
+use std::{
+	io,
+	net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+};
+
+pub use reader::CaptureReader;
+use snafu::Snafu;
+pub use stream::{ApduStream, CapturedApdu};
+use time::OffsetDateTime;
+pub use writer::PcapngWriter;
+
+use crate::error::SpanTraceWrapper;
+
+/// The TCP port of IEC 104.
+pub const IEC104_PORT: u16 = 2404;
+
+/// The largest packet accepted, to not allocate whatever a corrupted capture
+/// says.
+const MAX_PACKET_LENGTH: usize = 256 * 1024;
+
+/// Link types of the captured packets, as assigned by tcpdump.org.
+const LINKTYPE_NULL: u32 = 0;
+const LINKTYPE_ETHERNET: u32 = 1;
+/// IPv4 or IPv6 packets, without link layer header.
+pub const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub), context(suffix(false)))]
+pub enum CaptureError {
+	#[snafu(display("Error reading the capture"))]
+	Io {
+		source: io::Error,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Not a pcap or pcapng capture: magic number {magic:#010x}"))]
+	InvalidMagic {
+		magic: u32,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("The capture ends in the middle of a record"))]
+	Truncated {
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Invalid {block_type:#x} block of {len} bytes"))]
+	InvalidBlock {
+		block_type: u32,
+		len: usize,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Packet of the unknown interface {interface}"))]
+	UnknownInterface {
+		interface: u32,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Error writing the capture"))]
+	Write {
+		source: io::Error,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Packet of {len} bytes is too long"))]
+	PacketTooLong {
+		len: usize,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+}
+
+/// A captured packet, from its link layer header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Packet {
+	pub timestamp: OffsetDateTime,
+	/// The link type of the interface the packet was captured on.
+	pub link_type: u32,
+	pub data: Vec<u8>,
+}
+
+/// The TCP segment carried by a packet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TcpSegment<'a> {
+	pub source: SocketAddr,
+	pub destination: SocketAddr,
+	pub sequence_number: u32,
+	/// Whether the SYN flag is set, the sequence number being the initial one
+	/// of the stream.
+	pub syn: bool,
+	pub payload: &'a [u8],
+}
+
+/// The direction of an APDU between the controlling station, the client, and
+/// the controlled station, the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+	/// From the controlling station to the controlled station.
+	Control,
+	/// From the controlled station to the controlling station.
+	Monitor,
+}
+
+impl Packet {
+	/// The TCP segment of the packet, if it's an unfragmented IPv4 or IPv6
+	/// packet over a supported link type (Ethernet, Linux cooked, raw IP or
+	/// BSD loopback) carrying TCP.
+	#[must_use]
+	pub fn tcp_segment(&self) -> Option<TcpSegment<'_>> {
+		let ip = match self.link_type {
+			LINKTYPE_ETHERNET => {
+				let mut ether_type = u16::from_be_bytes([*self.data.get(12)?, *self.data.get(13)?]);
+				let mut offset = 14;
+				// VLAN tags
+				while matches!(ether_type, 0x8100 | 0x88A8) {
+					ether_type = u16::from_be_bytes([
+						*self.data.get(offset + 2)?,
+						*self.data.get(offset + 3)?,
+					]);
+					offset += 4;
+				}
+				self.data.get(offset..)?
+			}
+			LINKTYPE_LINUX_SLL => self.data.get(16..)?,
+			LINKTYPE_NULL => self.data.get(4..)?,
+			LINKTYPE_RAW => &self.data,
+			_ => return None,
+		};
+
+		let (source, destination, tcp) = match ip.first()? >> 4 {
+			4 => {
+				let header_length = (ip[0] & 0x0F) as usize * 4;
+				let total_length = u16::from_be_bytes([*ip.get(2)?, *ip.get(3)?]) as usize;
+				let fragment = u16::from_be_bytes([*ip.get(6)?, *ip.get(7)?]);
+				// Only TCP, and neither the more fragments flag nor an offset
+				if *ip.get(9)? != 6 || fragment & 0x3FFF != 0 {
+					return None;
+				}
+				let source: [u8; 4] = ip.get(12..16)?.try_into().ok()?;
+				let destination: [u8; 4] = ip.get(16..20)?.try_into().ok()?;
+				// The link layer may pad short packets
+				(
+					IpAddr::V4(Ipv4Addr::from(source)),
+					IpAddr::V4(Ipv4Addr::from(destination)),
+					ip.get(header_length..total_length)?,
+				)
+			}
+			6 => {
+				let payload_length = u16::from_be_bytes([*ip.get(4)?, *ip.get(5)?]) as usize;
+				// Extension headers are not supported
+				if *ip.get(6)? != 6 {
+					return None;
+				}
+				let source: [u8; 16] = ip.get(8..24)?.try_into().ok()?;
+				let destination: [u8; 16] = ip.get(24..40)?.try_into().ok()?;
+				(
+					IpAddr::V6(Ipv6Addr::from(source)),
+					IpAddr::V6(Ipv6Addr::from(destination)),
+					ip.get(40..40 + payload_length)?,
+				)
+			}
+			_ => return None,
+		};
+
+		let source_port = u16::from_be_bytes([*tcp.first()?, *tcp.get(1)?]);
+		let destination_port = u16::from_be_bytes([*tcp.get(2)?, *tcp.get(3)?]);
+		let sequence_number = u32::from_be_bytes(tcp.get(4..8)?.try_into().ok()?);
+		let header_length = (tcp.get(12)? >> 4) as usize * 4;
+		Some(TcpSegment {
+			source: SocketAddr::new(source, source_port),
+			destination: SocketAddr::new(destination, destination_port),
+			sequence_number,
+			syn: tcp.get(13)? & 0b0000_0010 != 0,
+			payload: tcp.get(header_length..)?,
+		})
+	}
+}
@@ -0,0 +1,331 @@
+use std::io::{self, Read};
+
+use snafu::{IntoError as _, OptionExt as _, ResultExt as _, ensure};
+use time::OffsetDateTime;
+
+use crate::capture::{
+	CaptureError, InvalidBlock, InvalidMagic, Io, MAX_PACKET_LENGTH, Packet, PacketTooLong,
+	Truncated, UnknownInterface,
+};
+
+/// Block types of pcapng.
+const SECTION_HEADER_BLOCK: u32 = 0x0A0D_0D0A;
+const INTERFACE_DESCRIPTION_BLOCK: u32 = 1;
+const SIMPLE_PACKET_BLOCK: u32 = 3;
+const ENHANCED_PACKET_BLOCK: u32 = 6;
+/// The byte order magic of the section header block.
+const BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+/// The option of the interface description block giving the resolution of
+/// the timestamps.
+const IF_TSRESOL: u16 = 9;
+
+/// An interface of a pcapng capture.
+#[derive(Debug, Clone, Copy)]
+struct Interface {
+	link_type: u32,
+	/// The timestamp units per second.
+	units_per_second: u64,
+}
+
+#[derive(Debug)]
+enum Format {
+	Pcap { nanoseconds: bool, link_type: u32 },
+	Pcapng { interfaces: Vec<Interface> },
+}
+
+/// Reads the packets of a pcap or pcapng capture, as written by tcpdump,
+/// Wireshark or [`PcapngWriter`](crate::capture::PcapngWriter).
+///
+/// The format is recognized from the first bytes. The packet blocks of a
+/// pcapng capture are read whatever the interface, the other blocks are
+/// skipped.
+#[derive(Debug)]
+pub struct CaptureReader<R> {
+	reader: R,
+	/// Whether the capture, or its current section, is big endian.
+	big_endian: bool,
+	format: Format,
+}
+
+impl<R: Read> CaptureReader<R> {
+	/// Reads the header of the capture.
+	pub fn new(mut reader: R) -> Result<Self, CaptureError> {
+		let mut magic = [0; 4];
+		reader.read_exact(&mut magic).context(Io)?;
+		let mut capture =
+			Self { reader, big_endian: false, format: Format::Pcapng { interfaces: Vec::new() } };
+
+		let magic = u32::from_le_bytes(magic);
+		let (big_endian, nanoseconds) = match magic {
+			SECTION_HEADER_BLOCK => {
+				capture.read_section_header()?;
+				return Ok(capture);
+			}
+			0xA1B2_C3D4 => (false, false),
+			0xD4C3_B2A1 => (true, false),
+			0xA1B2_3C4D => (false, true),
+			0x4D3C_B2A1 => (true, true),
+			_ => return InvalidMagic { magic }.fail(),
+		};
+		let mut header = [0; 20];
+		capture.reader.read_exact(&mut header).context(Io)?;
+		capture.big_endian = big_endian;
+		capture.format = Format::Pcap { nanoseconds, link_type: capture.u32(&header[16..20]) };
+		Ok(capture)
+	}
+
+	fn u16(&self, bytes: &[u8]) -> u16 {
+		let bytes = [bytes[0], bytes[1]];
+		if self.big_endian { u16::from_be_bytes(bytes) } else { u16::from_le_bytes(bytes) }
+	}
+
+	fn u32(&self, bytes: &[u8]) -> u32 {
+		let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+		if self.big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) }
+	}
+
+	/// Fills the buffer, or returns `false` if the capture ends before it.
+	fn read_record_header(&mut self, header: &mut [u8]) -> Result<bool, CaptureError> {
+		let mut read = 0;
+		while read < header.len() {
+			match self.reader.read(&mut header[read..]).context(Io)? {
+				0 if read == 0 => return Ok(false),
+				0 => return Truncated.fail(),
+				n => read += n,
+			}
+		}
+		Ok(true)
+	}
+
+	fn read_exact(&mut self, len: usize) -> Result<Vec<u8>, CaptureError> {
+		let mut data = vec![0; len];
+		self.reader.read_exact(&mut data).map_err(|e| match e.kind() {
+			io::ErrorKind::UnexpectedEof => Truncated.build(),
+			_ => Io.into_error(e),
+		})?;
+		Ok(data)
+	}
+
+	fn read_pcap_packet(
+		&mut self,
+		nanoseconds: bool,
+		link_type: u32,
+	) -> Result<Option<Packet>, CaptureError> {
+		let mut header = [0; 16];
+		if !self.read_record_header(&mut header)? {
+			return Ok(None);
+		}
+
+		let seconds = self.u32(&header[0..4]);
+		let fraction = self.u32(&header[4..8]);
+		let len = self.u32(&header[8..12]) as usize;
+		ensure!(len <= MAX_PACKET_LENGTH, PacketTooLong { len });
+		let data = self.read_exact(len)?;
+
+		let units_per_second = if nanoseconds { 1_000_000_000 } else { 1_000_000 };
+		let timestamp = timestamp(
+			u64::from(seconds) * units_per_second + u64::from(fraction),
+			units_per_second,
+		);
+		Ok(Some(Packet { timestamp, link_type, data }))
+	}
+
+	/// Reads the rest of a section header block, once its type is read. The
+	/// interfaces of the previous section are forgotten.
+	fn read_section_header(&mut self) -> Result<(), CaptureError> {
+		let mut header = [0; 8];
+		self.reader.read_exact(&mut header).context(Io)?;
+		self.big_endian = match u32::from_le_bytes([header[4], header[5], header[6], header[7]]) {
+			BYTE_ORDER_MAGIC => false,
+			magic if magic == BYTE_ORDER_MAGIC.swap_bytes() => true,
+			magic => return InvalidMagic { magic }.fail(),
+		};
+		let len = self.u32(&header[0..4]) as usize;
+		ensure!(
+			(12..=MAX_PACKET_LENGTH).contains(&len) && len.is_multiple_of(4),
+			InvalidBlock { block_type: SECTION_HEADER_BLOCK, len }
+		);
+		self.read_exact(len - 12)?;
+		self.format = Format::Pcapng { interfaces: Vec::new() };
+		Ok(())
+	}
+
+	fn read_pcapng_packet(&mut self) -> Result<Option<Packet>, CaptureError> {
+		loop {
+			let mut header = [0; 4];
+			if !self.read_record_header(&mut header)? {
+				return Ok(None);
+			}
+			let block_type = self.u32(&header);
+			if block_type == SECTION_HEADER_BLOCK {
+				self.read_section_header()?;
+				continue;
+			}
+
+			let len = self.read_exact(4)?;
+			let len = self.u32(&len) as usize;
+			ensure!(
+				(12..=MAX_PACKET_LENGTH).contains(&len) && len.is_multiple_of(4),
+				InvalidBlock { block_type, len }
+			);
+			// The body and the repeated length
+			let block = self.read_exact(len - 8)?;
+			let body = &block[..len - 12];
+			let Format::Pcapng { interfaces } = &self.format else {
+				return Ok(None);
+			};
+
+			match block_type {
+				INTERFACE_DESCRIPTION_BLOCK => {
+					let interface =
+						self.interface(body).context(InvalidBlock { block_type, len })?;
+					if let Format::Pcapng { interfaces } = &mut self.format {
+						interfaces.push(interface);
+					}
+				}
+				ENHANCED_PACKET_BLOCK => {
+					let (Some(header), Some(data)) = (body.get(..20), body.get(20..)) else {
+						return InvalidBlock { block_type, len }.fail();
+					};
+					let interface = self.u32(&header[0..4]);
+					let Some(Interface { link_type, units_per_second }) =
+						interfaces.get(interface as usize).copied()
+					else {
+						return UnknownInterface { interface }.fail();
+					};
+					let units = (u64::from(self.u32(&header[4..8])) << 32)
+						| u64::from(self.u32(&header[8..12]));
+					let captured = self.u32(&header[12..16]) as usize;
+					let data = data.get(..captured).context(InvalidBlock { block_type, len })?;
+					return Ok(Some(Packet {
+						timestamp: timestamp(units, units_per_second),
+						link_type,
+						data: data.to_vec(),
+					}));
+				}
+				SIMPLE_PACKET_BLOCK => {
+					let (Some(header), Some(data)) = (body.get(..4), body.get(4..)) else {
+						return InvalidBlock { block_type, len }.fail();
+					};
+					let Some(Interface { link_type, .. }) = interfaces.first().copied() else {
+						return UnknownInterface { interface: 0_u32 }.fail();
+					};
+					// Captured up to the snapshot length, padded to 32 bits
+					let original = self.u32(header) as usize;
+					// Simple packet blocks carry no timestamp
+					return Ok(Some(Packet {
+						timestamp: OffsetDateTime::UNIX_EPOCH,
+						link_type,
+						data: data[..original.min(data.len())].to_vec(),
+					}));
+				}
+				_ => {}
+			}
+		}
+	}
+
+	/// Decodes the body of an interface description block.
+	fn interface(&self, body: &[u8]) -> Option<Interface> {
+		let link_type = u32::from(self.u16(body.get(0..2)?));
+		let mut units_per_second = 1_000_000;
+		let mut options = body.get(8..)?;
+		while let [code_0, code_1, len_0, len_1, rest @ ..] = options {
+			let code = self.u16(&[*code_0, *code_1]);
+			let len = self.u16(&[*len_0, *len_1]) as usize;
+			let value = rest.get(..len)?;
+			if code == IF_TSRESOL {
+				let resolution = *value.first()?;
+				units_per_second = if resolution & 0x80 == 0 {
+					10_u64.checked_pow(u32::from(resolution))?
+				} else {
+					1_u64.checked_shl(u32::from(resolution & 0x7F))?
+				};
+			}
+			options = rest.get(len.next_multiple_of(4)..)?;
+		}
+		Some(Interface { link_type, units_per_second })
+	}
+
+	fn read_packet(&mut self) -> Result<Option<Packet>, CaptureError> {
+		match self.format {
+			Format::Pcap { nanoseconds, link_type } => {
+				self.read_pcap_packet(nanoseconds, link_type)
+			}
+			Format::Pcapng { .. } => self.read_pcapng_packet(),
+		}
+	}
+}
+
+/// The time of a timestamp counted in units since the Unix epoch.
+fn timestamp(units: u64, units_per_second: u64) -> OffsetDateTime {
+	let nanoseconds = i128::from(units) * 1_000_000_000 / i128::from(units_per_second.max(1));
+	OffsetDateTime::from_unix_timestamp_nanos(nanoseconds).unwrap_or(OffsetDateTime::UNIX_EPOCH)
+}
+
+impl<R: Read> Iterator for CaptureReader<R> {
+	type Item = Result<Packet, CaptureError>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		self.read_packet().transpose()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::capture::{IEC104_PORT, LINKTYPE_ETHERNET};
+
+	/// An Ethernet frame carrying an IPv4 TCP segment from 10.0.0.1:2404 to
+	/// 10.0.0.2:50000, padded like short frames are.
+	fn ethernet_frame(payload: &[u8]) -> Vec<u8> {
+		let mut frame = vec![0; 12];
+		frame.extend_from_slice(&[0x08, 0x00]);
+		let total_length = (20 + 20 + payload.len()) as u16;
+		frame.extend_from_slice(&[0x45, 0, 0, 0, 0, 0, 0x40, 0, 64, 6, 0, 0]);
+		frame[16..18].copy_from_slice(&total_length.to_be_bytes());
+		frame.extend_from_slice(&[10, 0, 0, 1, 10, 0, 0, 2]);
+		frame.extend_from_slice(&IEC104_PORT.to_be_bytes());
+		frame.extend_from_slice(&50000_u16.to_be_bytes());
+		frame.extend_from_slice(&1000_u32.to_be_bytes());
+		frame.extend_from_slice(&[0, 0, 0, 0, 0x50, 0x18, 0, 0, 0, 0, 0, 0]);
+		frame.extend_from_slice(payload);
+		frame.resize(frame.len().max(60), 0);
+		frame
+	}
+
+	#[test]
+	fn test_pcap_reader() {
+		let payload = [0x68, 0x04, 0x0B, 0x00, 0x00, 0x00];
+		let frame = ethernet_frame(&payload);
+		let mut capture = Vec::new();
+		// Big endian, microsecond resolution
+		capture.extend_from_slice(&[0xA1, 0xB2, 0xC3, 0xD4, 0, 2, 0, 4]);
+		capture.extend_from_slice(&[0; 8]);
+		capture.extend_from_slice(&65535_u32.to_be_bytes());
+		capture.extend_from_slice(&LINKTYPE_ETHERNET.to_be_bytes());
+		capture.extend_from_slice(&1_700_000_000_u32.to_be_bytes());
+		capture.extend_from_slice(&250_000_u32.to_be_bytes());
+		capture.extend_from_slice(&(frame.len() as u32).to_be_bytes());
+		capture.extend_from_slice(&(frame.len() as u32).to_be_bytes());
+		capture.extend_from_slice(&frame);
+
+		let packets =
+			CaptureReader::new(capture.as_slice()).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+		assert_eq!(packets.len(), 1);
+		assert_eq!(
+			packets[0].timestamp,
+			OffsetDateTime::from_unix_timestamp_nanos(1_700_000_000_250_000_000).unwrap()
+		);
+		let segment = packets[0].tcp_segment().unwrap();
+		assert_eq!(segment.source, "10.0.0.1:2404".parse().unwrap());
+		assert_eq!(segment.destination, "10.0.0.2:50000".parse().unwrap());
+		assert_eq!(segment.sequence_number, 1000);
+		assert!(!segment.syn);
+		assert_eq!(segment.payload, payload);
+
+		// A record cut short
+		capture.truncate(capture.len() - 1);
+		let mut reader = CaptureReader::new(capture.as_slice()).unwrap();
+		assert!(matches!(reader.next(), Some(Err(CaptureError::Truncated { .. }))));
+	}
+}
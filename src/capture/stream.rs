@@ -0,0 +1,246 @@
+use std::{
+	collections::{HashMap, VecDeque},
+	net::SocketAddr,
+};
+
+use time::OffsetDateTime;
+
+use crate::{
+	apdu::{Apdu, TELEGRAN_HEADER},
+	asdu::AsduProfile,
+	capture::{CaptureError, Direction, Packet, TcpSegment},
+	error::Error,
+};
+
+/// How many segments received after a missing one are kept, before giving up
+/// on the missing one.
+const MAX_OUT_OF_ORDER_SEGMENTS: usize = 64;
+
+/// An APDU reassembled from a capture.
+#[derive(Debug)]
+pub struct CapturedApdu {
+	/// The time the last segment of the APDU was captured.
+	pub timestamp: OffsetDateTime,
+	pub direction: Direction,
+	/// The address of the controlling station.
+	pub client: SocketAddr,
+	/// The address of the controlled station.
+	pub server: SocketAddr,
+	/// The APDU as sent on the wire.
+	pub bytes: Vec<u8>,
+	pub apdu: Result<Apdu, Error>,
+}
+
+/// One direction of a TCP connection.
+#[derive(Debug, Default)]
+struct HalfStream {
+	/// The sequence number of the next byte of the stream, once known.
+	next_sequence_number: Option<u32>,
+	/// The bytes of the stream not split into APDUs yet.
+	buffer: Vec<u8>,
+	/// The segments received after a missing one, with their sequence number.
+	out_of_order: Vec<(u32, Vec<u8>)>,
+}
+
+impl HalfStream {
+	/// Adds the segment to the stream, dropping what was already received.
+	fn push(&mut self, segment: &TcpSegment<'_>) {
+		if segment.syn {
+			*self = Self {
+				next_sequence_number: Some(segment.sequence_number.wrapping_add(1)),
+				..Self::default()
+			};
+			return;
+		}
+		if segment.payload.is_empty() {
+			return;
+		}
+		// A capture started in the middle of the connection
+		self.next_sequence_number.get_or_insert(segment.sequence_number);
+		self.out_of_order.push((segment.sequence_number, segment.payload.to_vec()));
+
+		while let Some(next) = self.next_sequence_number {
+			// Sequence numbers wrap around, compare their distance
+			let in_order = self.out_of_order.iter().position(|(sequence_number, _)| {
+				next.wrapping_sub(*sequence_number).cast_signed() >= 0
+			});
+			if let Some(position) = in_order {
+				let (sequence_number, payload) = self.out_of_order.swap_remove(position);
+				let received = next.wrapping_sub(sequence_number) as usize;
+				if let Some(new) = payload.get(received..) {
+					self.buffer.extend_from_slice(new);
+					self.next_sequence_number = Some(next.wrapping_add(new.len() as u32));
+				}
+			} else if self.out_of_order.len() > MAX_OUT_OF_ORDER_SEGMENTS {
+				// The missing segment wasn't captured, continue after the gap
+				tracing::debug!("Skipping the bytes missing from the capture");
+				self.buffer.clear();
+				self.next_sequence_number = self
+					.out_of_order
+					.iter()
+					.map(|(sequence_number, _)| *sequence_number)
+					.min_by_key(|sequence_number| sequence_number.wrapping_sub(next));
+			} else {
+				return;
+			}
+		}
+	}
+
+	/// Takes the complete APDUs out of the stream. The bytes that don't start
+	/// an APDU, at the start of a capture or after a gap, are skipped.
+	fn apdus(&mut self) -> Vec<Vec<u8>> {
+		let mut apdus = Vec::new();
+		let mut start = 0;
+		loop {
+			match &self.buffer[start..] {
+				[TELEGRAN_HEADER, length, ..] => {
+					let end = start + *length as usize + 2;
+					if end > self.buffer.len() {
+						break;
+					}
+					apdus.push(self.buffer[start..end].to_vec());
+					start = end;
+				}
+				[] | [TELEGRAN_HEADER] => break,
+				rest => {
+					let skipped =
+						rest.iter().position(|byte| *byte == TELEGRAN_HEADER).unwrap_or(rest.len());
+					tracing::debug!("Skipping {skipped} bytes that don't start an APDU");
+					start += skipped;
+				}
+			}
+		}
+		self.buffer.drain(..start);
+		apdus
+	}
+}
+
+/// The APDUs of the IEC 104 connections of a capture.
+///
+/// The TCP streams from and to the port of the controlled station are
+/// reassembled: the retransmitted bytes are dropped and the segments received
+/// out of order are put back in order. The streams are then split into APDUs,
+/// decoded with the field sizes of the profile.
+///
+/// The captures started in the middle of a connection are synchronized on the
+/// first start byte, which might be found in the middle of an APDU: the first
+/// APDUs of such connections might be invalid.
+#[derive(Debug)]
+pub struct ApduStream<I> {
+	packets: I,
+	/// The port of the controlled station.
+	port: u16,
+	profile: AsduProfile,
+	/// The streams in control and monitor direction, by client and server
+	/// address.
+	connections: HashMap<(SocketAddr, SocketAddr), [HalfStream; 2]>,
+	ready: VecDeque<CapturedApdu>,
+}
+
+impl<I> ApduStream<I>
+where
+	I: Iterator<Item = Result<Packet, CaptureError>>,
+{
+	/// Reads the APDUs of the packets, usually of a
+	/// [`CaptureReader`](crate::capture::CaptureReader), exchanged with
+	/// `port`, usually [`IEC104_PORT`](crate::capture::IEC104_PORT).
+	pub fn new(packets: I, port: u16, profile: AsduProfile) -> Self {
+		Self { packets, port, profile, connections: HashMap::new(), ready: VecDeque::new() }
+	}
+
+	fn push(&mut self, packet: &Packet) {
+		let Some(segment) = packet.tcp_segment() else {
+			return;
+		};
+		let (direction, client, server) = if segment.destination.port() == self.port {
+			(Direction::Control, segment.source, segment.destination)
+		} else if segment.source.port() == self.port {
+			(Direction::Monitor, segment.destination, segment.source)
+		} else {
+			return;
+		};
+
+		let stream = &mut self.connections.entry((client, server)).or_default()[direction as usize];
+		stream.push(&segment);
+		for bytes in stream.apdus() {
+			self.ready.push_back(CapturedApdu {
+				timestamp: packet.timestamp,
+				direction,
+				client,
+				server,
+				apdu: Apdu::from_bytes_with_profile(&bytes, self.profile),
+				bytes,
+			});
+		}
+	}
+}
+
+impl<I> Iterator for ApduStream<I>
+where
+	I: Iterator<Item = Result<Packet, CaptureError>>,
+{
+	type Item = Result<CapturedApdu, CaptureError>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		loop {
+			if let Some(apdu) = self.ready.pop_front() {
+				return Some(Ok(apdu));
+			}
+			match self.packets.next()? {
+				Ok(packet) => self.push(&packet),
+				Err(e) => return Some(Err(e)),
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		apdu::Frame,
+		capture::{CaptureReader, IEC104_PORT, LINKTYPE_RAW, PcapngWriter, tap::tcp_packet},
+	};
+
+	#[test]
+	fn test_apdu_stream() {
+		let client: SocketAddr = "10.0.0.2:50000".parse().unwrap();
+		let server: SocketAddr = SocketAddr::new("10.0.0.1".parse().unwrap(), IEC104_PORT);
+		let start_dt_act = [0x68, 0x04, 0x07, 0x00, 0x00, 0x00];
+		let start_dt_con = [0x68, 0x04, 0x0B, 0x00, 0x00, 0x00];
+		let test_fr_act = [0x68, 0x04, 0x43, 0x00, 0x00, 0x00];
+		let retransmitted = [start_dt_act.as_slice(), &test_fr_act[..3]].concat();
+
+		let mut writer = PcapngWriter::new(Vec::new(), LINKTYPE_RAW as u16).unwrap();
+		for (source, destination, sequence_number, payload) in [
+			(client, server, 100, start_dt_act.as_slice()),
+			(server, client, 500, &start_dt_con),
+			// The end of the test frame before its start, retransmitted with the
+			// acknowledged StartDT activation
+			(client, server, 109, &test_fr_act[3..]),
+			(client, server, 100, &retransmitted),
+		] {
+			let packet = tcp_packet(source, destination, sequence_number, 0, payload);
+			writer.write_packet(OffsetDateTime::UNIX_EPOCH, &packet).unwrap();
+		}
+		let capture = writer.into_inner();
+
+		let reader = CaptureReader::new(capture.as_slice()).unwrap();
+		let apdus = ApduStream::new(reader, IEC104_PORT, AsduProfile::IEC_104)
+			.collect::<Result<Vec<_>, _>>()
+			.unwrap();
+		assert_eq!(
+			apdus.iter().map(|apdu| (apdu.direction, apdu.bytes.as_slice())).collect::<Vec<_>>(),
+			[
+				(Direction::Control, start_dt_act.as_slice()),
+				(Direction::Monitor, &start_dt_con),
+				(Direction::Control, &test_fr_act),
+			]
+		);
+		assert!(apdus.iter().all(|apdu| apdu.client == client && apdu.server == server));
+		assert!(matches!(
+			&apdus[2].apdu,
+			Ok(Apdu { frame: Frame::U(u), .. }) if u.test_fr_activation
+		));
+	}
+}
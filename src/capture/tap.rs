@@ -0,0 +1,369 @@
+use std::{
+	collections::HashMap,
+	fs::File,
+	io::BufWriter,
+	net::{IpAddr, SocketAddr},
+	path::{Path, PathBuf},
+	sync::{
+		Arc, LazyLock, Mutex, PoisonError,
+		atomic::{AtomicU32, AtomicU64, Ordering},
+	},
+};
+
+use snafu::ResultExt as _;
+use time::OffsetDateTime;
+use tokio::sync::mpsc;
+
+use crate::capture::{CaptureError, LINKTYPE_RAW, PcapngWriter, Write};
+
+/// A packet to write, with when it was captured.
+type Captured = (OffsetDateTime, Vec<u8>);
+
+/// How many packets can wait for the writer thread. The packets captured while
+/// it's full are dropped, so that a slow disk doesn't hold the connections
+/// back nor fill the memory.
+const CAPACITY: usize = 4096;
+
+/// The queue of the writer of an open capture, with its count of dropped
+/// packets.
+type OpenCapture = (mpsc::WeakSender<Captured>, Arc<AtomicU64>);
+
+/// The captures open in the process, by path, so that the clients and servers
+/// configured with the same one share it instead of replacing it.
+static TAPS: LazyLock<Mutex<HashMap<PathBuf, OpenCapture>>> = LazyLock::new(Mutex::default);
+
+/// A pcapng capture the APDUs of the connections are written to, as if they
+/// were captured on the wire.
+///
+/// The packets are written by a thread of the capture, flushing it whenever
+/// there is no packet left to write. The thread stops once every tap of the
+/// capture is dropped.
+#[derive(Debug, Clone)]
+pub(crate) struct Tap {
+	packets: mpsc::Sender<Captured>,
+	/// The packets dropped because the writer couldn't keep up, since they
+	/// were last reported.
+	dropped: Arc<AtomicU64>,
+}
+
+impl Tap {
+	/// Opens the capture of the path. It's created, replacing the file if it
+	/// exists, unless it's already open in the process.
+	pub fn open(path: &Path) -> Result<Self, CaptureError> {
+		let mut taps = TAPS.lock().unwrap_or_else(PoisonError::into_inner);
+		if let Some((packets, dropped)) = taps.get(path)
+			&& let Some(packets) = packets.upgrade()
+		{
+			return Ok(Self { packets, dropped: dropped.clone() });
+		}
+
+		let file = File::create(path).context(Write)?;
+		let writer = PcapngWriter::new(BufWriter::new(file), LINKTYPE_RAW as u16)?;
+		let (packets, packets_rx) = mpsc::channel(CAPACITY);
+		let dropped = Arc::new(AtomicU64::new(0));
+		let writer_dropped = dropped.clone();
+		std::thread::Builder::new()
+			.name("iec104-capture".to_owned())
+			.spawn(move || write_packets(writer, packets_rx, &writer_dropped))
+			.context(Write)?;
+		taps.retain(|_, (packets, _)| packets.strong_count() > 0);
+		taps.insert(path.to_owned(), (packets.downgrade(), dropped.clone()));
+		Ok(Self { packets, dropped })
+	}
+
+	/// Queues the packet for the writer, or drops it if the writer is behind.
+	fn write(&self, packet: Vec<u8>) {
+		match self.packets.try_send((OffsetDateTime::now_utc(), packet)) {
+			// Reported once per backlog, the writer reports how many once it's caught up
+			Err(mpsc::error::TrySendError::Full(_)) => {
+				if self.dropped.fetch_add(1, Ordering::Relaxed) == 0 {
+					tracing::warn!("The capture can't keep up, dropping packets");
+				}
+			}
+			// The writer only stops once every tap is dropped
+			Ok(()) | Err(mpsc::error::TrySendError::Closed(_)) => (),
+		}
+	}
+
+	/// Taps a connection between the two addresses.
+	pub fn connection(&self, local: SocketAddr, peer: SocketAddr) -> ConnectionTap {
+		ConnectionTap {
+			tap: self.clone(),
+			local,
+			peer,
+			sent: AtomicU32::new(0),
+			received: AtomicU32::new(0),
+		}
+	}
+}
+
+/// Writes the packets until every sender is dropped. The errors and the
+/// dropped packets are only logged, the connections go on without being
+/// captured.
+fn write_packets(
+	mut writer: PcapngWriter<BufWriter<File>>,
+	mut packets: mpsc::Receiver<Captured>,
+	dropped: &AtomicU64,
+) {
+	let mut next = packets.blocking_recv();
+	while let Some((timestamp, packet)) = next {
+		if let Err(e) = writer.write_packet(timestamp, &packet) {
+			tracing::warn!("Error writing the capture: {e}");
+		}
+		next = packets.try_recv().ok();
+		if next.is_none() {
+			// Complete whenever it's read while the connections are idle
+			if let Err(e) = writer.flush() {
+				tracing::warn!("Error writing the capture: {e}");
+			}
+			let dropped = dropped.swap(0, Ordering::Relaxed);
+			if dropped > 0 {
+				tracing::warn!("{dropped} packets were dropped from the capture");
+			}
+			next = packets.blocking_recv();
+		}
+	}
+}
+
+/// Writes the APDUs of a connection to the capture, in TCP segments carrying
+/// a single APDU each.
+#[derive(Debug)]
+pub(crate) struct ConnectionTap {
+	tap: Tap,
+	local: SocketAddr,
+	peer: SocketAddr,
+	/// The sequence numbers of the next bytes sent and received.
+	sent: AtomicU32,
+	received: AtomicU32,
+}
+
+impl ConnectionTap {
+	pub fn sent(&self, apdu: &[u8]) {
+		let sequence_number = self.sent.fetch_add(apdu.len() as u32, Ordering::Relaxed);
+		let acknowledgement_number = self.received.load(Ordering::Relaxed);
+		self.write(tcp_packet(
+			self.local,
+			self.peer,
+			sequence_number,
+			acknowledgement_number,
+			apdu,
+		));
+	}
+
+	pub fn received(&self, apdu: &[u8]) {
+		let sequence_number = self.received.fetch_add(apdu.len() as u32, Ordering::Relaxed);
+		let acknowledgement_number = self.sent.load(Ordering::Relaxed);
+		self.write(tcp_packet(
+			self.peer,
+			self.local,
+			sequence_number,
+			acknowledgement_number,
+			apdu,
+		));
+	}
+
+	fn write(&self, packet: Vec<u8>) {
+		self.tap.write(packet);
+	}
+}
+
+/// An IP packet carrying a TCP segment with the PSH and ACK flags. The
+/// checksums are left to 0.
+pub(super) fn tcp_packet(
+	source: SocketAddr,
+	destination: SocketAddr,
+	sequence_number: u32,
+	acknowledgement_number: u32,
+	payload: &[u8],
+) -> Vec<u8> {
+	let mut packet = Vec::with_capacity(60 + payload.len());
+	let tcp_length = 20 + payload.len() as u16;
+	match (source.ip(), destination.ip()) {
+		(IpAddr::V4(source), IpAddr::V4(destination)) => {
+			packet.extend_from_slice(&[0x45, 0]);
+			packet.extend_from_slice(&(20 + tcp_length).to_be_bytes());
+			// Don't fragment, TTL of 64 and TCP
+			packet.extend_from_slice(&[0, 0, 0x40, 0, 64, 6, 0, 0]);
+			packet.extend_from_slice(&source.octets());
+			packet.extend_from_slice(&destination.octets());
+		}
+		(source, destination) => {
+			packet.extend_from_slice(&[0x60, 0, 0, 0]);
+			packet.extend_from_slice(&tcp_length.to_be_bytes());
+			// TCP and hop limit of 64
+			packet.extend_from_slice(&[6, 64]);
+			packet.extend_from_slice(&to_ipv6(source).octets());
+			packet.extend_from_slice(&to_ipv6(destination).octets());
+		}
+	}
+	packet.extend_from_slice(&source.port().to_be_bytes());
+	packet.extend_from_slice(&destination.port().to_be_bytes());
+	packet.extend_from_slice(&sequence_number.to_be_bytes());
+	packet.extend_from_slice(&acknowledgement_number.to_be_bytes());
+	// 20 bytes header, PSH and ACK, then the window, checksum and urgent pointer
+	packet.extend_from_slice(&[0x50, 0x18, 0xFF, 0xFF, 0, 0, 0, 0]);
+	packet.extend_from_slice(payload);
+	packet
+}
+
+const fn to_ipv6(ip: IpAddr) -> std::net::Ipv6Addr {
+	match ip {
+		IpAddr::V4(ip) => ip.to_ipv6_mapped(),
+		IpAddr::V6(ip) => ip,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{collections::HashSet, time::Duration};
+
+	use tokio::sync::mpsc;
+
+	use super::*;
+	use crate::{
+		apdu::{Apdu, Frame},
+		asdu::Asdu,
+		capture::{ApduStream, CaptureReader, CapturedApdu, Direction},
+		config::{ClientConfig, ProtocolConfig, ServerConfig},
+		cot::Cot,
+		test_util::{Commands, connect_client, server_config, start_server},
+		types::{GenericObject, InformationObjects, MSpNa1},
+		types_id::TypeId,
+	};
+
+	fn capture_path(name: &str) -> PathBuf {
+		std::env::temp_dir().join(format!("iec104-{name}-{}.pcapng", std::process::id()))
+	}
+
+	fn capture_config(path: &Path) -> ProtocolConfig {
+		ProtocolConfig { capture: Some(path.to_owned()), ..Default::default() }
+	}
+
+	/// Reads the APDUs of the connections to the port, once the capture
+	/// satisfies the condition since the packets are written in the
+	/// background.
+	async fn wait_captured(
+		path: &Path,
+		port: u16,
+		condition: impl Fn(&[CapturedApdu]) -> bool + Sync,
+	) -> Vec<CapturedApdu> {
+		tokio::time::timeout(Duration::from_secs(5), async {
+			loop {
+				let apdus = File::open(path)
+					.ok()
+					.and_then(|file| CaptureReader::new(file).ok())
+					.and_then(|reader| {
+						ApduStream::new(reader, port, ProtocolConfig::default().asdu_profile)
+							.collect::<Result<Vec<_>, _>>()
+							.ok()
+					});
+				if let Some(apdus) = apdus
+					&& condition(&apdus)
+				{
+					break apdus;
+				}
+				tokio::time::sleep(Duration::from_millis(10)).await;
+			}
+		})
+		.await
+		.unwrap()
+	}
+
+	#[tokio::test]
+	async fn test_capture() {
+		let server_capture = capture_path("server");
+		let client_capture = capture_path("client");
+		let (command_tx, _command_rx) = mpsc::unbounded_channel();
+		let server = start_server(
+			ServerConfig { protocol: capture_config(&server_capture), ..server_config() },
+			Commands(command_tx),
+		)
+		.await;
+		let port = server.local_addr().unwrap().port();
+		let (_client, mut object_rx) = connect_client(
+			port,
+			ClientConfig { protocol: capture_config(&client_capture), ..Default::default() },
+		)
+		.await;
+
+		let spontaneous = Asdu {
+			type_id: TypeId::M_SP_NA_1,
+			cot: Cot::SpontaneousData,
+			originator_address: 0,
+			address_field: 1,
+			sequence: false,
+			test: false,
+			positive: false,
+			information_objects: InformationObjects::MSpNa1(vec![GenericObject {
+				address: 200,
+				object: MSpNa1::default(),
+			}]),
+		};
+		server.send_asdu(spontaneous.clone()).unwrap();
+		assert_eq!(object_rx.recv().await.unwrap(), spontaneous);
+
+		// Both ends capture the same exchange
+		for path in [&server_capture, &client_capture] {
+			let apdus = wait_captured(path, port, |apdus| {
+				apdus.iter().any(|captured| {
+					captured.direction == Direction::Monitor
+						&& matches!(&captured.apdu, Ok(Apdu { frame: Frame::I(i), .. }) if i.asdu == spontaneous)
+				})
+			})
+			.await;
+			assert!(matches!(
+				&apdus[..2],
+				[
+					CapturedApdu {
+						direction: Direction::Control,
+						apdu: Ok(Apdu { frame: Frame::U(start), .. }),
+						..
+					},
+					CapturedApdu {
+						direction: Direction::Monitor,
+						apdu: Ok(Apdu { frame: Frame::U(confirmation), .. }),
+						..
+					},
+				] if start.start_dt_activation && confirmation.start_dt_confirmation
+			));
+			std::fs::remove_file(path).unwrap();
+		}
+	}
+
+	#[tokio::test]
+	async fn test_shared_capture() {
+		let path = capture_path("shared");
+		let (command_tx, _command_rx) = mpsc::unbounded_channel();
+		let server = start_server(server_config(), Commands(command_tx)).await;
+		let port = server.local_addr().unwrap().port();
+
+		// The second client doesn't replace the capture of the first one
+		let config = ClientConfig { protocol: capture_config(&path), ..Default::default() };
+		let (first, _) = connect_client(port, config.clone()).await;
+		let (second, _) = connect_client(port, config).await;
+		let apdus = wait_captured(&path, port, |apdus| {
+			apdus.iter().map(|captured| captured.client).collect::<HashSet<_>>().len() == 2
+		})
+		.await;
+		assert!(apdus.iter().all(|captured| captured.apdu.is_ok()));
+
+		drop((first, second));
+		std::fs::remove_file(&path).unwrap();
+	}
+
+	#[test]
+	fn test_dropped_packets() {
+		let (packets, mut packets_rx) = mpsc::channel(2);
+		let tap = Tap { packets, dropped: Arc::new(AtomicU64::new(0)) };
+		let connection =
+			tap.connection("127.0.0.1:2404".parse().unwrap(), "127.0.0.1:50000".parse().unwrap());
+
+		// The writer is behind, the packets that don't fit are counted
+		for _ in 0..5 {
+			connection.sent(&[0x68, 4, 0x43, 0, 0, 0]);
+		}
+		assert_eq!(tap.dropped.load(Ordering::Relaxed), 3);
+		assert!(packets_rx.try_recv().is_ok() && packets_rx.try_recv().is_ok());
+		assert!(packets_rx.try_recv().is_err());
+	}
+}
@@ -0,0 +1,79 @@
+use std::io::Write;
+
+use snafu::{ResultExt as _, ensure};
+use time::OffsetDateTime;
+
+use crate::capture::{CaptureError, MAX_PACKET_LENGTH, PacketTooLong, Write as WriteError};
+
+/// Writes the packets of a single interface to a pcapng capture.
+///
+/// The timestamps are written with a nanosecond resolution. Every packet is
+/// written as soon as it's given, wrap the writer in a
+/// [`BufWriter`](std::io::BufWriter) to group the writes.
+#[derive(Debug)]
+pub struct PcapngWriter<W: Write> {
+	writer: W,
+}
+
+impl<W: Write> PcapngWriter<W> {
+	/// Writes the section header and the description of the interface with
+	/// the given link type.
+	pub fn new(writer: W, link_type: u16) -> Result<Self, CaptureError> {
+		let mut capture = Self { writer };
+
+		// Byte order magic, version 1.0 and unknown section length
+		let mut section_header = 0x1A2B_3C4D_u32.to_le_bytes().to_vec();
+		section_header.extend_from_slice(&[1, 0, 0, 0]);
+		section_header.extend_from_slice(&u64::MAX.to_le_bytes());
+		capture.write_block(0x0A0D_0D0A, &section_header)?;
+
+		// No snapshot length, then the if_tsresol option for nanoseconds and the end
+		// of the options
+		let mut interface = link_type.to_le_bytes().to_vec();
+		interface.extend_from_slice(&[0; 6]);
+		interface.extend_from_slice(&[9, 0, 1, 0, 9, 0, 0, 0]);
+		interface.extend_from_slice(&[0; 4]);
+		capture.write_block(1, &interface)?;
+		Ok(capture)
+	}
+
+	/// Writes a packet, from its link layer header.
+	pub fn write_packet(
+		&mut self,
+		timestamp: OffsetDateTime,
+		data: &[u8],
+	) -> Result<(), CaptureError> {
+		ensure!(data.len() <= MAX_PACKET_LENGTH, PacketTooLong { len: data.len() });
+		let nanoseconds = u64::try_from(timestamp.unix_timestamp_nanos()).unwrap_or_default();
+		let len = (data.len() as u32).to_le_bytes();
+
+		let mut block = Vec::with_capacity(20 + data.len().next_multiple_of(4));
+		block.extend_from_slice(&0_u32.to_le_bytes());
+		block.extend_from_slice(&((nanoseconds >> 32) as u32).to_le_bytes());
+		block.extend_from_slice(&(nanoseconds as u32).to_le_bytes());
+		block.extend_from_slice(&len);
+		block.extend_from_slice(&len);
+		block.extend_from_slice(data);
+		block.resize(block.len().next_multiple_of(4), 0);
+		self.write_block(6, &block)
+	}
+
+	/// Writes a block with a body padded to 32 bits.
+	fn write_block(&mut self, block_type: u32, body: &[u8]) -> Result<(), CaptureError> {
+		let len = (body.len() as u32 + 12).to_le_bytes();
+		self.writer.write_all(&block_type.to_le_bytes()).context(WriteError)?;
+		self.writer.write_all(&len).context(WriteError)?;
+		self.writer.write_all(body).context(WriteError)?;
+		self.writer.write_all(&len).context(WriteError)
+	}
+
+	/// Flushes the underlying writer.
+	pub fn flush(&mut self) -> Result<(), CaptureError> {
+		self.writer.flush().context(WriteError)
+	}
+
+	/// The underlying writer.
+	pub fn into_inner(self) -> W {
+		self.writer
+	}
+}
@@ -1,68 +1,93 @@
-use std::{
-	fmt::Debug,
-	pin::Pin,
-	sync::{Arc, atomic::AtomicBool},
-};
+use std::{fmt::Debug, pin::Pin, sync::Arc};
 
 use async_trait::async_trait;
 use lazy_static::lazy_static;
-use snafu::{ResultExt, whatever};
+use snafu::{OptionExt as _, ResultExt, ensure, whatever};
+use time::OffsetDateTime;
 use tokio::{
 	io::{AsyncRead, AsyncWrite},
 	net::TcpStream,
-	sync::mpsc,
+	sync::{mpsc, oneshot, watch},
 	task::JoinHandle,
+	time::Instant,
 };
 use tokio_native_tls::TlsStream;
 use tracing::instrument;
 
 use crate::{
 	apdu::{Frame, UFrame},
-	asdu::Asdu,
+	asdu::{Asdu, BROADCAST_ADDRESS},
+	capture::tap::Tap,
 	client::{
+		command::{ClockSynchronization, CommandHandle, SelectedCommand},
 		connection_handler::{ConnectionHandler, ConnectionHandlerState},
+		dispatcher::Dispatcher,
 		errors::ClientError,
+		send_queue::{Priority, SendQueue, send_queue},
+		state::ConnectionStatus,
+		subscription::{Subscription, SubscriptionFilter},
 	},
 	config::ClientConfig,
 	cot::Cot,
 	error::Error,
+	point::PointUpdate,
 	types::{
-		CBoNa1, CBoTa1, CScNa1, CScTa1, CdcNa1, CdcTa1, CrcNa1, CrcTa1, GenericObject,
-		InformationObjects,
-		commands::{Dco, Qu, Rco, Rcs, Sco},
+		CBoNa1, CBoTa1, CCiNa1, CCsNa1, CIcNa1, CScNa1, CScTa1, CSeNa1, CSeNb1, CSeNc1, CSeTa1,
+		CSeTb1, CSeTc1, CdcNa1, CdcTa1, CrcNa1, CrcTa1, GenericObject, InformationObjects,
+		commands::{Dco, Frz, Qoi, Qu, Rco, Rcs, Rqt, Sco},
 		information_elements::{Dpi, SelectExecute, Spi},
-		time::Cp56Time2a,
+		quality_descriptors::Qos,
+		time::{Cp56Time2a, TimeZone},
 	},
 	types_id::TypeId,
 };
 
-mod connection_handler;
+pub mod command;
+pub(crate) mod connection_handler;
+mod dispatcher;
 pub mod errors;
-mod receive_handler;
+mod file_transfer;
+pub mod pool;
+pub(crate) mod receive_handler;
+mod redundancy;
+pub mod send_queue;
+pub mod state;
+pub mod subscription;
 
 use connection_handler::{AtomicConnectionHandlerState, ConnectionHandlerCommand};
 
 lazy_static! {
-	static ref TEST_FR_CON_FRAME: Frame =
+	pub(crate) static ref TEST_FR_CON_FRAME: Frame =
 		Frame::U(UFrame { test_fr_confirmation: true, ..Default::default() });
-	static ref START_DT_CON_FRAME: Frame =
+	pub(crate) static ref START_DT_CON_FRAME: Frame =
 		Frame::U(UFrame { start_dt_confirmation: true, ..Default::default() });
-	static ref STOP_DT_CON_FRAME: Frame =
+	pub(crate) static ref STOP_DT_CON_FRAME: Frame =
 		Frame::U(UFrame { stop_dt_confirmation: true, ..Default::default() });
-	static ref TEST_FR_ACT_FRAME: Frame =
+	pub(crate) static ref TEST_FR_ACT_FRAME: Frame =
 		Frame::U(UFrame { test_fr_activation: true, ..Default::default() });
-	static ref START_DT_ACT_FRAME: Frame =
+	pub(crate) static ref START_DT_ACT_FRAME: Frame =
 		Frame::U(UFrame { start_dt_activation: true, ..Default::default() });
-	static ref STOP_DT_ACT_FRAME: Frame =
+	pub(crate) static ref STOP_DT_ACT_FRAME: Frame =
 		Frame::U(UFrame { stop_dt_activation: true, ..Default::default() });
 }
 
 #[derive(Debug)]
-enum Connection {
+pub(crate) enum Connection {
 	Tcp(TcpStream),
 	Tls(TlsStream<TcpStream>),
 }
 
+impl Connection {
+	/// The local and peer addresses of the connection.
+	pub fn addresses(&self) -> std::io::Result<(std::net::SocketAddr, std::net::SocketAddr)> {
+		let stream = match self {
+			Self::Tcp(stream) => stream,
+			Self::Tls(stream) => stream.get_ref().get_ref().get_ref(),
+		};
+		Ok((stream.local_addr()?, stream.peer_addr()?))
+	}
+}
+
 impl AsyncRead for Connection {
 	fn poll_read(
 		self: Pin<&mut Self>,
@@ -112,44 +137,114 @@ impl AsyncWrite for Connection {
 #[async_trait]
 pub trait OnNewObjects {
 	async fn on_new_objects(&self, asdu: Asdu);
+
+	/// Called instead of [`OnNewObjects::on_new_objects`] when
+	/// [`ClientConfig::cp24_reference`] is set, with the expanded CP24Time2a
+	/// time tag of every object, in order. `None` for the objects without a
+	/// CP24Time2a time tag or with an invalid one.
+	///
+	/// Ignores the times by default.
+	async fn on_new_objects_with_times(&self, asdu: Asdu, times: Vec<Option<OffsetDateTime>>) {
+		let _ = times;
+		self.on_new_objects(asdu).await;
+	}
+}
+
+#[async_trait]
+pub trait OnPointUpdates {
+	/// Called with the points updated by every received monitoring ASDU.
+	async fn on_point_updates(&self, updates: Vec<PointUpdate>);
+}
+
+/// Adapts an [`OnPointUpdates`] callback to be used as the callback of the
+/// client, decoding every received ASDU into point updates.
+///
+/// The CP24Time2a time tags are expanded with
+/// [`ClientConfig::cp24_reference`], or with the reception time if it's not
+/// set. The ASDUs without point updates (confirmations, file transfer, ...)
+/// are dropped.
+#[derive(Debug)]
+pub struct PointUpdates<T>(pub T);
+
+#[async_trait]
+impl<T: OnPointUpdates + Send + Sync> OnNewObjects for PointUpdates<T> {
+	async fn on_new_objects(&self, asdu: Asdu) {
+		let updates = PointUpdate::from_asdu(&asdu, OffsetDateTime::now_utc());
+		if !updates.is_empty() {
+			self.0.on_point_updates(updates).await;
+		}
+	}
+
+	async fn on_new_objects_with_times(&self, asdu: Asdu, times: Vec<Option<OffsetDateTime>>) {
+		let mut updates = PointUpdate::from_asdu(&asdu, OffsetDateTime::now_utc());
+		// Only the CP24Time2a time tags are expanded, keep the others
+		for (update, time) in updates.iter_mut().zip(times) {
+			if time.is_some() {
+				update.timestamp = time;
+			}
+		}
+		if !updates.is_empty() {
+			self.0.on_point_updates(updates).await;
+		}
+	}
 }
 
 pub struct Client {
 	config: ClientConfig,
-	callback: Arc<dyn OnNewObjects + Send + Sync>,
+	dispatcher: Arc<Dispatcher>,
 	receive_task: Option<JoinHandle<Result<(), Error>>>,
 	write_tx: Option<mpsc::Sender<ConnectionHandlerCommand>>,
-	out_buffer_full: Arc<AtomicBool>,
+	send_queue: Option<SendQueue>,
 	connection_handler_state: Option<Arc<AtomicConnectionHandlerState>>,
+	status: Arc<watch::Sender<ConnectionStatus>>,
+	/// The capture of the connections, opened on the first connection so that
+	/// the next ones are added to it.
+	tap: Option<Tap>,
 }
 
 impl Client {
 	#[must_use]
 	pub fn new(config: ClientConfig, callback: impl OnNewObjects + Send + Sync + 'static) -> Self {
 		Self {
+			dispatcher: Arc::new(Dispatcher::new(Arc::new(callback), config.cp24_reference)),
 			config,
-			callback: Arc::new(callback),
 			receive_task: None,
 			write_tx: None,
-			out_buffer_full: Arc::new(AtomicBool::new(false)),
+			send_queue: None,
 			connection_handler_state: None,
+			status: Arc::new(watch::Sender::new(ConnectionStatus::default())),
+			tap: None,
 		}
 	}
 
 	#[instrument(level = "debug")]
 	pub async fn connect(&mut self) -> Result<(), Error> {
-		if self.receive_task.is_some() {
+		// The task stops when the reconnection is given up
+		if self.receive_task.as_ref().is_some_and(|task| !task.is_finished()) {
 			whatever!("Receive task already running");
 		}
 
 		let (tx, rx) = mpsc::channel(1024);
+		let (queue, queue_rx) = send_queue(usize::from(self.config.send_queue_capacity));
 
-		let callback = self.callback.clone();
+		if self.tap.is_none()
+			&& let Some(path) = &self.config.protocol.capture
+		{
+			self.tap = Some(Tap::open(path).whatever_context("Error creating the capture")?);
+		}
+		let callback = self.dispatcher.clone();
 		let config = self.config.clone();
-		let out_buffer_full = self.out_buffer_full.clone();
 
-		let mut connection_handler =
-			ConnectionHandler::new(callback, config, rx, out_buffer_full).await?;
+		let mut connection_handler = ConnectionHandler::new(
+			callback,
+			config,
+			rx,
+			queue_rx,
+			queue.downgrade(),
+			self.status.clone(),
+			self.tap.clone(),
+		)
+		.await?;
 
 		self.connection_handler_state = Some(connection_handler.get_state());
 
@@ -161,24 +256,46 @@ impl Client {
 		}));
 
 		self.write_tx = Some(tx);
+		self.send_queue = Some(queue);
 
 		Ok(())
 	}
 
+	/// Queues the ASDU to be sent. The ASDUs are held in the send queue while
+	/// the k window is full, and the commands are sent before the other ASDUs
+	/// (see [`Priority`](send_queue::Priority)).
+	///
+	/// Fails if the queue is full.
 	#[instrument(level = "debug")]
 	pub async fn send_asdu(&self, asdu: Asdu) -> Result<(), ClientError> {
-		self.check_connection_started()?;
+		self.queue_asdu(asdu, None)
+	}
 
-		if self.out_buffer_full.load(std::sync::atomic::Ordering::Relaxed) {
-			return errors::OutputBufferFull.fail();
-		}
+	/// Sends the ASDU like [`Client::send_asdu`] and waits until the server
+	/// acknowledges its I-frame.
+	///
+	/// Fails if the connection is lost before the acknowledge.
+	#[instrument(level = "debug")]
+	pub async fn send_asdu_acknowledged(&self, asdu: Asdu) -> Result<(), ClientError> {
+		let (acknowledged_tx, acknowledged_rx) = oneshot::channel();
+		self.queue_asdu(asdu, Some(acknowledged_tx))?;
+		acknowledged_rx.await.context(errors::NotAcknowledged)
+	}
 
-		if let Some(tx) = &self.write_tx {
-			tx.send(ConnectionHandlerCommand::Asdu(asdu)).await.context(errors::SendCommand)?;
-		} else {
-			return errors::NoWriteChannel.fail();
+	fn queue_asdu(
+		&self,
+		asdu: Asdu,
+		acknowledged: Option<oneshot::Sender<()>>,
+	) -> Result<(), ClientError> {
+		self.check_connection_started()?;
+
+		let queue = self.send_queue.as_ref().context(errors::NoWriteChannel)?;
+		let priority = Priority::of(asdu.type_id);
+		match queue.try_send(asdu, priority, acknowledged) {
+			Ok(()) => Ok(()),
+			Err(mpsc::error::TrySendError::Full(_)) => errors::OutputBufferFull.fail(),
+			Err(mpsc::error::TrySendError::Closed(_)) => errors::NoWriteChannel.fail(),
 		}
-		Ok(())
 	}
 
 	#[instrument(level = "debug")]
@@ -224,6 +341,69 @@ impl Client {
 		Ok(())
 	}
 
+	/// Watches the state of the connection. Every transition is published with
+	/// its reason, but a slow watcher only sees the latest one.
+	///
+	/// Can be called before connecting and stays valid across reconnections.
+	#[must_use]
+	pub fn connection_status(&self) -> watch::Receiver<ConnectionStatus> {
+		self.status.subscribe()
+	}
+
+	/// Subscribes to the received ASDUs matching the filter, as an alternative
+	/// to the callback. The callback keeps receiving every ASDU.
+	///
+	/// The subscription can be created before connecting and stays valid
+	/// across reconnections.
+	#[must_use]
+	pub fn subscribe(&self, filter: SubscriptionFilter) -> Subscription {
+		Subscription::new(
+			self.dispatcher
+				.subscribe_bounded(usize::from(self.config.subscription_capacity), move |asdu| {
+					filter.matches(asdu)
+				}),
+		)
+	}
+
+	/// Sends a command and returns a handle to track the replies of the server
+	/// to it. The replies are matched by type, common address and the address
+	/// of the first information object.
+	#[instrument(level = "debug")]
+	pub async fn send_command(&self, asdu: Asdu) -> Result<CommandHandle, ClientError> {
+		let type_id = asdu.type_id;
+		let common_address = asdu.address_field;
+		let ioa = asdu.information_objects.addresses().first().copied();
+		let responses = self.dispatcher.subscribe(move |response| {
+			response.type_id == type_id
+				&& response.address_field == common_address
+				&& response.information_objects.addresses().first().copied() == ioa
+		});
+
+		self.send_asdu(asdu).await?;
+		Ok(CommandHandle::new(responses, self.config.command_timeout))
+	}
+
+	/// Sends the select of a command and waits for its confirmation. The
+	/// select/execute qualifier of the command is overwritten. The returned
+	/// command must be executed within the select timeout.
+	#[instrument(level = "debug")]
+	pub async fn select(&self, mut command: Asdu) -> Result<SelectedCommand<'_>, ClientError> {
+		if !command.information_objects.set_select_execute(SelectExecute::Select) {
+			return errors::NotSelectable { type_id: command.type_id }.fail();
+		}
+		command.cot = Cot::Activation;
+
+		self.send_command(command.clone()).await?.confirmation().await?;
+		Ok(SelectedCommand::new(self, command, Instant::now() + self.config.select_timeout))
+	}
+
+	/// Selects the command and executes it as soon as the selection is
+	/// confirmed.
+	#[instrument(level = "debug")]
+	pub async fn select_before_operate(&self, command: Asdu) -> Result<CommandHandle, ClientError> {
+		self.select(command).await?.execute().await
+	}
+
 	pub async fn send_command_sp(
 		&self,
 		common_address: u16,
@@ -232,7 +412,7 @@ impl Client {
 		timestamp: Option<Cp56Time2a>,
 		select_execute: Option<SelectExecute>,
 		qu: Option<Qu>,
-	) -> Result<(), ClientError> {
+	) -> Result<CommandHandle, ClientError> {
 		let sco = Sco {
 			se: select_execute.unwrap_or(SelectExecute::Execute),
 			qu: qu.unwrap_or(Qu::Unspecified),
@@ -255,14 +435,14 @@ impl Client {
 			),
 		};
 
-		self.send_asdu(Asdu {
+		self.send_command(Asdu {
 			type_id,
 			information_objects,
-			originator_address: 0,
+			originator_address: self.config.protocol.originator_address,
 			address_field: common_address,
 			sequence: false,
 			test: false,
-			cot: Cot::Request,
+			cot: Cot::Activation,
 			positive: false,
 		})
 		.await
@@ -276,7 +456,7 @@ impl Client {
 		timestamp: Option<Cp56Time2a>,
 		select_execute: Option<SelectExecute>,
 		qu: Option<Qu>,
-	) -> Result<(), ClientError> {
+	) -> Result<CommandHandle, ClientError> {
 		let dco = Dco {
 			se: select_execute.unwrap_or(SelectExecute::Execute),
 			qu: qu.unwrap_or(Qu::Unspecified),
@@ -299,14 +479,14 @@ impl Client {
 			),
 		};
 
-		self.send_asdu(Asdu {
+		self.send_command(Asdu {
 			type_id,
 			information_objects,
-			originator_address: 0,
+			originator_address: self.config.protocol.originator_address,
 			address_field: common_address,
 			sequence: false,
 			test: false,
-			cot: Cot::Request,
+			cot: Cot::Activation,
 			positive: false,
 		})
 		.await
@@ -320,7 +500,7 @@ impl Client {
 		timestamp: Option<Cp56Time2a>,
 		select_execute: Option<SelectExecute>,
 		qu: Option<Qu>,
-	) -> Result<(), ClientError> {
+	) -> Result<CommandHandle, ClientError> {
 		let rco = Rco {
 			se: select_execute.unwrap_or(SelectExecute::Execute),
 			qu: qu.unwrap_or(Qu::Unspecified),
@@ -343,14 +523,14 @@ impl Client {
 			),
 		};
 
-		self.send_asdu(Asdu {
+		self.send_command(Asdu {
 			type_id,
 			information_objects,
-			originator_address: 0,
+			originator_address: self.config.protocol.originator_address,
 			address_field: common_address,
 			sequence: false,
 			test: false,
-			cot: Cot::Request,
+			cot: Cot::Activation,
 			positive: false,
 		})
 		.await
@@ -362,7 +542,7 @@ impl Client {
 		ioa: u32,
 		value: u32,
 		timestamp: Option<Cp56Time2a>,
-	) -> Result<(), ClientError> {
+	) -> Result<CommandHandle, ClientError> {
 		let (type_id, information_objects) = match timestamp {
 			Some(timestamp) => (
 				TypeId::C_BO_TA_1,
@@ -380,19 +560,310 @@ impl Client {
 			),
 		};
 
-		self.send_asdu(Asdu {
+		self.send_command(Asdu {
 			type_id,
 			information_objects,
-			originator_address: 0,
+			originator_address: self.config.protocol.originator_address,
 			address_field: common_address,
 			sequence: false,
 			test: false,
-			cot: Cot::Request,
+			cot: Cot::Activation,
 			positive: false,
 		})
 		.await
 	}
 
+	/// Sends a set-point command with a normalized value, in the range
+	/// [-1, 1).
+	///
+	/// Fails without sending anything if the value is out of range.
+	pub async fn send_setpoint_normalized(
+		&self,
+		common_address: u16,
+		ioa: u32,
+		value: f32,
+		timestamp: Option<Cp56Time2a>,
+		qos: Option<Qos>,
+	) -> Result<CommandHandle, ClientError> {
+		if !(-1.0..1.0).contains(&value) {
+			return errors::InvalidNormalizedValue { value }.fail();
+		}
+		// The normalized value is a fraction of 2^15. The cast saturates the values
+		// rounded up to 1
+		let nva = ((value * 32768.0).round() as i16).cast_unsigned();
+		self.send_setpoint(common_address, qos, |qos| match timestamp {
+			Some(time) => (
+				TypeId::C_SE_TA_1,
+				InformationObjects::CSeTa1(vec![GenericObject {
+					address: ioa,
+					object: CSeTa1 { nva, qos, time },
+				}]),
+			),
+			None => (
+				TypeId::C_SE_NA_1,
+				InformationObjects::CSeNa1(vec![GenericObject {
+					address: ioa,
+					object: CSeNa1 { nva, qos },
+				}]),
+			),
+		})
+		.await
+	}
+
+	/// Sends a set-point command with a scaled value.
+	pub async fn send_setpoint_scaled(
+		&self,
+		common_address: u16,
+		ioa: u32,
+		value: i16,
+		timestamp: Option<Cp56Time2a>,
+		qos: Option<Qos>,
+	) -> Result<CommandHandle, ClientError> {
+		let sva = value.cast_unsigned();
+		self.send_setpoint(common_address, qos, |qos| match timestamp {
+			Some(time) => (
+				TypeId::C_SE_TB_1,
+				InformationObjects::CSeTb1(vec![GenericObject {
+					address: ioa,
+					object: CSeTb1 { sva, qos, time },
+				}]),
+			),
+			None => (
+				TypeId::C_SE_NB_1,
+				InformationObjects::CSeNb1(vec![GenericObject {
+					address: ioa,
+					object: CSeNb1 { sva, qos },
+				}]),
+			),
+		})
+		.await
+	}
+
+	/// Sends a set-point command with a short floating point value.
+	pub async fn send_setpoint_float(
+		&self,
+		common_address: u16,
+		ioa: u32,
+		value: f32,
+		timestamp: Option<Cp56Time2a>,
+		qos: Option<Qos>,
+	) -> Result<CommandHandle, ClientError> {
+		self.send_setpoint(common_address, qos, |qos| match timestamp {
+			Some(time) => (
+				TypeId::C_SE_TC_1,
+				InformationObjects::CSeTc1(vec![GenericObject {
+					address: ioa,
+					object: CSeTc1 { value, qos, time },
+				}]),
+			),
+			None => (
+				TypeId::C_SE_NC_1,
+				InformationObjects::CSeNc1(vec![GenericObject {
+					address: ioa,
+					object: CSeNc1 { value, qos },
+				}]),
+			),
+		})
+		.await
+	}
+
+	/// Sends the set-point command built by `command` from its qualifier. The
+	/// qualifier defaults to a direct execute.
+	async fn send_setpoint(
+		&self,
+		common_address: u16,
+		qos: Option<Qos>,
+		command: impl FnOnce(Qos) -> (TypeId, InformationObjects),
+	) -> Result<CommandHandle, ClientError> {
+		let (type_id, information_objects) =
+			command(qos.unwrap_or(Qos { se: SelectExecute::Execute, ql: false }));
+		self.send_command(Asdu {
+			type_id,
+			information_objects,
+			originator_address: self.config.protocol.originator_address,
+			address_field: common_address,
+			sequence: false,
+			test: false,
+			cot: Cot::Activation,
+			positive: false,
+		})
+		.await
+	}
+
+	/// Sends a general or group interrogation and waits for its termination.
+	/// Returns the information objects received in response, in order.
+	///
+	/// Fails if the interrogation is rejected or if the server goes silent for
+	/// longer than t1 before terminating it. Fails without sending anything
+	/// for the broadcast address, since every station confirms and terminates
+	/// it with its own common address.
+	#[instrument(level = "debug")]
+	pub async fn interrogate(
+		&self,
+		common_address: u16,
+		qoi: Qoi,
+	) -> Result<Vec<InformationObjects>, ClientError> {
+		let request = Asdu {
+			type_id: TypeId::C_IC_NA_1,
+			information_objects: InformationObjects::CIcNa1(vec![GenericObject {
+				address: 0,
+				object: CIcNa1 { qoi },
+			}]),
+			originator_address: self.config.protocol.originator_address,
+			address_field: common_address,
+			sequence: false,
+			test: false,
+			cot: Cot::Activation,
+			positive: false,
+		};
+		// The qualifiers of the general and group interrogations have the same value
+		// as the cause of transmission of their responses
+		let response_cot = Cot::try_from(qoi.to_byte()).unwrap_or(Cot::InterrogationGeneral);
+		self.activation_transaction(request, response_cot).await
+	}
+
+	/// Sends a counter interrogation and waits for its termination. Returns the
+	/// integrated totals received in response, in order.
+	///
+	/// With [`Frz::Read`] the counters are read. With the freeze and reset
+	/// qualifiers most stations only confirm and terminate the command, and the
+	/// frozen values are read with a following [`Frz::Read`] interrogation or
+	/// sent spontaneously. In that case the returned vector is empty.
+	///
+	/// Fails without sending anything if `rqt` requests no counters, or for the
+	/// broadcast address like [`Client::interrogate`].
+	#[instrument(level = "debug")]
+	pub async fn interrogate_counters(
+		&self,
+		common_address: u16,
+		rqt: Rqt,
+		frz: Frz,
+	) -> Result<Vec<InformationObjects>, ClientError> {
+		let response_cot = match rqt {
+			Rqt::ReqCo1 => Cot::CounterInterrogationGroup1,
+			Rqt::ReqCo2 => Cot::CounterInterrogationGroup2,
+			Rqt::ReqCo3 => Cot::CounterInterrogationGroup3,
+			Rqt::ReqCo4 => Cot::CounterInterrogationGroup4,
+			Rqt::ReqCoGen => Cot::CounterInterrogationGeneral,
+			Rqt::None | Rqt::Other(_) => return errors::InvalidRequestQualifier { rqt }.fail(),
+		};
+		let request = Asdu {
+			type_id: TypeId::C_CI_NA_1,
+			information_objects: InformationObjects::CCiNa1(vec![GenericObject {
+				address: 0,
+				object: CCiNa1 { rqt, frz },
+			}]),
+			originator_address: self.config.protocol.originator_address,
+			address_field: common_address,
+			sequence: false,
+			test: false,
+			cot: Cot::Activation,
+			positive: false,
+		};
+		self.activation_transaction(request, response_cot).await
+	}
+
+	/// Synchronizes the clock of the station with `time`, sent in UTC, or with
+	/// the current local time if `None`, and waits for the confirmation. Fails
+	/// if `time` is `None` and the local offset of the system can't be
+	/// determined.
+	///
+	/// Returns the time reported by the station in the confirmation and the
+	/// measured round trip, which can be used to compensate the transmission
+	/// delay in the following synchronizations. Fails if the confirmation
+	/// doesn't carry the time of the station.
+	#[instrument(level = "debug")]
+	pub async fn sync_clock(
+		&self,
+		common_address: u16,
+		time: Option<OffsetDateTime>,
+	) -> Result<ClockSynchronization, ClientError> {
+		// Fails instead of sending UTC when the local offset can't be determined
+		let (time, zone) = match time {
+			Some(time) => (time, TimeZone::Utc),
+			None => (OffsetDateTime::now_utc(), TimeZone::Local),
+		};
+		let sent = Cp56Time2a::from_offset_date_time(time, zone).context(errors::InvalidTime)?;
+		let start = Instant::now();
+		let mut handle = self
+			.send_command(Asdu {
+				type_id: TypeId::C_CS_NA_1,
+				information_objects: InformationObjects::CCsNa1(vec![GenericObject {
+					address: 0,
+					object: CCsNa1 { time: sent.clone() },
+				}]),
+				originator_address: self.config.protocol.originator_address,
+				address_field: common_address,
+				sequence: false,
+				test: false,
+				cot: Cot::Activation,
+				positive: false,
+			})
+			.await?;
+		let confirmation = handle.confirmation().await?;
+		let round_trip = start.elapsed();
+
+		let station_time = match confirmation.information_objects {
+			InformationObjects::CCsNa1(objects) if !objects.is_empty() => {
+				objects[0].object.time.clone()
+			}
+			_ => {
+				return errors::UnexpectedResponse {
+					type_id: TypeId::C_CS_NA_1,
+					cot: confirmation.cot,
+				}
+				.fail();
+			}
+		};
+		let sync = ClockSynchronization { sent, station_time, round_trip };
+		// The station answers in the time zone it was synchronized in
+		if let Ok(station_time) = sync.station_time.to_offset_date_time(zone) {
+			self.dispatcher.set_station_clock(station_time + sync.delay());
+		}
+		Ok(sync)
+	}
+
+	/// Sends the activation and collects the objects received with
+	/// `response_cot` until the activation is terminated.
+	#[instrument(level = "debug")]
+	async fn activation_transaction(
+		&self,
+		request: Asdu,
+		response_cot: Cot,
+	) -> Result<Vec<InformationObjects>, ClientError> {
+		let type_id = request.type_id;
+		let common_address = request.address_field;
+		ensure!(common_address != BROADCAST_ADDRESS, errors::BroadcastInterrogation);
+		let mut responses = self.dispatcher.subscribe(move |asdu| {
+			asdu.address_field == common_address
+				&& (asdu.type_id == type_id || asdu.cot == response_cot)
+		});
+
+		self.send_asdu(request).await?;
+
+		let mut objects = Vec::new();
+		loop {
+			let asdu = tokio::time::timeout(self.config.protocol.t1, responses.recv())
+				.await
+				.context(errors::Timeout)?
+				.context(errors::NoWriteChannel)?;
+
+			if asdu.type_id != type_id {
+				objects.push(asdu.information_objects);
+				continue;
+			}
+			// The P/N bit is set on negative confirmations
+			if asdu.positive {
+				return errors::NegativeConfirmation { cot: asdu.cot }.fail();
+			}
+			match asdu.cot {
+				Cot::ActivationTermination => return Ok(objects),
+				Cot::ActivationConfirmation => {}
+				cot => tracing::warn!("Unexpected cause of transmission {cot:?} for {type_id:?}"),
+			}
+		}
+	}
+
 	#[instrument(level = "debug")]
 	fn check_connection_started(&self) -> Result<(), ClientError> {
 		self.check_connected()?;
@@ -408,7 +879,9 @@ impl Client {
 
 	#[instrument(level = "debug")]
 	fn check_connected(&self) -> Result<(), ClientError> {
-		if self.connection_handler_state.is_none() {
+		if self.connection_handler_state.is_none()
+			|| self.receive_task.as_ref().is_some_and(JoinHandle::is_finished)
+		{
 			return errors::NotConnected.fail();
 		}
 		if let Some(state) = &self.connection_handler_state
@@ -433,3 +906,208 @@ impl Debug for Client {
 		)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+
+	use tokio::sync::mpsc;
+
+	use super::*;
+	use crate::{
+		server::OnNewCommand,
+		test_util::{Commands, connect_client, server_config, start, start_server},
+		types::{MItNa1, MMeNc1, MSpNa1},
+	};
+
+	/// Answers the counter interrogations with a single counter.
+	struct Counters;
+
+	#[async_trait]
+	impl OnNewCommand for Counters {
+		async fn on_new_command(&self, asdu: Asdu) -> Vec<Asdu> {
+			let counters = Asdu {
+				type_id: TypeId::M_IT_NA_1,
+				cot: Cot::CounterInterrogationGeneral,
+				information_objects: InformationObjects::MItNa1(vec![GenericObject {
+					address: 300,
+					object: MItNa1 { bcr: 1234, ..Default::default() },
+				}]),
+				..asdu.clone()
+			};
+			vec![
+				Asdu { cot: Cot::ActivationConfirmation, ..asdu.clone() },
+				counters,
+				Asdu { cot: Cot::ActivationTermination, ..asdu },
+			]
+		}
+	}
+
+	#[tokio::test]
+	async fn test_interrogate() {
+		let (command_tx, mut command_rx) = mpsc::unbounded_channel();
+		let (server, client, _object_rx) = start(Commands(command_tx)).await;
+		server
+			.update_points(
+				1,
+				InformationObjects::MMeNc1(
+					(1..=200)
+						.map(|address| GenericObject { address, object: MMeNc1::default() })
+						.collect(),
+				),
+				false,
+			)
+			.unwrap();
+		server
+			.update_points(
+				1,
+				InformationObjects::MSpNa1(vec![GenericObject {
+					address: 300,
+					object: MSpNa1::default(),
+				}]),
+				false,
+			)
+			.unwrap();
+		assert!(server.database().set_groups(1, 300, &[Qoi::Group1]));
+
+		let objects = client.interrogate(1, Qoi::Global).await.unwrap();
+		assert_eq!(objects.iter().flat_map(InformationObjects::addresses).count(), 201);
+
+		let objects = client.interrogate(1, Qoi::Group1).await.unwrap();
+		assert_eq!(
+			objects.iter().flat_map(InformationObjects::addresses).collect::<Vec<_>>(),
+			[300]
+		);
+
+		let result = client.interrogate(2, Qoi::Global).await;
+		assert!(matches!(
+			result,
+			Err(ClientError::NegativeConfirmation { cot: Cot::UnknownAsduAddress, .. })
+		));
+
+		// Every station would answer with its own common address
+		assert!(matches!(
+			client.interrogate(BROADCAST_ADDRESS, Qoi::Global).await,
+			Err(ClientError::BroadcastInterrogation { .. })
+		));
+		assert!(matches!(
+			client.interrogate_counters(BROADCAST_ADDRESS, Rqt::ReqCoGen, Frz::Read).await,
+			Err(ClientError::BroadcastInterrogation { .. })
+		));
+
+		// The interrogations never reach the callback
+		assert!(command_rx.try_recv().is_err());
+	}
+
+	#[tokio::test]
+	async fn test_command_handle() {
+		let (command_tx, mut command_rx) = mpsc::unbounded_channel();
+		let server = start_server(server_config(), Commands(command_tx)).await;
+		let (client, _object_rx) = connect_client(
+			server.local_addr().unwrap().port(),
+			ClientConfig { command_timeout: Duration::from_millis(200), ..Default::default() },
+		)
+		.await;
+
+		let mut handle = client.send_command_sp(1, 100, Spi::On, None, None, None).await.unwrap();
+		let command = command_rx.recv().await.unwrap();
+		assert_eq!(command.cot, Cot::Activation);
+
+		let confirmation = handle.confirmation().await.unwrap();
+		assert_eq!(confirmation.cot, Cot::ActivationConfirmation);
+		assert_eq!(confirmation.type_id, command.type_id);
+		assert_eq!(confirmation.information_objects.addresses(), [100]);
+		// The confirmation is kept once received
+		assert_eq!(handle.confirmation().await.unwrap(), confirmation);
+		// The callback never terminates the command
+		assert!(matches!(handle.termination().await, Err(ClientError::Timeout { .. })));
+	}
+
+	#[tokio::test]
+	async fn test_setpoints() {
+		let (command_tx, mut command_rx) = mpsc::unbounded_channel();
+		let (_server, client, _object_rx) = start(Commands(command_tx)).await;
+
+		for (value, nva) in [(-0.5, 0xC000), (-1.0, 0x8000), (0.25, 0x2000), (0.999_99, 0x7FFF)] {
+			client.send_setpoint_normalized(1, 10, value, None, None).await.unwrap();
+			let command = command_rx.recv().await.unwrap();
+			let InformationObjects::CSeNa1(objects) = command.information_objects else {
+				panic!("Expected a CSeNa1, got {command:?}")
+			};
+			assert_eq!(objects[0].object.nva, nva);
+		}
+		for value in [1.0, -1.5, f32::NAN] {
+			assert!(matches!(
+				client.send_setpoint_normalized(1, 10, value, None, None).await,
+				Err(ClientError::InvalidNormalizedValue { .. })
+			));
+		}
+
+		let qos = Qos { se: SelectExecute::Select, ql: false };
+		client.send_setpoint_scaled(1, 11, -100, None, Some(qos.clone())).await.unwrap();
+		let command = command_rx.recv().await.unwrap();
+		let InformationObjects::CSeNb1(objects) = command.information_objects else {
+			panic!("Expected a CSeNb1, got {command:?}")
+		};
+		assert_eq!(objects[0].object.sva.cast_signed(), -100);
+		assert_eq!(objects[0].object.qos, qos);
+
+		let time = Cp56Time2a { year: 24, month: 1, day: 1, ..Default::default() };
+		client.send_setpoint_float(1, 12, -2.5, Some(time.clone()), None).await.unwrap();
+		let command = command_rx.recv().await.unwrap();
+		assert_eq!(command.cot, Cot::Activation);
+		assert_eq!(command.originator_address, ClientConfig::default().protocol.originator_address);
+		let InformationObjects::CSeTc1(objects) = command.information_objects else {
+			panic!("Expected a CSeTc1, got {command:?}")
+		};
+		assert_eq!(objects[0].address, 12);
+		assert_eq!(objects[0].object.value.to_bits(), (-2.5_f32).to_bits());
+		assert_eq!(objects[0].object.qos.se, SelectExecute::Execute);
+		assert_eq!(objects[0].object.time, time);
+	}
+
+	#[tokio::test]
+	async fn test_sync_clock() {
+		let (command_tx, mut command_rx) = mpsc::unbounded_channel();
+		let (_server, client, _object_rx) = start(Commands(command_tx)).await;
+
+		// 2023-11-14 22:13:20 UTC, a tuesday
+		let time = OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+		let sync = client.sync_clock(1, Some(time)).await.unwrap();
+		assert_eq!(command_rx.recv().await.unwrap().type_id, TypeId::C_CS_NA_1);
+		assert_eq!(
+			(sync.sent.year, sync.sent.month, sync.sent.day, sync.sent.weekday),
+			(23, 11, 14, 2)
+		);
+		assert_eq!((sync.sent.hour, sync.sent.min, sync.sent.ms), (22, 13, 20_000));
+		assert_eq!(sync.station_time, sync.sent);
+	}
+
+	#[tokio::test]
+	async fn test_counter_interrogation() {
+		let (_server, client, _object_rx) = start(Counters).await;
+
+		let objects = client.interrogate_counters(1, Rqt::ReqCoGen, Frz::Read).await.unwrap();
+
+		let [InformationObjects::MItNa1(counters)] = objects.as_slice() else {
+			panic!("Expected a single MItNa1 ASDU, got {objects:?}")
+		};
+		assert_eq!(counters[0].address, 300);
+		assert_eq!(counters[0].object.bcr, 1234);
+	}
+
+	#[tokio::test]
+	async fn test_interrogate_counters_invalid_qualifier() {
+		let (command_tx, mut command_rx) = mpsc::unbounded_channel();
+		let (_server, client, _object_rx) = start(Commands(command_tx)).await;
+
+		for rqt in [Rqt::None, Rqt::Other(40)] {
+			assert!(matches!(
+				client.interrogate_counters(1, rqt, Frz::Read).await,
+				Err(ClientError::InvalidRequestQualifier { rqt: rejected, .. }) if rejected == rqt
+			));
+		}
+		tokio::time::sleep(Duration::from_millis(50)).await;
+		assert!(command_rx.try_recv().is_err());
+	}
+}
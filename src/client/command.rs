@@ -0,0 +1,230 @@
+use std::time::Duration;
+
+use snafu::{OptionExt as _, ResultExt as _};
+use tokio::{sync::mpsc, time::Instant};
+use tracing::instrument;
+
+use crate::{
+	asdu::Asdu,
+	client::{
+		Client,
+		errors::{self, ClientError},
+	},
+	cot::Cot,
+	types::{information_elements::SelectExecute, time::Cp56Time2a},
+};
+
+/// Handle to a command sent to the server.
+///
+/// Receives the replies of the server matching the type, common address and
+/// information object address of the command. Dropping the handle doesn't
+/// cancel the command, it only stops tracking it.
+#[derive(Debug)]
+pub struct CommandHandle {
+	responses: mpsc::UnboundedReceiver<Asdu>,
+	timeout: Duration,
+	confirmation: Option<Asdu>,
+	termination: Option<Asdu>,
+}
+
+impl CommandHandle {
+	pub(crate) const fn new(responses: mpsc::UnboundedReceiver<Asdu>, timeout: Duration) -> Self {
+		Self { responses, timeout, confirmation: None, termination: None }
+	}
+
+	/// Waits for the activation confirmation, or the deactivation confirmation
+	/// for a cancelled selection. Fails if the confirmation is negative or
+	/// doesn't arrive within the command timeout.
+	#[instrument(level = "debug")]
+	pub async fn confirmation(&mut self) -> Result<Asdu, ClientError> {
+		if let Some(confirmation) = &self.confirmation {
+			return Ok(confirmation.clone());
+		}
+
+		loop {
+			let asdu = self.next_response().await?;
+			match asdu.cot {
+				Cot::ActivationConfirmation | Cot::DeactivationConfirmation => {
+					self.confirmation = Some(asdu.clone());
+					return Ok(asdu);
+				}
+				// The confirmation was lost or never sent. Keep it for `termination`
+				Cot::ActivationTermination => self.termination = Some(asdu),
+				cot => tracing::warn!("Unexpected cause of transmission {cot:?} for a command"),
+			}
+		}
+	}
+
+	/// Waits for the activation confirmation and then for the activation
+	/// termination. Not every command is terminated, check the server
+	/// documentation before waiting for it.
+	#[instrument(level = "debug")]
+	pub async fn termination(mut self) -> Result<Asdu, ClientError> {
+		self.confirmation().await?;
+		if let Some(termination) = self.termination.take() {
+			return Ok(termination);
+		}
+
+		loop {
+			let asdu = self.next_response().await?;
+			match asdu.cot {
+				Cot::ActivationTermination => return Ok(asdu),
+				cot => tracing::warn!("Unexpected cause of transmission {cot:?} for a command"),
+			}
+		}
+	}
+
+	/// The next reply of the server. Negative replies are returned as errors.
+	async fn next_response(&mut self) -> Result<Asdu, ClientError> {
+		let asdu = tokio::time::timeout(self.timeout, self.responses.recv())
+			.await
+			.context(errors::Timeout)?
+			.context(errors::NoWriteChannel)?;
+		// The P/N bit is set on negative replies
+		if asdu.positive {
+			return errors::NegativeConfirmation { cot: asdu.cot }.fail();
+		}
+		Ok(asdu)
+	}
+}
+
+/// Result of a clock synchronization.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClockSynchronization {
+	/// The time sent to the station.
+	pub sent: Cp56Time2a,
+	/// The time returned by the station in the activation confirmation.
+	pub station_time: Cp56Time2a,
+	/// Time between sending the command and receiving its confirmation.
+	pub round_trip: Duration,
+}
+
+impl ClockSynchronization {
+	/// The estimated transmission delay from the client to the station, half
+	/// of the round trip. Adding it to the time of a later synchronization
+	/// compensates for the time the command spends on the way.
+	#[must_use]
+	pub fn delay(&self) -> Duration {
+		self.round_trip / 2
+	}
+}
+
+/// A command selected with [`Client::select`], waiting to be executed or
+/// cancelled.
+#[derive(Debug)]
+pub struct SelectedCommand<'a> {
+	client: &'a Client,
+	command: Asdu,
+	deadline: Instant,
+}
+
+impl<'a> SelectedCommand<'a> {
+	pub(crate) const fn new(client: &'a Client, command: Asdu, deadline: Instant) -> Self {
+		Self { client, command, deadline }
+	}
+
+	/// Sends the execute of the selected command. Fails without sending
+	/// anything if the select timeout has elapsed since the selection was
+	/// confirmed.
+	#[instrument(level = "debug")]
+	pub async fn execute(mut self) -> Result<CommandHandle, ClientError> {
+		if Instant::now() > self.deadline {
+			return errors::SelectTimeout.fail();
+		}
+		self.command.information_objects.set_select_execute(SelectExecute::Execute);
+		self.client.send_command(self.command).await
+	}
+
+	/// Cancels the selection with a deactivation. The returned handle resolves
+	/// to the deactivation confirmation.
+	#[instrument(level = "debug")]
+	pub async fn cancel(self) -> Result<CommandHandle, ClientError> {
+		self.client.send_command(Asdu { cot: Cot::Deactivation, ..self.command }).await
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use async_trait::async_trait;
+
+	use super::*;
+	use crate::{
+		server::OnNewCommand,
+		test_util::start,
+		types::{
+			CScNa1, GenericObject, InformationObjects, commands::Sco, information_elements::Spi,
+		},
+		types_id::TypeId,
+	};
+
+	/// Confirms the selects and deactivations and terminates the executes.
+	struct SelectBeforeOperate(mpsc::UnboundedSender<Asdu>);
+
+	#[async_trait]
+	impl OnNewCommand for SelectBeforeOperate {
+		async fn on_new_command(&self, asdu: Asdu) -> Vec<Asdu> {
+			self.0.send(asdu.clone()).unwrap();
+			let InformationObjects::CScNa1(objects) = &asdu.information_objects else {
+				return vec![Asdu { cot: Cot::UnknownType, positive: true, ..asdu }];
+			};
+			match (asdu.cot, objects[0].object.sco.se) {
+				(Cot::Deactivation, _) => vec![Asdu { cot: Cot::DeactivationConfirmation, ..asdu }],
+				(_, SelectExecute::Select) => {
+					vec![Asdu { cot: Cot::ActivationConfirmation, ..asdu }]
+				}
+				(_, SelectExecute::Execute) => vec![
+					Asdu { cot: Cot::ActivationConfirmation, ..asdu.clone() },
+					Asdu { cot: Cot::ActivationTermination, ..asdu },
+				],
+			}
+		}
+	}
+
+	#[tokio::test]
+	async fn test_select_before_operate() {
+		let (command_tx, mut command_rx) = mpsc::unbounded_channel();
+		let (_server, client, _object_rx) = start(SelectBeforeOperate(command_tx)).await;
+
+		let command = Asdu {
+			type_id: TypeId::C_SC_NA_1,
+			cot: Cot::Activation,
+			originator_address: 0,
+			address_field: 1,
+			sequence: false,
+			test: false,
+			positive: false,
+			information_objects: InformationObjects::CScNa1(vec![GenericObject {
+				address: 100,
+				object: CScNa1 { sco: Sco { scs: Spi::On, ..Default::default() } },
+			}]),
+		};
+
+		let handle = client.select_before_operate(command.clone()).await.unwrap();
+		assert_eq!(handle.termination().await.unwrap().cot, Cot::ActivationTermination);
+
+		let se = |asdu: Asdu| match asdu.information_objects {
+			InformationObjects::CScNa1(objects) => (asdu.cot, objects[0].object.sco.se),
+			_ => panic!("Expected a CScNa1"),
+		};
+		assert_eq!(se(command_rx.recv().await.unwrap()), (Cot::Activation, SelectExecute::Select));
+		assert_eq!(se(command_rx.recv().await.unwrap()), (Cot::Activation, SelectExecute::Execute));
+
+		let mut handle = client.select(command.clone()).await.unwrap().cancel().await.unwrap();
+		assert_eq!(handle.confirmation().await.unwrap().cot, Cot::DeactivationConfirmation);
+		assert_eq!(se(command_rx.recv().await.unwrap()), (Cot::Activation, SelectExecute::Select));
+		assert_eq!(
+			se(command_rx.recv().await.unwrap()),
+			(Cot::Deactivation, SelectExecute::Select)
+		);
+
+		let interrogation = Asdu {
+			type_id: TypeId::C_IC_NA_1,
+			information_objects: InformationObjects::CIcNa1(vec![GenericObject::default()]),
+			..command
+		};
+		assert!(matches!(
+			client.select(interrogation).await,
+			Err(ClientError::NotSelectable { type_id: TypeId::C_IC_NA_1, .. })
+		));
+	}
+}
@@ -1,11 +1,17 @@
-use std::sync::{Arc, atomic::AtomicBool};
+use std::{
+	hash::{BuildHasher as _, Hasher as _, RandomState},
+	sync::Arc,
+	time::Duration,
+};
 
 use atomic_enum::atomic_enum;
 use snafu::{ResultExt as _, whatever};
 use tokio::{
 	io::{ReadHalf, WriteHalf},
 	net::TcpStream,
-	sync::mpsc,
+	select,
+	sync::{mpsc, watch},
+	time::Instant,
 };
 use tokio_native_tls::{
 	TlsConnector,
@@ -16,9 +22,19 @@ use tracing::instrument;
 use crate::{
 	apdu::Frame,
 	asdu::Asdu,
-	client::{Connection, OnNewObjects, START_DT_ACT_FRAME, receive_handler::ReceiveHandler},
-	config::{ClientConfig, TlsClientConfig},
-	error::Error,
+	capture::tap::{ConnectionTap, Tap},
+	client::{
+		Connection, OnNewObjects, START_DT_ACT_FRAME,
+		receive_handler::ReceiveHandler,
+		redundancy::StandbyLink,
+		send_queue::{Priority, SendQueueReceiver, WeakSendQueue},
+		state::{ConnectionState, ConnectionStatus, StateChangeReason},
+	},
+	config::{ClientConfig, Endpoint, ReconnectPolicy, TlsClientConfig},
+	cot::Cot,
+	error::{self, Error},
+	types::{CIcNa1, GenericObject, InformationObjects},
+	types_id::TypeId,
 };
 
 #[atomic_enum]
@@ -35,7 +51,6 @@ pub enum ConnectionHandlerCommand {
 	Start,
 	Stop,
 	Test,
-	Asdu(Asdu),
 }
 
 pub struct ConnectionHandler {
@@ -45,7 +60,24 @@ pub struct ConnectionHandler {
 	config: ClientConfig,
 	state: Arc<AtomicConnectionHandlerState>,
 	rx: mpsc::Receiver<ConnectionHandlerCommand>,
-	out_buffer_full: Arc<AtomicBool>,
+	queue_rx: SendQueueReceiver,
+	/// Queues the ASDUs sent on behalf of the client, like the interrogation
+	/// after reconnecting. Weak so that the queue is closed once the client is
+	/// dropped.
+	queue: WeakSendQueue,
+	status: Arc<watch::Sender<ConnectionStatus>>,
+	/// The failed reconnection attempts since the connection was lost.
+	attempts: u32,
+	/// Whether the data transfer is being restarted after reconnecting.
+	reconnected: bool,
+	/// The endpoint of the active connection.
+	endpoint: Endpoint,
+	/// The other endpoints of the redundancy group.
+	standby: Vec<StandbyLink>,
+	/// The capture of the connections, when configured.
+	tap: Option<Tap>,
+	/// Where the APDUs of the active connection are captured.
+	connection_tap: Option<ConnectionTap>,
 }
 
 impl ConnectionHandler {
@@ -53,12 +85,47 @@ impl ConnectionHandler {
 		callback: Arc<dyn OnNewObjects + Send + Sync>,
 		config: ClientConfig,
 		rx: mpsc::Receiver<ConnectionHandlerCommand>,
-		out_buffer_full: Arc<AtomicBool>,
+		queue_rx: SendQueueReceiver,
+		queue: WeakSendQueue,
+		status: Arc<watch::Sender<ConnectionStatus>>,
+		tap: Option<Tap>,
 	) -> Result<Self, Error> {
-		let connection =
-			Self::make_connection(&config).await.whatever_context("Error making connection")?;
+		// The first endpoint that can be reached becomes the active one, the others
+		// are standby
+		let mut endpoints =
+			std::iter::once(Endpoint { address: config.address.clone(), port: config.port })
+				.chain(config.redundancy_group.iter().cloned())
+				.collect::<Vec<_>>();
+		let mut active = None;
+		for (i, endpoint) in endpoints.iter().enumerate() {
+			match Self::make_connection(&config, endpoint).await {
+				Ok(connection) => {
+					active = Some((i, connection));
+					break;
+				}
+				Err(e) => {
+					status.send_modify(|status| {
+						status.reason =
+							StateChangeReason::from_error(&e, StateChangeReason::ConnectionFailed);
+					});
+					if i + 1 == endpoints.len() {
+						return Err(e).whatever_context("Error making connection");
+					}
+					tracing::warn!("Error connecting to {endpoint:?}: {e}. Trying the next one");
+				}
+			}
+		}
+		let Some((active, connection)) = active else {
+			whatever!("No endpoint to connect to");
+		};
+		let endpoint = endpoints.remove(active);
+		let standby = endpoints
+			.into_iter()
+			.map(|endpoint| StandbyLink::spawn(endpoint, config.clone()))
+			.collect();
+		let connection_tap = Self::tap_connection(tap.as_ref(), &connection);
 		let (read_connection, write_connection) = tokio::io::split(connection);
-		Ok(Self {
+		let handler = Self {
 			callback,
 			config,
 			state: Arc::new(AtomicConnectionHandlerState::new(
@@ -67,14 +134,48 @@ impl ConnectionHandler {
 			read_connection,
 			write_connection,
 			rx,
-			out_buffer_full,
-		})
+			queue_rx,
+			queue,
+			status,
+			attempts: 0,
+			reconnected: false,
+			endpoint,
+			standby,
+			tap,
+			connection_tap,
+		};
+		handler.set_state(ConnectionHandlerState::WaitingForStart, StateChangeReason::Connected);
+		Ok(handler)
 	}
 
 	pub fn get_state(&self) -> Arc<AtomicConnectionHandlerState> {
 		self.state.clone()
 	}
 
+	/// Taps the connection, if there is a capture and its addresses are known.
+	fn tap_connection(tap: Option<&Tap>, connection: &Connection) -> Option<ConnectionTap> {
+		let tap = tap?;
+		match connection.addresses() {
+			Ok((local, peer)) => Some(tap.connection(local, peer)),
+			Err(e) => {
+				tracing::warn!("Error getting the addresses of the connection to capture: {e}");
+				None
+			}
+		}
+	}
+
+	/// Makes the connection the active one.
+	fn set_connection(&mut self, connection: Connection) {
+		self.connection_tap = Self::tap_connection(self.tap.as_ref(), &connection);
+		(self.read_connection, self.write_connection) = tokio::io::split(connection);
+	}
+
+	/// Moves to the new state and notifies the watchers of the status.
+	fn set_state(&self, state: ConnectionHandlerState, reason: StateChangeReason) {
+		self.state.store(state, std::sync::atomic::Ordering::Relaxed);
+		self.status.send_replace(ConnectionStatus { state: state.into(), reason });
+	}
+
 	#[instrument(level = "debug", skip_all)]
 	pub async fn run(&mut self) -> Result<(), Error> {
 		loop {
@@ -83,9 +184,9 @@ impl ConnectionHandler {
 					if let Some(cmd) = self.rx.recv().await {
 						match cmd {
 							ConnectionHandlerCommand::Start => {
-								self.state.store(
+								self.set_state(
 									ConnectionHandlerState::Starting,
-									std::sync::atomic::Ordering::Relaxed,
+									StateChangeReason::StartRequested,
 								);
 							}
 							_ => {
@@ -101,65 +202,199 @@ impl ConnectionHandler {
 					tracing::debug!("Starting");
 					if let Err(e) = self.send_start_dt().await {
 						tracing::error!("Error sending startDT: {e}. Reconnecting");
-						self.state.store(
+						self.set_state(
 							ConnectionHandlerState::Reconnecting,
-							std::sync::atomic::Ordering::Relaxed,
+							StateChangeReason::from_error(&e, StateChangeReason::StartDtRejected),
 						);
 						continue;
 					}
 					tracing::debug!("StartDT activation confirmed");
-					self.state.store(
+					self.set_state(
 						ConnectionHandlerState::Started,
-						std::sync::atomic::Ordering::Relaxed,
+						StateChangeReason::StartDtConfirmed,
 					);
+					let reconnected = std::mem::take(&mut self.reconnected);
+					if self.config.interrogation.is_some()
+						|| (reconnected && self.config.reconnect.interrogate)
+					{
+						Self::queue_interrogations(&self.queue, &self.config);
+					}
 				}
 				ConnectionHandlerState::Started => {
-					if let Err(e) = ReceiveHandler::new(
+					let receiving = ReceiveHandler::new(
 						&mut self.read_connection,
 						&mut self.write_connection,
 						self.callback.clone(),
-						self.config.clone(),
-						&mut self.rx,
-						self.out_buffer_full.clone(),
+						self.config.protocol.clone(),
+						Some(&mut self.rx),
+						&mut self.queue_rx,
+						self.connection_tap.as_ref(),
 					)
-					.receive_task()
-					.await
-					{
+					.receive_task();
+					let result = select! {
+						result = receiving => result,
+						() = Self::interrogate_periodically(&self.queue, &self.config) => Ok(()),
+					};
+					if let Err(e) = result {
 						tracing::error!("Error receiving task: {e}. Reconnecting");
-						self.state.store(
+						self.set_state(
 							ConnectionHandlerState::Reconnecting,
-							std::sync::atomic::Ordering::Relaxed,
+							StateChangeReason::from_error(&e, StateChangeReason::ConnectionLost),
 						);
 						continue;
 					}
 					tracing::debug!("Received a stop. Going back to waiting for start");
-					self.state.store(
+					self.set_state(
 						ConnectionHandlerState::WaitingForStart,
-						std::sync::atomic::Ordering::Relaxed,
+						StateChangeReason::StopDt,
 					);
 				}
-				ConnectionHandlerState::Reconnecting => {
-					tracing::debug!("Reconnecting");
-					let Ok(connection) = Self::make_connection(&self.config).await else {
-						tracing::error!("Error making connection");
-						tokio::time::sleep(self.config.protocol.t0).await;
-						continue;
-					};
-					(self.read_connection, self.write_connection) = tokio::io::split(connection);
-					self.state.store(
-						ConnectionHandlerState::Starting,
-						std::sync::atomic::Ordering::Relaxed,
-					);
+				ConnectionHandlerState::Reconnecting => self.reconnect().await?,
+			}
+		}
+	}
+
+	/// Makes one reconnection attempt, on a standby link first. Waits before
+	/// returning if it fails, and fails once the attempts are exhausted.
+	async fn reconnect(&mut self) -> Result<(), Error> {
+		tracing::debug!("Reconnecting");
+		if let Some(connection) = self.take_standby().await {
+			self.set_connection(connection);
+			// The data transfer goes on on the standby whatever the reconnection policy
+			self.reconnected(StateChangeReason::Switchover, true);
+			return Ok(());
+		}
+
+		match Self::make_connection(&self.config, &self.endpoint).await {
+			Ok(connection) => {
+				self.set_connection(connection);
+				self.reconnected(StateChangeReason::Connected, self.config.reconnect.restart);
+			}
+			Err(e) => {
+				tracing::error!("Error making connection: {e}");
+				self.attempts += 1;
+				if self
+					.config
+					.reconnect
+					.max_attempts
+					.is_some_and(|max_attempts| self.attempts >= max_attempts)
+				{
+					self.status.send_replace(ConnectionStatus {
+						state: ConnectionState::Disconnected,
+						reason: StateChangeReason::ReconnectFailed,
+					});
+					whatever!("Giving up reconnecting after {} attempts", self.attempts);
+				}
+				self.set_state(
+					ConnectionHandlerState::Reconnecting,
+					StateChangeReason::from_error(&e, StateChangeReason::ConnectionFailed),
+				);
+				tokio::time::sleep(Self::reconnect_delay(
+					&self.config.reconnect,
+					self.config.protocol.t0,
+					self.attempts,
+				))
+				.await;
+			}
+		}
+		Ok(())
+	}
+
+	/// Moves to the starting state once a new connection is established if the
+	/// data transfer is restarted, or waits for the start otherwise.
+	fn reconnected(&mut self, reason: StateChangeReason, restart: bool) {
+		self.attempts = 0;
+		if restart {
+			self.reconnected = true;
+			self.set_state(ConnectionHandlerState::Starting, reason);
+		} else {
+			self.set_state(ConnectionHandlerState::WaitingForStart, reason);
+		}
+	}
+
+	/// The delay before the next attempt after `attempts` failed ones.
+	fn reconnect_delay(policy: &ReconnectPolicy, t0: Duration, attempts: u32) -> Duration {
+		let exponent = i32::try_from(attempts.saturating_sub(1)).unwrap_or(i32::MAX);
+		let delay =
+			Duration::try_from_secs_f64(t0.as_secs_f64() * policy.backoff_factor.powi(exponent))
+				.map_or(policy.max_delay, |delay| delay.min(policy.max_delay));
+
+		// A new `RandomState` is randomly seeded, which is enough for the jitter
+		let random = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+		let jitter = policy.jitter.clamp(0.0, 1.0) * 2.0_f64.mul_add(random, -1.0);
+		Duration::try_from_secs_f64(delay.as_secs_f64() * (1.0 + jitter)).unwrap_or(delay)
+	}
+
+	/// Queues the configured interrogations, or a general interrogation of
+	/// the broadcast address if none are.
+	fn queue_interrogations(queue: &WeakSendQueue, config: &ClientConfig) {
+		let Some(queue) = queue.upgrade() else {
+			return;
+		};
+		let interrogation = config.interrogation.clone().unwrap_or_default();
+		for &common_address in &interrogation.common_addresses {
+			for &qoi in &interrogation.qualifiers {
+				let asdu = Asdu {
+					type_id: TypeId::C_IC_NA_1,
+					information_objects: InformationObjects::CIcNa1(vec![GenericObject {
+						address: 0,
+						object: CIcNa1 { qoi },
+					}]),
+					originator_address: config.protocol.originator_address,
+					address_field: common_address,
+					sequence: false,
+					test: false,
+					cot: Cot::Activation,
+					positive: false,
+				};
+				// The handler is the only consumer of the queue, waiting for room would
+				// never end
+				if let Err(e) = queue.try_send(asdu, Priority::High, None) {
+					tracing::warn!("Error queuing the interrogation of {common_address}: {e}");
 				}
 			}
 		}
 	}
 
+	/// Queues the configured interrogations every period. Never returns, and
+	/// never wakes up if there is no period.
+	async fn interrogate_periodically(queue: &WeakSendQueue, config: &ClientConfig) {
+		let Some(period) =
+			config.interrogation.as_ref().and_then(|interrogation| interrogation.period)
+		else {
+			return std::future::pending().await;
+		};
+		// The first ones are sent when the data transfer starts
+		let mut interval = tokio::time::interval_at(Instant::now() + period, period);
+		loop {
+			interval.tick().await;
+			Self::queue_interrogations(queue, config);
+		}
+	}
+
+	/// Takes the connection of the first healthy standby link. The endpoint of
+	/// the failed connection becomes a standby link in its place.
+	async fn take_standby(&mut self) -> Option<Connection> {
+		for i in 0..self.standby.len() {
+			if let Some(connection) = self.standby[i].take().await {
+				let link = self.standby.remove(i);
+				tracing::info!("Switching over from {:?} to {:?}", self.endpoint, link.endpoint());
+				let failed = std::mem::replace(&mut self.endpoint, link.endpoint().clone());
+				self.standby.push(StandbyLink::spawn(failed, self.config.clone()));
+				return Some(connection);
+			}
+		}
+		None
+	}
+
 	#[instrument(level = "debug")]
-	async fn make_connection(config: &ClientConfig) -> Result<Connection, Error> {
+	pub(crate) async fn make_connection(
+		config: &ClientConfig,
+		endpoint: &Endpoint,
+	) -> Result<Connection, Error> {
 		let stream = tokio::time::timeout(
 			config.protocol.t0,
-			TcpStream::connect(format!("{}:{}", config.address, config.port)),
+			TcpStream::connect(format!("{}:{}", endpoint.address, endpoint.port)),
 		)
 		.await
 		.whatever_context("Connection timeout")?
@@ -168,10 +403,7 @@ impl ConnectionHandler {
 		Ok(if let Some(ref tls) = config.tls {
 			let connector = Self::make_tls_connector(tls)?;
 			Connection::Tls(
-				connector
-					.connect(&config.address, stream)
-					.await
-					.whatever_context("Error connecting")?,
+				connector.connect(&endpoint.address, stream).await.context(error::TlsHandshake)?,
 			)
 		} else {
 			Connection::Tcp(stream)
@@ -225,16 +457,27 @@ impl ConnectionHandler {
 	#[instrument(level = "debug", skip_all)]
 	pub async fn send_start_dt(&mut self) -> Result<(), Error> {
 		let mut buffer = [0; 255];
-		ReceiveHandler::send_frame(&mut self.write_connection, &START_DT_ACT_FRAME)
-			.await
-			.whatever_context("Error sending startDT activation")?;
+		ReceiveHandler::send_frame(
+			&mut self.write_connection,
+			&START_DT_ACT_FRAME,
+			self.connection_tap.as_ref(),
+		)
+		.await
+		.whatever_context("Error sending startDT activation")?;
 
-		let apdu = tokio::time::timeout(
+		let Ok(apdu) = tokio::time::timeout(
 			self.config.protocol.t1,
-			ReceiveHandler::receive_apdu(&mut self.read_connection, &mut buffer),
+			ReceiveHandler::receive_apdu(
+				&mut self.read_connection,
+				&mut buffer,
+				self.config.protocol.asdu_profile,
+				self.connection_tap.as_ref(),
+			),
 		)
 		.await
-		.whatever_context("Timeout waiting for startDT activation")?;
+		else {
+			return error::T1Timeout { frame: "StartDT confirmation" }.fail();
+		};
 
 		let apdu = apdu.whatever_context("Error receiving APDU")?;
 		if let Frame::U(u) = apdu.frame
@@ -247,3 +490,270 @@ impl ConnectionHandler {
 		Ok(())
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use std::time::{Duration, Instant};
+
+	use tokio::sync::{mpsc, watch};
+
+	use crate::{
+		asdu::Asdu,
+		client::{
+			Client,
+			errors::ClientError,
+			state::{ConnectionState, ConnectionStatus, StateChangeReason},
+		},
+		config::{
+			ClientConfig, Endpoint, InterrogationConfig, ProtocolConfig, ReconnectPolicy,
+			ServerConfig,
+		},
+		cot::Cot,
+		server::Server,
+		test_util::{Commands, Objects, connect_client, server_config, start_server},
+		types::{GenericObject, InformationObjects, MSpNa1},
+		types_id::TypeId,
+	};
+
+	#[tokio::test]
+	async fn test_connection_status() {
+		let (command_tx, _command_rx) = mpsc::unbounded_channel();
+		let mut server = start_server(server_config(), Commands(command_tx)).await;
+		let port = server.local_addr().unwrap().port();
+
+		let (object_tx, _object_rx) = mpsc::unbounded_channel();
+		let mut client =
+			Client::new(ClientConfig { port, ..Default::default() }, Objects(object_tx));
+		let mut status = client.connection_status();
+		assert_eq!(status.borrow().state, ConnectionState::Disconnected);
+
+		client.connect().await.unwrap();
+		assert_eq!(
+			*status.borrow_and_update(),
+			ConnectionStatus {
+				state: ConnectionState::WaitingForStart,
+				reason: StateChangeReason::Connected
+			}
+		);
+
+		client.start_receiving().await.unwrap();
+		let wait_for = |status: &watch::Receiver<ConnectionStatus>, state| {
+			let mut status = status.clone();
+			async move {
+				tokio::time::timeout(
+					Duration::from_secs(5),
+					status.wait_for(|status| status.state == state),
+				)
+				.await
+				.unwrap()
+				.unwrap()
+				.reason
+			}
+		};
+		assert_eq!(
+			wait_for(&status, ConnectionState::Started).await,
+			StateChangeReason::StartDtConfirmed
+		);
+
+		server.stop();
+		assert_eq!(
+			wait_for(&status, ConnectionState::Reconnecting).await,
+			StateChangeReason::ConnectionLost
+		);
+	}
+
+	#[tokio::test]
+	async fn test_redundancy_switchover() {
+		let mut servers = Vec::new();
+		for _ in 0..2 {
+			let (command_tx, _command_rx) = mpsc::unbounded_channel();
+			servers.push(start_server(server_config(), Commands(command_tx)).await);
+		}
+		let standby = servers.pop().unwrap();
+		let mut active = servers.pop().unwrap();
+
+		let (object_tx, mut object_rx) = mpsc::unbounded_channel();
+		let mut client = Client::new(
+			ClientConfig {
+				port: active.local_addr().unwrap().port(),
+				protocol: ProtocolConfig { t0: Duration::from_millis(100), ..Default::default() },
+				// Switching over restarts the data transfer even without restarting it
+				// after the reconnections
+				reconnect: ReconnectPolicy { restart: false, ..Default::default() },
+				redundancy_group: vec![Endpoint {
+					address: "127.0.0.1".to_owned(),
+					port: standby.local_addr().unwrap().port(),
+				}],
+				..Default::default()
+			},
+			Objects(object_tx),
+		);
+		let status = client.connection_status();
+		client.connect().await.unwrap();
+		client.start_receiving().await.unwrap();
+		let wait_for_started = || {
+			let mut status = status.clone();
+			async move {
+				tokio::time::timeout(
+					Duration::from_secs(5),
+					status.wait_for(|status| status.state == ConnectionState::Started),
+				)
+				.await
+				.unwrap()
+				.unwrap();
+			}
+		};
+		wait_for_started().await;
+
+		active.stop();
+		let mut switching = status.clone();
+		tokio::time::timeout(
+			Duration::from_secs(5),
+			switching.wait_for(|status| status.state != ConnectionState::Started),
+		)
+		.await
+		.unwrap()
+		.unwrap();
+		wait_for_started().await;
+
+		// The data transfer continues on the standby server
+		let spontaneous = Asdu {
+			type_id: TypeId::M_SP_NA_1,
+			cot: Cot::SpontaneousData,
+			originator_address: 0,
+			address_field: 1,
+			sequence: false,
+			test: false,
+			positive: false,
+			information_objects: InformationObjects::MSpNa1(vec![GenericObject {
+				address: 1,
+				object: MSpNa1::default(),
+			}]),
+		};
+		let received = tokio::time::timeout(Duration::from_secs(5), async {
+			loop {
+				standby.send_asdu(spontaneous.clone()).unwrap();
+				if let Ok(asdu) =
+					tokio::time::timeout(Duration::from_millis(100), object_rx.recv()).await
+				{
+					break asdu.unwrap();
+				}
+			}
+		})
+		.await
+		.unwrap();
+		assert_eq!(received, spontaneous);
+	}
+
+	#[tokio::test]
+	async fn test_reconnect_policy() {
+		let (command_tx, _command_rx) = mpsc::unbounded_channel();
+		let mut server = start_server(server_config(), Commands(command_tx.clone())).await;
+		let port = server.local_addr().unwrap().port();
+		let (mut client, mut object_rx) = connect_client(
+			port,
+			ClientConfig {
+				protocol: ProtocolConfig { t0: Duration::from_millis(100), ..Default::default() },
+				reconnect: ReconnectPolicy {
+					max_attempts: Some(2),
+					interrogate: true,
+					..Default::default()
+				},
+				..Default::default()
+			},
+		)
+		.await;
+		let mut status = client.connection_status();
+
+		// A new server with a point to interrogate takes the place of the first one
+		server.stop();
+		let mut server =
+			Server::new(ServerConfig { port, ..server_config() }, Commands(command_tx));
+		server
+			.update_points(
+				5,
+				InformationObjects::MSpNa1(vec![GenericObject {
+					address: 1,
+					object: MSpNa1::default(),
+				}]),
+				false,
+			)
+			.unwrap();
+		while server.start().await.is_err() {
+			tokio::time::sleep(Duration::from_millis(10)).await;
+		}
+
+		// The data transfer is restarted and the data image refreshed
+		let interrogated = tokio::time::timeout(Duration::from_secs(5), async {
+			loop {
+				let asdu = object_rx.recv().await.unwrap();
+				if asdu.cot == Cot::InterrogationGeneral {
+					break asdu;
+				}
+			}
+		})
+		.await
+		.unwrap();
+		assert_eq!(interrogated.address_field, 5);
+		assert_eq!(status.borrow().state, ConnectionState::Started);
+
+		// Without a server the client gives up after two attempts
+		server.stop();
+		tokio::time::timeout(
+			Duration::from_secs(5),
+			status.wait_for(|status| status.reason == StateChangeReason::ReconnectFailed),
+		)
+		.await
+		.unwrap()
+		.unwrap();
+		assert_eq!(status.borrow().state, ConnectionState::Disconnected);
+		tokio::time::sleep(Duration::from_millis(50)).await;
+		assert!(matches!(client.start_receiving().await, Err(ClientError::NotConnected { .. })));
+	}
+
+	#[tokio::test]
+	async fn test_automatic_interrogation() {
+		let (command_tx, _command_rx) = mpsc::unbounded_channel();
+		let server = start_server(server_config(), Commands(command_tx)).await;
+		server
+			.update_points(
+				5,
+				InformationObjects::MSpNa1(vec![GenericObject {
+					address: 1,
+					object: MSpNa1::default(),
+				}]),
+				false,
+			)
+			.unwrap();
+		let start = Instant::now();
+		let (_client, mut object_rx) = connect_client(
+			server.local_addr().unwrap().port(),
+			ClientConfig {
+				interrogation: Some(InterrogationConfig {
+					common_addresses: vec![5],
+					period: Some(Duration::from_millis(200)),
+					..Default::default()
+				}),
+				..Default::default()
+			},
+		)
+		.await;
+
+		// Once when the data transfer starts, then periodically
+		for _ in 0..2 {
+			let terminated = tokio::time::timeout(Duration::from_secs(5), async {
+				loop {
+					let asdu = object_rx.recv().await.unwrap();
+					if asdu.cot == Cot::ActivationTermination {
+						break asdu;
+					}
+				}
+			})
+			.await
+			.unwrap();
+			assert_eq!(terminated.type_id, TypeId::C_IC_NA_1);
+			assert_eq!(terminated.address_field, 5);
+		}
+		assert!(start.elapsed() >= Duration::from_millis(200));
+	}
+}
@@ -0,0 +1,148 @@
+use std::sync::{Arc, Mutex, PoisonError};
+
+use async_trait::async_trait;
+use time::OffsetDateTime;
+use tokio::{sync::mpsc, time::Instant};
+
+use crate::{
+	asdu::Asdu,
+	client::{OnNewObjects, subscription::Lagged},
+	config::Cp24Reference,
+};
+
+type Filter = Box<dyn Fn(&Asdu) -> bool + Send + Sync>;
+
+enum Subscriber {
+	/// Used by the transactions of the client, which read every response until
+	/// they complete or time out.
+	Unbounded(mpsc::UnboundedSender<Asdu>),
+	/// Used by the subscriptions of the user. The ASDUs that don't fit are
+	/// dropped and counted, and the count is delivered before the next ASDU
+	/// that fits.
+	Bounded { tx: mpsc::Sender<Result<Asdu, Lagged>>, missed: u64 },
+}
+
+impl Subscriber {
+	fn is_closed(&self) -> bool {
+		match self {
+			Subscriber::Unbounded(tx) => tx.is_closed(),
+			Subscriber::Bounded { tx, .. } => tx.is_closed(),
+		}
+	}
+
+	fn send(&mut self, asdu: &Asdu) {
+		match self {
+			// The receiver might have been dropped since the retain. Nothing to do then
+			Subscriber::Unbounded(tx) => {
+				let _ = tx.send(asdu.clone());
+			}
+			Subscriber::Bounded { tx, missed } => {
+				if *missed > 0 {
+					if tx.try_send(Err(Lagged { missed: *missed })).is_err() {
+						*missed += 1;
+						return;
+					}
+					*missed = 0;
+				}
+				if tx.try_send(Ok(asdu.clone())).is_err() {
+					*missed += 1;
+				}
+			}
+		}
+	}
+}
+
+/// Sits between the receive handler and the user callback. Every received ASDU
+/// is first copied to the subscribers whose filter matches it (e.g. a pending
+/// interrogation waiting for its responses) and then handed to the callback.
+pub(crate) struct Dispatcher {
+	callback: Arc<dyn OnNewObjects + Send + Sync>,
+	subscribers: Mutex<Vec<(Filter, Subscriber)>>,
+	cp24_reference: Option<Cp24Reference>,
+	/// The station clock at the last clock synchronization.
+	station_clock: Mutex<Option<(OffsetDateTime, Instant)>>,
+}
+
+impl Dispatcher {
+	pub fn new(
+		callback: Arc<dyn OnNewObjects + Send + Sync>,
+		cp24_reference: Option<Cp24Reference>,
+	) -> Self {
+		Self {
+			callback,
+			subscribers: Mutex::new(Vec::new()),
+			cp24_reference,
+			station_clock: Mutex::new(None),
+		}
+	}
+
+	/// Records the station clock after a clock synchronization.
+	pub fn set_station_clock(&self, time: OffsetDateTime) {
+		*self.station_clock.lock().unwrap_or_else(PoisonError::into_inner) =
+			Some((time, Instant::now()));
+	}
+
+	/// The reference time to expand the CP24Time2a time tags received now.
+	fn reference_time(&self, reference: Cp24Reference) -> OffsetDateTime {
+		let station_clock = *self.station_clock.lock().unwrap_or_else(PoisonError::into_inner);
+		match (reference, station_clock) {
+			(Cp24Reference::ClockSync, Some((time, at))) => time + at.elapsed(),
+			(Cp24Reference::ClockSync | Cp24Reference::Reception, _) => OffsetDateTime::now_utc(),
+		}
+	}
+
+	/// Receives a copy of every ASDU matching the filter until the receiver is
+	/// dropped.
+	pub fn subscribe(
+		&self,
+		filter: impl Fn(&Asdu) -> bool + Send + Sync + 'static,
+	) -> mpsc::UnboundedReceiver<Asdu> {
+		let (tx, rx) = mpsc::unbounded_channel();
+		self.add_subscriber(Box::new(filter), Subscriber::Unbounded(tx));
+		rx
+	}
+
+	/// Like [`Dispatcher::subscribe`], but at most `capacity` ASDUs are queued
+	/// and the ASDUs received while the queue is full are reported as missed.
+	pub fn subscribe_bounded(
+		&self,
+		capacity: usize,
+		filter: impl Fn(&Asdu) -> bool + Send + Sync + 'static,
+	) -> mpsc::Receiver<Result<Asdu, Lagged>> {
+		let (tx, rx) = mpsc::channel(capacity.max(1));
+		self.add_subscriber(Box::new(filter), Subscriber::Bounded { tx, missed: 0 });
+		rx
+	}
+
+	fn add_subscriber(&self, filter: Filter, subscriber: Subscriber) {
+		self.subscribers.lock().unwrap_or_else(PoisonError::into_inner).push((filter, subscriber));
+	}
+}
+
+#[async_trait]
+impl OnNewObjects for Dispatcher {
+	async fn on_new_objects(&self, asdu: Asdu) {
+		{
+			let mut subscribers = self.subscribers.lock().unwrap_or_else(PoisonError::into_inner);
+			subscribers.retain(|(_, subscriber)| !subscriber.is_closed());
+			for (filter, subscriber) in subscribers.iter_mut() {
+				if filter(&asdu) {
+					subscriber.send(&asdu);
+				}
+			}
+		}
+		match self.cp24_reference {
+			Some(reference) => {
+				let reference = self.reference_time(reference);
+				let times = asdu
+					.information_objects
+					.cp24_times()
+					.into_iter()
+					.map(|time| time.and_then(|time| time.expand(reference).ok()))
+					.collect();
+				self.callback.on_new_objects_with_times(asdu, times).await;
+			}
+			None => self.callback.on_new_objects(asdu).await,
+		}
+	}
+}
@@ -1,8 +1,16 @@
 use snafu::Snafu;
-use tokio::sync::mpsc;
+use tokio::{
+	sync::{mpsc, oneshot},
+	time::error::Elapsed,
+};
 
 use super::connection_handler::ConnectionHandlerCommand;
-use crate::error::SpanTraceWrapper;
+use crate::{
+	cot::Cot,
+	error::SpanTraceWrapper,
+	types::{commands::Rqt, time::ParseTimeError},
+	types_id::TypeId,
+};
 
 #[derive(Debug, Snafu)]
 #[snafu(visibility(pub), context(suffix(false)))]
@@ -33,14 +41,117 @@ pub enum ClientError {
 		#[snafu(implicit)]
 		context: Box<SpanTraceWrapper>,
 	},
-	#[snafu(display("Output buffer is full"))]
+	#[snafu(display("The send queue is full"))]
 	OutputBufferFull {
 		#[snafu(implicit)]
 		context: Box<SpanTraceWrapper>,
 	},
+	#[snafu(display("The connection was lost before the ASDU was acknowledged"))]
+	NotAcknowledged {
+		source: oneshot::error::RecvError,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
 	#[snafu(display("There is no channel to send commands"))]
 	NoWriteChannel {
 		#[snafu(implicit)]
 		context: Box<SpanTraceWrapper>,
 	},
+	#[snafu(display("The request was rejected by the server with cause {cot:?}"))]
+	NegativeConfirmation {
+		cot: Cot,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("The normalized value {value} is out of the range [-1, 1)"))]
+	InvalidNormalizedValue {
+		value: f32,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("{rqt:?} is not a counter interrogation request"))]
+	InvalidRequestQualifier {
+		rqt: Rqt,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display(
+		"The interrogations of the broadcast address are answered by every station, interrogate their common addresses instead"
+	))]
+	BroadcastInterrogation {
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("The {cot:?} of the server carries no {type_id:?} object"))]
+	UnexpectedResponse {
+		type_id: TypeId,
+		cot: Cot,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Timeout waiting for the response of the server"))]
+	Timeout {
+		source: Elapsed,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Commands of type {type_id:?} can't be selected"))]
+	NotSelectable {
+		type_id: TypeId,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("The select timeout elapsed before the execute"))]
+	SelectTimeout {
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("The file {nof} is not ready to be transferred"))]
+	FileNotReady {
+		nof: u16,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("The section {nos} of the file {nof} is not ready to be transferred"))]
+	SectionNotReady {
+		nof: u16,
+		nos: u8,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display(
+		"Checksum mismatch in file {nof}, section {nos}. Expected {expected}, computed {computed}"
+	))]
+	ChecksumMismatch {
+		nof: u16,
+		nos: u8,
+		expected: u8,
+		computed: u8,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Invalid time"))]
+	InvalidTime {
+		source: ParseTimeError,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("No station of the pool has the common address {common_address}"))]
+	UnknownCommonAddress {
+		common_address: u16,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("The station {name} is configured more than once"))]
+	DuplicateStation {
+		name: String,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("The common address {common_address} is assigned to more than one station"))]
+	DuplicateCommonAddress {
+		common_address: u16,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
 }
@@ -0,0 +1,433 @@
+use snafu::{OptionExt as _, ResultExt as _};
+use tokio::sync::mpsc;
+use tracing::instrument;
+
+use crate::{
+	asdu::Asdu,
+	client::{
+		Client,
+		errors::{self, ClientError},
+	},
+	cot::Cot,
+	types::{
+		FDrTa1, FFaNa1, FScNa1, GenericObject, InformationObjects,
+		file_transfer::{Afq, AfqAction, FileError, Lsq, Scq, ScqAction},
+	},
+	types_id::TypeId,
+};
+
+/// Sum modulo 256 of all the octets, as used by the last section and last
+/// segment checksums.
+fn checksum(data: &[u8]) -> u8 {
+	data.iter().fold(0, |sum, byte| sum.wrapping_add(*byte))
+}
+
+impl Client {
+	/// Downloads a file from the server: selects the file, requests it,
+	/// requests and acknowledges every section as it becomes ready and
+	/// acknowledges the file once the checksums are verified.
+	///
+	/// A checksum mismatch is negatively acknowledged to the server before
+	/// failing.
+	#[instrument(level = "debug")]
+	pub async fn download_file(
+		&self,
+		common_address: u16,
+		ioa: u32,
+		nof: u16,
+	) -> Result<Vec<u8>, ClientError> {
+		let mut transfer = FileTransfer::new(self, common_address, ioa, nof);
+
+		transfer.call(0, ScqAction::SelectFile).await?;
+		let lof = loop {
+			if let InformationObjects::FFrNa1(objects) = transfer.next().await?
+				&& let Some(GenericObject { object: ready, .. }) = objects.first()
+			{
+				if ready.frq.negative {
+					return errors::FileNotReady { nof }.fail();
+				}
+				break ready.lof;
+			}
+		};
+
+		transfer.call(0, ScqAction::RequestFile).await?;
+		let mut file = Vec::with_capacity(lof as usize);
+		loop {
+			match transfer.next().await? {
+				InformationObjects::FSrNa1(objects) => {
+					let Some(GenericObject { object: section, .. }) = objects.first() else {
+						continue;
+					};
+					if section.srq.not_ready {
+						return errors::SectionNotReady { nof, nos: section.nos }.fail();
+					}
+					transfer.call(section.nos, ScqAction::RequestSection).await?;
+					file.extend(transfer.receive_section(section.nos).await?);
+				}
+				InformationObjects::FLsNa1(objects) => {
+					let Some(GenericObject { object: last, .. }) = objects.first() else {
+						continue;
+					};
+					if !matches!(
+						last.lsq,
+						Lsq::FileTransferWithoutDeactivation | Lsq::FileTransferWithDeactivation
+					) {
+						tracing::warn!("Unexpected last segment of file {nof}: {last:?}");
+						continue;
+					}
+					transfer
+						.acknowledge(0, AfqAction::FilePositive, checksum(&file), last.chs)
+						.await?;
+					if file.len() != lof as usize {
+						tracing::warn!(
+							"File {nof} was announced with {lof} bytes but {} were received",
+							file.len()
+						);
+					}
+					return Ok(file);
+				}
+				objects => {
+					tracing::warn!("Unexpected objects during file transfer: {objects:?}");
+				}
+			}
+		}
+	}
+
+	/// Calls the directory of the server and returns its entries, in the order
+	/// they were sent, up to the entry flagged as the last file of the
+	/// directory.
+	///
+	/// The address of each entry is the information object address to use
+	/// when selecting the file.
+	#[instrument(level = "debug")]
+	pub async fn list_directory(
+		&self,
+		common_address: u16,
+		ioa: u32,
+	) -> Result<Vec<GenericObject<FDrTa1>>, ClientError> {
+		// The entries carry the address of each file, not the one of the directory
+		let mut responses = self.dispatcher.subscribe(move |asdu| {
+			asdu.address_field == common_address && asdu.type_id == TypeId::F_DR_TA_1
+		});
+
+		self.send_asdu(Asdu {
+			type_id: TypeId::F_SC_NA_1,
+			information_objects: InformationObjects::FScNa1(vec![GenericObject {
+				address: ioa,
+				object: FScNa1 {
+					nof: 0,
+					nos: 0,
+					scq: Scq { action: ScqAction::Default, error: FileError::Default },
+				},
+			}]),
+			originator_address: self.config.protocol.originator_address,
+			address_field: common_address,
+			sequence: false,
+			test: false,
+			cot: Cot::Request,
+			positive: false,
+		})
+		.await?;
+
+		let mut entries = Vec::new();
+		loop {
+			let asdu = tokio::time::timeout(self.config.command_timeout, responses.recv())
+				.await
+				.context(errors::Timeout)?
+				.context(errors::NoWriteChannel)?;
+			if asdu.positive {
+				return errors::NegativeConfirmation { cot: asdu.cot }.fail();
+			}
+			let InformationObjects::FDrTa1(objects) = asdu.information_objects else {
+				continue;
+			};
+			for entry in objects {
+				let last = entry.object.sof.lfd;
+				entries.push(entry);
+				if last {
+					return Ok(entries);
+				}
+			}
+		}
+	}
+}
+
+/// State of a single file transfer.
+struct FileTransfer<'a> {
+	client: &'a Client,
+	common_address: u16,
+	ioa: u32,
+	nof: u16,
+	responses: mpsc::UnboundedReceiver<Asdu>,
+}
+
+impl<'a> FileTransfer<'a> {
+	fn new(client: &'a Client, common_address: u16, ioa: u32, nof: u16) -> Self {
+		let responses = client.dispatcher.subscribe(move |asdu| {
+			asdu.address_field == common_address
+				&& (TypeId::F_FR_NA_1 as u8..=TypeId::F_DR_TA_1 as u8)
+					.contains(&(asdu.type_id as u8))
+				&& asdu.information_objects.addresses().first() == Some(&ioa)
+		});
+		Self { client, common_address, ioa, nof, responses }
+	}
+
+	/// Receives the segments of the section up to its last segment and
+	/// acknowledges it.
+	async fn receive_section(&mut self, nos: u8) -> Result<Vec<u8>, ClientError> {
+		let mut section = Vec::new();
+		loop {
+			match self.next().await? {
+				InformationObjects::FSgNa1(objects) => {
+					for segment in objects {
+						section.extend(segment.object.segment);
+					}
+				}
+				InformationObjects::FLsNa1(objects) => {
+					let Some(GenericObject { object: last, .. }) = objects.first() else {
+						continue;
+					};
+					self.acknowledge(nos, AfqAction::SectionPositive, checksum(&section), last.chs)
+						.await?;
+					return Ok(section);
+				}
+				objects => {
+					tracing::warn!("Unexpected objects during section transfer: {objects:?}");
+				}
+			}
+		}
+	}
+
+	/// Sends a call to the server with the given select and call qualifier.
+	async fn call(&self, nos: u8, action: ScqAction) -> Result<(), ClientError> {
+		self.send(
+			TypeId::F_SC_NA_1,
+			InformationObjects::FScNa1(vec![GenericObject {
+				address: self.ioa,
+				object: FScNa1 {
+					nof: self.nof,
+					nos,
+					scq: Scq { action, error: FileError::Default },
+				},
+			}]),
+		)
+		.await
+	}
+
+	/// Acknowledges the file or section. If the checksums don't match, a
+	/// negative acknowledge is sent instead and an error is returned.
+	async fn acknowledge(
+		&self,
+		nos: u8,
+		positive: AfqAction,
+		computed: u8,
+		expected: u8,
+	) -> Result<(), ClientError> {
+		let afq = if computed == expected {
+			Afq { action: positive, error: FileError::Default }
+		} else {
+			let action = match positive {
+				AfqAction::FilePositive => AfqAction::FileNegative,
+				_ => AfqAction::SectionNegative,
+			};
+			Afq { action, error: FileError::ChecksumFailed }
+		};
+
+		self.send(
+			TypeId::F_FA_NA_1,
+			InformationObjects::FFaNa1(vec![GenericObject {
+				address: self.ioa,
+				object: FFaNa1 { nof: self.nof, nos, afq },
+			}]),
+		)
+		.await?;
+
+		if computed != expected {
+			return errors::ChecksumMismatch { nof: self.nof, nos, expected, computed }.fail();
+		}
+		Ok(())
+	}
+
+	async fn send(
+		&self,
+		type_id: TypeId,
+		information_objects: InformationObjects,
+	) -> Result<(), ClientError> {
+		self.client
+			.send_asdu(Asdu {
+				type_id,
+				information_objects,
+				originator_address: self.client.config.protocol.originator_address,
+				address_field: self.common_address,
+				sequence: false,
+				test: false,
+				cot: Cot::File,
+				positive: false,
+			})
+			.await
+	}
+
+	/// The objects of the next file transfer ASDU from the server.
+	async fn next(&mut self) -> Result<InformationObjects, ClientError> {
+		let asdu = tokio::time::timeout(self.client.config.command_timeout, self.responses.recv())
+			.await
+			.context(errors::Timeout)?
+			.context(errors::NoWriteChannel)?;
+		// The P/N bit is set on negative replies
+		if asdu.positive {
+			return errors::NegativeConfirmation { cot: asdu.cot }.fail();
+		}
+		Ok(asdu.information_objects)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use async_trait::async_trait;
+
+	use super::*;
+	use crate::{
+		server::OnNewCommand,
+		test_util::start,
+		types::{
+			FFrNa1, FLsNa1, FSgNa1, FSrNa1, FromBytes, ToBytes,
+			file_transfer::{Frq, Sof, Srq},
+			time::Cp56Time2a,
+		},
+	};
+
+	fn object<T: FromBytes + ToBytes + Default>(object: T) -> Vec<GenericObject<T>> {
+		vec![GenericObject { address: 1, object }]
+	}
+
+	/// Serves a single file made of the given sections.
+	struct FileServer(Vec<Vec<u8>>);
+
+	#[async_trait]
+	impl OnNewCommand for FileServer {
+		async fn on_new_command(&self, asdu: Asdu) -> Vec<Asdu> {
+			let reply = |information_objects: InformationObjects| Asdu {
+				type_id: information_objects.type_id().unwrap(),
+				information_objects,
+				..asdu.clone()
+			};
+			let section_ready = |nos: u8| {
+				reply(InformationObjects::FSrNa1(object(FSrNa1 {
+					nof: 1,
+					nos,
+					lof: self.0[nos as usize - 1].len() as u32,
+					srq: Srq::default(),
+				})))
+			};
+
+			match &asdu.information_objects {
+				InformationObjects::FScNa1(objects) => match objects[0].object.scq.action {
+					ScqAction::SelectFile => {
+						vec![reply(InformationObjects::FFrNa1(object(FFrNa1 {
+							nof: 1,
+							lof: self.0.iter().map(Vec::len).sum::<usize>() as u32,
+							frq: Frq::default(),
+						})))]
+					}
+					ScqAction::RequestFile => vec![section_ready(1)],
+					ScqAction::RequestSection => {
+						let nos = objects[0].object.nos;
+						let section = &self.0[nos as usize - 1];
+						let mut replies: Vec<Asdu> = section
+							.chunks(2)
+							.map(|segment| {
+								reply(InformationObjects::FSgNa1(object(FSgNa1 {
+									nof: 1,
+									nos,
+									segment: segment.to_vec(),
+								})))
+							})
+							.collect();
+						replies.push(reply(InformationObjects::FLsNa1(object(FLsNa1 {
+							nof: 1,
+							nos,
+							lsq: Lsq::SectionTransferWithoutDeactivation,
+							chs: checksum(section),
+						}))));
+						replies
+					}
+					_ => vec![],
+				},
+				InformationObjects::FFaNa1(objects) => match objects[0].object.afq.action {
+					AfqAction::SectionPositive
+						if (objects[0].object.nos as usize) < self.0.len() =>
+					{
+						vec![section_ready(objects[0].object.nos + 1)]
+					}
+					AfqAction::SectionPositive => {
+						vec![reply(InformationObjects::FLsNa1(object(FLsNa1 {
+							nof: 1,
+							nos: 0,
+							lsq: Lsq::FileTransferWithoutDeactivation,
+							chs: checksum(&self.0.concat()),
+						})))]
+					}
+					_ => vec![],
+				},
+				_ => vec![],
+			}
+		}
+	}
+
+	/// Answers the calls of the directory with three entries, in two ASDUs.
+	struct Directory;
+
+	#[async_trait]
+	impl OnNewCommand for Directory {
+		async fn on_new_command(&self, asdu: Asdu) -> Vec<Asdu> {
+			let entry = |address: u32, lfd: bool| GenericObject {
+				address,
+				object: FDrTa1 {
+					nof: address as u16,
+					lof: address * 1000,
+					sof: Sof { lfd, ..Default::default() },
+					time: Cp56Time2a::default(),
+				},
+			};
+			let entries = |objects: Vec<GenericObject<FDrTa1>>| Asdu {
+				type_id: TypeId::F_DR_TA_1,
+				cot: Cot::Request,
+				sequence: true,
+				information_objects: InformationObjects::FDrTa1(objects),
+				..asdu.clone()
+			};
+			match &asdu.information_objects {
+				InformationObjects::FScNa1(objects)
+					if objects[0].object.scq.action == ScqAction::Default =>
+				{
+					vec![
+						entries(vec![entry(1, false), entry(2, false)]),
+						entries(vec![entry(3, true)]),
+					]
+				}
+				_ => vec![],
+			}
+		}
+	}
+
+	#[tokio::test]
+	async fn test_list_directory() {
+		let (_server, client, _object_rx) = start(Directory).await;
+
+		let directory = client.list_directory(1, 0).await.unwrap();
+		assert_eq!(directory.iter().map(|entry| entry.address).collect::<Vec<_>>(), [1, 2, 3]);
+		assert_eq!(directory[1].object.nof, 2);
+		assert_eq!(directory[2].object.lof, 3000);
+		assert!(directory[2].object.sof.lfd);
+	}
+
+	#[tokio::test]
+	async fn test_download_file() {
+		let sections = vec![vec![1, 2, 3, 4, 5], vec![250, 251, 252]];
+		let (_server, client, _object_rx) = start(FileServer(sections.clone())).await;
+
+		let file = client.download_file(1, 1, 1).await.unwrap();
+
+		assert_eq!(file, sections.concat());
+	}
+}
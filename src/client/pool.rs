@@ -0,0 +1,408 @@
+use std::{
+	collections::{BTreeMap, HashMap, btree_map::Entry},
+	fmt::Debug,
+	pin::Pin,
+	sync::{Arc, Mutex, PoisonError},
+	task::{Context, Poll},
+};
+
+use async_trait::async_trait;
+use futures_core::Stream;
+use futures_util::future::join_all;
+use snafu::{OptionExt as _, ensure};
+use tokio::sync::mpsc;
+use tracing::instrument;
+
+use crate::{
+	asdu::Asdu,
+	client::{
+		Client, OnNewObjects,
+		command::CommandHandle,
+		errors::{self, ClientError},
+		subscription::Lagged,
+	},
+	config::PoolConfig,
+	error::Error,
+};
+
+/// An ASDU received by a [`ClientPool`], tagged with the name of the station
+/// it was received from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StationAsdu {
+	pub station: Arc<str>,
+	pub asdu: Asdu,
+}
+
+/// The ASDUs received from all the stations of a [`ClientPool`], in the order
+/// they were received.
+///
+/// Can be consumed with [`StationAsdus::recv`] or as a [`Stream`]. At most
+/// [`PoolConfig::asdu_capacity`] ASDUs are queued until they are read. The
+/// ASDUs received while the queue is full are dropped, and how many were is
+/// reported with a [`Lagged`] in their place, once there is room again.
+#[derive(Debug)]
+pub struct StationAsdus {
+	asdus: mpsc::Receiver<Result<StationAsdu, Lagged>>,
+}
+
+impl StationAsdus {
+	/// The next ASDU, or how many were dropped at this point of the sequence.
+	/// Returns `None` once the pool is dropped.
+	pub async fn recv(&mut self) -> Option<Result<StationAsdu, Lagged>> {
+		self.asdus.recv().await
+	}
+}
+
+impl Stream for StationAsdus {
+	type Item = Result<StationAsdu, Lagged>;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		self.asdus.poll_recv(cx)
+	}
+}
+
+/// Forwards the ASDUs of a station to the merged stream of the pool.
+#[derive(Debug)]
+struct StationCallback {
+	station: Arc<str>,
+	tx: mpsc::Sender<Result<StationAsdu, Lagged>>,
+	/// The ASDUs dropped since the last one that fit in the stream, shared by
+	/// all the stations so that the count is reported where they were dropped.
+	missed: Arc<Mutex<u64>>,
+}
+
+#[async_trait]
+impl OnNewObjects for StationCallback {
+	async fn on_new_objects(&self, asdu: Asdu) {
+		// The stream might have been dropped, the ASDU is simply discarded then
+		if self.tx.is_closed() {
+			return;
+		}
+		let mut missed = self.missed.lock().unwrap_or_else(PoisonError::into_inner);
+		if *missed > 0 {
+			if self.tx.try_send(Err(Lagged { missed: *missed })).is_err() {
+				*missed += 1;
+				return;
+			}
+			*missed = 0;
+		}
+		if self.tx.try_send(Ok(StationAsdu { station: self.station.clone(), asdu })).is_err() {
+			*missed += 1;
+		}
+	}
+}
+
+/// A set of clients, one per station, built from a single [`PoolConfig`].
+///
+/// The clients run on the runtime the pool is used from. The ASDUs of all the
+/// stations are merged into a single stream, and the outgoing ASDUs are routed
+/// to a station by their common address.
+pub struct ClientPool {
+	stations: BTreeMap<Arc<str>, Client>,
+	routes: HashMap<u16, Arc<str>>,
+	asdus: Option<StationAsdus>,
+}
+
+impl ClientPool {
+	/// Creates a client for every station. The stations are not connected
+	/// yet.
+	///
+	/// Fails if a station name or a common address is configured twice.
+	pub fn new(config: PoolConfig) -> Result<Self, ClientError> {
+		let (tx, rx) = mpsc::channel(usize::from(config.asdu_capacity).max(1));
+		let missed = Arc::new(Mutex::new(0));
+		let mut stations = BTreeMap::new();
+		let mut routes = HashMap::new();
+
+		for station in config.stations {
+			let name: Arc<str> = station.name.into();
+			for common_address in station.common_addresses {
+				ensure!(
+					routes.insert(common_address, name.clone()).is_none(),
+					errors::DuplicateCommonAddress { common_address }
+				);
+			}
+			let Entry::Vacant(entry) = stations.entry(name.clone()) else {
+				return errors::DuplicateStation { name: name.as_ref() }.fail();
+			};
+			entry.insert(Client::new(
+				station.client,
+				StationCallback { station: name, tx: tx.clone(), missed: missed.clone() },
+			));
+		}
+
+		Ok(Self { stations, routes, asdus: Some(StationAsdus { asdus: rx }) })
+	}
+
+	/// Takes the stream of the ASDUs received from all the stations. Returns
+	/// `None` if it was already taken.
+	pub const fn take_asdus(&mut self) -> Option<StationAsdus> {
+		self.asdus.take()
+	}
+
+	/// The names of the stations, in alphabetical order.
+	pub fn stations(&self) -> impl Iterator<Item = &str> {
+		self.stations.keys().map(AsRef::as_ref)
+	}
+
+	/// The client of the station with the given name.
+	#[must_use]
+	pub fn station(&self, name: &str) -> Option<&Client> {
+		self.stations.get(name)
+	}
+
+	/// The client of the station with the given name.
+	#[must_use]
+	pub fn station_mut(&mut self, name: &str) -> Option<&mut Client> {
+		self.stations.get_mut(name)
+	}
+
+	/// The client of the station the common address is routed to.
+	#[must_use]
+	pub fn route(&self, common_address: u16) -> Option<&Client> {
+		self.routes.get(&common_address).and_then(|name| self.stations.get(name))
+	}
+
+	/// Connects all the stations concurrently. Returns the stations that
+	/// failed to connect, with their error.
+	#[instrument(level = "debug")]
+	pub async fn connect(&mut self) -> Vec<(Arc<str>, Error)> {
+		self.for_each_station(Client::connect).await
+	}
+
+	/// Starts the data transfer of all the connected stations concurrently.
+	/// Returns the stations that failed to start, with their error.
+	#[instrument(level = "debug")]
+	pub async fn start_receiving(&mut self) -> Vec<(Arc<str>, ClientError)> {
+		self.for_each_station(Client::start_receiving).await
+	}
+
+	/// Sends the ASDU to the station its common address is routed to.
+	#[instrument(level = "debug")]
+	pub async fn send_asdu(&self, asdu: Asdu) -> Result<(), ClientError> {
+		self.routed(asdu.address_field)?.send_asdu(asdu).await
+	}
+
+	/// Sends the command to the station its common address is routed to. See
+	/// [`Client::send_command`].
+	#[instrument(level = "debug")]
+	pub async fn send_command(&self, asdu: Asdu) -> Result<CommandHandle, ClientError> {
+		self.routed(asdu.address_field)?.send_command(asdu).await
+	}
+
+	fn routed(&self, common_address: u16) -> Result<&Client, ClientError> {
+		self.route(common_address).context(errors::UnknownCommonAddress { common_address })
+	}
+
+	/// Runs the action on every client concurrently, on the task of the
+	/// caller. The clients stay in the pool while the actions run.
+	async fn for_each_station<'a, F, Fut, E>(&'a mut self, action: F) -> Vec<(Arc<str>, E)>
+	where
+		F: Fn(&'a mut Client) -> Fut,
+		Fut: Future<Output = Result<(), E>> + 'a,
+	{
+		join_all(self.stations.iter_mut().map(|(name, client)| {
+			let action = action(client);
+			async move { action.await.err().map(|e| (name.clone(), e)) }
+		}))
+		.await
+		.into_iter()
+		.flatten()
+		.collect()
+	}
+}
+
+impl Debug for ClientPool {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("ClientPool")
+			.field("stations", &self.stations.keys().collect::<Vec<_>>())
+			.field("routes", &self.routes)
+			.finish_non_exhaustive()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+
+	use super::*;
+	use crate::{
+		client::state::ConnectionState,
+		config::{ClientConfig, StationConfig},
+		cot::Cot,
+		server::Server,
+		test_util::{Commands, server_config, start_server},
+		types::{
+			CScNa1, GenericObject, InformationObjects, MSpNa1, commands::Sco,
+			information_elements::Spi,
+		},
+		types_id::TypeId,
+	};
+
+	/// Starts a server per station and returns the configuration of the
+	/// stations, with the servers and the commands they receive.
+	async fn stations(
+		stations: &[(&str, u16)],
+	) -> (Vec<StationConfig>, Vec<(Server, mpsc::UnboundedReceiver<Asdu>)>) {
+		let mut configs = Vec::new();
+		let mut servers = Vec::new();
+		for &(name, common_address) in stations {
+			let (command_tx, command_rx) = mpsc::unbounded_channel();
+			let server = start_server(server_config(), Commands(command_tx)).await;
+			configs.push(StationConfig {
+				name: name.to_owned(),
+				common_addresses: vec![common_address],
+				client: ClientConfig {
+					port: server.local_addr().unwrap().port(),
+					..Default::default()
+				},
+			});
+			servers.push((server, command_rx));
+		}
+		(configs, servers)
+	}
+
+	#[tokio::test]
+	async fn test_client_pool() {
+		let (stations, mut servers) = stations(&[("north", 1), ("south", 2)]).await;
+
+		let duplicated = PoolConfig {
+			stations: vec![stations[0].clone(), stations[0].clone()],
+			asdu_capacity: 1024,
+		};
+		assert!(matches!(
+			ClientPool::new(duplicated),
+			Err(ClientError::DuplicateCommonAddress { common_address: 1, .. })
+		));
+
+		let mut pool = ClientPool::new(PoolConfig { stations, asdu_capacity: 1024 }).unwrap();
+		let mut asdus = pool.take_asdus().unwrap();
+		assert!(pool.take_asdus().is_none());
+		assert_eq!(pool.stations().collect::<Vec<_>>(), ["north", "south"]);
+		assert!(pool.connect().await.is_empty());
+		assert!(pool.start_receiving().await.is_empty());
+		for name in ["north", "south"] {
+			let mut status = pool.station(name).unwrap().connection_status();
+			tokio::time::timeout(
+				Duration::from_secs(5),
+				status.wait_for(|status| status.state == ConnectionState::Started),
+			)
+			.await
+			.unwrap()
+			.unwrap();
+		}
+
+		// Commands are routed by their common address
+		let command = Asdu {
+			type_id: TypeId::C_SC_NA_1,
+			cot: Cot::Activation,
+			originator_address: 1,
+			address_field: 2,
+			sequence: false,
+			test: false,
+			positive: false,
+			information_objects: InformationObjects::CScNa1(vec![GenericObject {
+				address: 10,
+				object: CScNa1 { sco: Sco { scs: Spi::On, ..Default::default() } },
+			}]),
+		};
+		pool.send_command(command.clone()).await.unwrap().confirmation().await.unwrap();
+		assert_eq!(servers[1].1.recv().await.unwrap().address_field, 2);
+		assert!(servers[0].1.try_recv().is_err());
+		assert!(matches!(
+			pool.send_asdu(Asdu { address_field: 3, ..command }).await,
+			Err(ClientError::UnknownCommonAddress { common_address: 3, .. })
+		));
+
+		// The received ASDUs are tagged with their station
+		let spontaneous = Asdu {
+			type_id: TypeId::M_SP_NA_1,
+			cot: Cot::SpontaneousData,
+			originator_address: 0,
+			address_field: 1,
+			sequence: false,
+			test: false,
+			positive: false,
+			information_objects: InformationObjects::MSpNa1(vec![GenericObject {
+				address: 1,
+				object: MSpNa1::default(),
+			}]),
+		};
+		servers[0].0.send_asdu(spontaneous.clone()).unwrap();
+		let received = loop {
+			let received = tokio::time::timeout(Duration::from_secs(5), asdus.recv())
+				.await
+				.unwrap()
+				.unwrap()
+				.unwrap();
+			if received.asdu.type_id == TypeId::M_SP_NA_1 {
+				break received;
+			}
+		};
+		assert_eq!(received, StationAsdu { station: "north".into(), asdu: spontaneous });
+	}
+
+	#[tokio::test]
+	async fn test_station_asdus_lag() {
+		let (tx, rx) = mpsc::channel(2);
+		let mut asdus = StationAsdus { asdus: rx };
+		let missed = Arc::new(Mutex::new(0));
+		let [north, south] = ["north", "south"].map(|station| StationCallback {
+			station: station.into(),
+			tx: tx.clone(),
+			missed: missed.clone(),
+		});
+		let asdu = |ioa| Asdu {
+			type_id: TypeId::M_SP_NA_1,
+			cot: Cot::SpontaneousData,
+			originator_address: 0,
+			address_field: 1,
+			sequence: false,
+			test: false,
+			positive: false,
+			information_objects: InformationObjects::MSpNa1(vec![GenericObject {
+				address: ioa,
+				object: MSpNa1::default(),
+			}]),
+		};
+
+		for ioa in 1..=3 {
+			north.on_new_objects(asdu(ioa)).await;
+			south.on_new_objects(asdu(ioa)).await;
+		}
+		for station in ["north", "south"] {
+			let received = asdus.recv().await.unwrap().unwrap();
+			assert_eq!(received, StationAsdu { station: station.into(), asdu: asdu(1) });
+		}
+		assert!(asdus.asdus.is_empty());
+
+		// The ASDUs missed from all the stations are reported where they were dropped
+		south.on_new_objects(asdu(4)).await;
+		assert_eq!(asdus.recv().await.unwrap(), Err(Lagged { missed: 4 }));
+		let received = asdus.recv().await.unwrap().unwrap();
+		assert_eq!(received, StationAsdu { station: "south".into(), asdu: asdu(4) });
+	}
+
+	#[tokio::test]
+	async fn test_failed_station_stays_in_pool() {
+		let (mut stations, _servers) = stations(&[("north", 1)]).await;
+		// Nothing listens on the port of the second station
+		let unreachable = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+		stations.push(StationConfig {
+			name: "south".to_owned(),
+			common_addresses: vec![2],
+			client: ClientConfig {
+				port: unreachable.local_addr().unwrap().port(),
+				..Default::default()
+			},
+		});
+		drop(unreachable);
+		let mut pool = ClientPool::new(PoolConfig { stations, asdu_capacity: 1024 }).unwrap();
+
+		let failures = pool.connect().await;
+		assert_eq!(failures.len(), 1);
+		assert_eq!(failures[0].0.as_ref(), "south");
+		assert_eq!(pool.stations().collect::<Vec<_>>(), ["north", "south"]);
+		assert!(pool.route(1).is_some() && pool.route(2).is_some());
+	}
+}
@@ -1,29 +1,26 @@
-use std::{
-	collections::VecDeque,
-	pin::Pin,
-	sync::{Arc, atomic::AtomicBool},
-	time::Duration,
-};
+use std::{collections::VecDeque, pin::Pin, sync::Arc, time::Duration};
 
 use lazy_static::lazy_static;
 use snafu::{OptionExt as _, ResultExt as _, whatever};
 use tokio::{
 	io::{AsyncRead, AsyncReadExt as _, AsyncWrite, AsyncWriteExt as _, ReadHalf, WriteHalf},
 	select,
-	sync::mpsc,
+	sync::{mpsc, oneshot},
 	time::Instant,
 };
 use tracing::instrument;
 
 use crate::{
 	apdu::{APUD_MAX_LENGTH, Apdu, Frame, IFrame, SFrame, TELEGRAN_HEADER, UFrame},
-	asdu::Asdu,
+	asdu::{Asdu, AsduProfile},
+	capture::tap::ConnectionTap,
 	client::{
 		Connection, OnNewObjects, START_DT_CON_FRAME, STOP_DT_ACT_FRAME, STOP_DT_CON_FRAME,
 		TEST_FR_ACT_FRAME, TEST_FR_CON_FRAME, connection_handler::ConnectionHandlerCommand,
+		send_queue::SendQueueReceiver,
 	},
-	config::ClientConfig,
-	error::Error,
+	config::ProtocolConfig,
+	error::{self, Error},
 };
 
 lazy_static! {
@@ -34,18 +31,24 @@ pub struct ReceiveHandler<'a> {
 	read_connection: &'a mut ReadHalf<Connection>,
 	write_connection: &'a mut WriteHalf<Connection>,
 	callback: Arc<dyn OnNewObjects + Send + Sync>,
-	config: ClientConfig,
-	rx: &'a mut mpsc::Receiver<ConnectionHandlerCommand>,
-	out_buffer_full: Arc<AtomicBool>,
+	config: ProtocolConfig,
+	/// The commands of the connection handler, if it sends any.
+	rx: Option<&'a mut mpsc::Receiver<ConnectionHandlerCommand>>,
+	queue: &'a mut SendQueueReceiver,
 	t1_u: Pin<Box<tokio::time::Sleep>>,
 	t1_i: Pin<Box<tokio::time::Sleep>>,
 	t2: Pin<Box<tokio::time::Sleep>>,
 	t3: Pin<Box<tokio::time::Sleep>>,
 	unacknowledged_seq_num: VecDeque<(u16, Instant)>,
+	/// The senders waiting for the acknowledge of their I-frame, by the
+	/// sequence number stored in `unacknowledged_seq_num`.
+	acknowledgements: VecDeque<(u16, oneshot::Sender<()>)>,
 	sent_counter: u16,
 	received_counter: u16,
 	unacknowledged_rcv_frames: u16,
 	outstanding_test_fr_con_messages: u16,
+	/// When set, where the sent and received APDUs are captured.
+	tap: Option<&'a ConnectionTap>,
 }
 
 impl<'a> ReceiveHandler<'a> {
@@ -53,25 +56,28 @@ impl<'a> ReceiveHandler<'a> {
 		read_connection: &'a mut ReadHalf<Connection>,
 		write_connection: &'a mut WriteHalf<Connection>,
 		callback: Arc<dyn OnNewObjects + Send + Sync>,
-		config: ClientConfig,
-		rx: &'a mut mpsc::Receiver<ConnectionHandlerCommand>,
-		out_buffer_full: Arc<AtomicBool>,
+		config: ProtocolConfig,
+		rx: Option<&'a mut mpsc::Receiver<ConnectionHandlerCommand>>,
+		queue: &'a mut SendQueueReceiver,
+		tap: Option<&'a ConnectionTap>,
 	) -> Self {
 		Self {
 			read_connection,
 			write_connection,
 			callback,
 			rx,
-			out_buffer_full,
+			queue,
 			t1_u: Box::pin(tokio::time::sleep(*TIMER_UNSET)),
 			t1_i: Box::pin(tokio::time::sleep(*TIMER_UNSET)),
 			t2: Box::pin(tokio::time::sleep(*TIMER_UNSET)),
 			t3: Box::pin(tokio::time::sleep(*TIMER_UNSET)),
-			unacknowledged_seq_num: VecDeque::with_capacity(config.protocol.k as usize),
+			unacknowledged_seq_num: VecDeque::with_capacity(config.k as usize),
+			acknowledgements: VecDeque::new(),
 			sent_counter: 0,
 			received_counter: 0,
 			unacknowledged_rcv_frames: 0,
 			outstanding_test_fr_con_messages: 0,
+			tap,
 			config,
 		}
 	}
@@ -80,15 +86,15 @@ impl<'a> ReceiveHandler<'a> {
 	pub async fn send_frame<W: AsyncWrite + Unpin>(
 		write_connection: &mut W,
 		frame: &Frame,
+		tap: Option<&ConnectionTap>,
 	) -> Result<(), Error> {
-		write_connection
-			.write_all(
-				&frame
-					.to_apdu_bytes()
-					.whatever_context("Error converting frame to APDU and encoding")?,
-			)
-			.await
-			.whatever_context("Error sending data")?;
+		let bytes = frame
+			.to_apdu_bytes()
+			.whatever_context("Error converting frame to APDU and encoding")?;
+		write_connection.write_all(&bytes).await.whatever_context("Error sending data")?;
+		if let Some(tap) = tap {
+			tap.sent(&bytes);
+		}
 		Ok(())
 	}
 
@@ -96,8 +102,10 @@ impl<'a> ReceiveHandler<'a> {
 	pub async fn receive_apdu<R: AsyncRead + Unpin>(
 		connection: &mut R,
 		buffer: &mut [u8; 255],
+		profile: AsduProfile,
+		tap: Option<&ConnectionTap>,
 	) -> Result<Apdu, Error> {
-		connection.read(&mut buffer[0..2]).await.whatever_context("Error receiving data")?;
+		connection.read_exact(&mut buffer[0..2]).await.whatever_context("Error receiving data")?;
 		if buffer[0] != TELEGRAN_HEADER {
 			whatever!("Invalid starter byte: {:02x}{:02x}", buffer[0], buffer[1]);
 		}
@@ -109,18 +117,22 @@ impl<'a> ReceiveHandler<'a> {
 			.read_exact(&mut buffer[2..length + 2])
 			.await
 			.whatever_context("Error receiving data")?;
-		Apdu::from_bytes(&buffer[0..length + 2]).whatever_context("Error decoding APDU")
+		if let Some(tap) = tap {
+			tap.received(&buffer[0..length + 2]);
+		}
+		Apdu::from_bytes_with_profile(&buffer[0..length + 2], profile)
+			.whatever_context("Error decoding APDU")
 	}
 
 	#[instrument(level = "debug", skip_all)]
 	pub async fn receive_task(mut self) -> Result<(), Error> {
-		self.t3.as_mut().reset(Instant::now() + self.config.protocol.t3);
+		self.t3.as_mut().reset(Instant::now() + self.config.t3);
 
 		let mut buffer = [0; 255];
 
 		loop {
 			select! {
-				apdu = Self::receive_apdu(&mut self.read_connection,	&mut buffer) => {
+				apdu = Self::receive_apdu(&mut self.read_connection, &mut buffer, self.config.asdu_profile, self.tap) => {
 					if let Ok(apdu) = apdu {
 						match apdu.frame {
 							Frame::I(i) => {
@@ -137,28 +149,32 @@ impl<'a> ReceiveHandler<'a> {
 								}
 							}
 						}
-						self.t3.as_mut().reset(Instant::now() + self.config.protocol.t3);
+						self.t3.as_mut().reset(Instant::now() + self.config.t3);
 					} else {
 						whatever!("Error receiving APDU");
 					}
 				}
-				Some(cmd) = self.rx.recv() => {
+				// Only take new ASDUs while the k window is open. The ones left in the queue
+				// are sent once the peer acknowledges the outstanding I-frames.
+				Some(queued) = self.queue.recv(), if self.unacknowledged_seq_num.len() < self.config.k as usize => {
+					let sequence_number = self.handle_send_asdu(queued.asdu).await.whatever_context("Error sending command")?;
+					if let Some(acknowledged) = queued.acknowledged {
+						self.acknowledgements.push_back((sequence_number, acknowledged));
+					}
+					self.t2.as_mut().reset(self.unacknowledged_seq_num.front().whatever_context("Unacknowledged sequence number is empty")?.1 + self.config.t2);
+					self.t1_i.as_mut().reset(self.unacknowledged_seq_num.front().map_or(Instant::now() + *TIMER_UNSET, |(_, time)| *time + self.config.t1));
+				}
+				Some(cmd) = Self::recv_command(&mut self.rx) => {
 					match cmd {
-						ConnectionHandlerCommand::Asdu(asdu) => {
-							Self::handle_send_asdu(asdu, &mut self.sent_counter, self.received_counter, self.write_connection, &mut self.unacknowledged_seq_num, self.config.protocol.k, &mut self.unacknowledged_rcv_frames).await.whatever_context("Error sending command")?;
-							self.out_buffer_full.store(self.unacknowledged_seq_num.len() >= self.config.protocol.k as usize, std::sync::atomic::Ordering::Relaxed);
-							self.t2.as_mut().reset(self.unacknowledged_seq_num.front().whatever_context("Unacknowledged sequence number is empty")?.1 + self.config.protocol.t2);
-							self.t1_i.as_mut().reset(self.unacknowledged_seq_num.front().map_or(Instant::now() + *TIMER_UNSET, |(_, time)| *time + self.config.protocol.t1));
-						}
 						ConnectionHandlerCommand::Stop => {
-							Self::send_frame(&mut self.write_connection, &STOP_DT_ACT_FRAME).await.whatever_context("Error sending stopDT activation")?;
+							Self::send_frame(&mut self.write_connection, &STOP_DT_ACT_FRAME, self.tap).await.whatever_context("Error sending stopDT activation")?;
 							self.confirm_all_messages().await.whatever_context("Error confirming all messages")?;
-							self.t1_u.as_mut().reset(Instant::now() + self.config.protocol.t1);
+							self.t1_u.as_mut().reset(Instant::now() + self.config.t1);
 						},
 						ConnectionHandlerCommand::Test => {
 							self.send_test_frame().await.whatever_context("Error sending test frame")?;
 						},
-						_ => {
+						ConnectionHandlerCommand::Start => {
 							tracing::error!("Received unexpected command: {cmd:?}");
 						}
 					}
@@ -172,89 +188,94 @@ impl<'a> ReceiveHandler<'a> {
 					self.confirm_all_messages().await.whatever_context("Error confirming all messages")?;
 				}
 				_ = &mut self.t1_u => {
-					whatever!("t1 for u frames timeout");
+					return error::T1Timeout { frame: "U-frame confirmation" }.fail();
 				}
 				_ = &mut self.t1_i => {
-					whatever!("t1 for i frames timeout");
+					return error::T1Timeout { frame: "I-frame acknowledge" }.fail();
 				}
 			}
-			if self.unacknowledged_rcv_frames > self.config.protocol.w {
+			if self.unacknowledged_rcv_frames > self.config.w {
 				tracing::debug!(
 					"Received more than w frames without acknowledgement. Sending S frame"
 				);
 				Self::send_frame(
 					&mut self.write_connection,
 					&Frame::S(SFrame { receive_sequence_number: self.received_counter }),
+					self.tap,
 				)
 				.await
 				.whatever_context("Error sending S frame")?;
 				self.unacknowledged_rcv_frames = 0;
-				self.t2.as_mut().reset(Instant::now() + self.config.protocol.t2);
+				self.t2.as_mut().reset(Instant::now() + self.config.t2);
 			}
 		}
 	}
 
-	#[instrument(level = "debug")]
-	async fn handle_send_asdu(
-		asdu: Asdu,
-		sent_counter: &mut u16,
-		received_counter: u16,
-		write_connection: &mut WriteHalf<Connection>,
-		unacknowledged_seq_num: &mut VecDeque<(u16, Instant)>,
-		k: u16,
-		unacknowledged_rcv_frames: &mut u16,
-	) -> Result<(), Error> {
+	/// The next command of the connection handler. Never returns without one.
+	async fn recv_command(
+		rx: &mut Option<&'a mut mpsc::Receiver<ConnectionHandlerCommand>>,
+	) -> Option<ConnectionHandlerCommand> {
+		match rx {
+			Some(rx) => rx.recv().await,
+			None => std::future::pending().await,
+		}
+	}
+
+	/// Sends the ASDU in an I-frame. Returns the sequence number the frame is
+	/// tracked with until it's acknowledged.
+	#[instrument(level = "debug", skip(self))]
+	async fn handle_send_asdu(&mut self, asdu: Asdu) -> Result<u16, Error> {
 		let frame = Frame::I(IFrame {
-			send_sequence_number: *sent_counter,
-			receive_sequence_number: received_counter,
+			send_sequence_number: self.sent_counter,
+			receive_sequence_number: self.received_counter,
 			asdu,
 		});
 
-		Self::send_frame(write_connection, &frame)
-			.await
-			.whatever_context("Error sending command")?;
+		let bytes = frame
+			.to_apdu_bytes_with_profile(self.config.asdu_profile)
+			.whatever_context("Error converting frame to APDU and encoding")?;
+		self.write_connection.write_all(&bytes).await.whatever_context("Error sending command")?;
+		if let Some(tap) = self.tap {
+			tap.sent(&bytes);
+		}
 
 		// The modulo is to avoid overflow
-		*sent_counter = (*sent_counter + 1) % 32768;
+		self.sent_counter = (self.sent_counter + 1) % 32768;
 
-		if unacknowledged_seq_num.len() < k as usize {
-			unacknowledged_seq_num.push_back((*sent_counter, Instant::now()));
+		if self.unacknowledged_seq_num.len() < self.config.k as usize {
+			self.unacknowledged_seq_num.push_back((self.sent_counter, Instant::now()));
 		} else {
 			whatever!("Unacknowledged sequence number is full. Closing connection");
 		}
 
-		*unacknowledged_rcv_frames = 0;
+		self.unacknowledged_rcv_frames = 0;
 
-		Ok(())
+		Ok(self.sent_counter)
 	}
 
 	#[instrument(level = "debug", skip_all)]
 	fn handle_receive_i_frame(&mut self, i: &IFrame) -> Result<(), Error> {
 		tracing::debug!("Received I frame: {i:?}");
 		if i.send_sequence_number != self.received_counter {
-			whatever!(
-				"Received I frame with wrong sequence number. Expected: {}, Received: {}",
-				self.received_counter,
-				i.send_sequence_number
-			);
+			return error::SequenceNumber {
+				expected: self.received_counter,
+				received: i.send_sequence_number,
+			}
+			.fail();
 		}
 
 		Self::check_sequence_acknowledge(
 			&mut self.unacknowledged_seq_num,
 			i.receive_sequence_number,
 			self.sent_counter,
-		)
-		.whatever_context("Error checking sequence acknowledge")?;
+		)?;
 
-		self.out_buffer_full.store(
-			self.unacknowledged_seq_num.len() >= self.config.protocol.k as usize,
-			std::sync::atomic::Ordering::Relaxed,
-		);
+		self.notify_acknowledged();
 
 		self.t1_i.as_mut().reset(
 			self.unacknowledged_seq_num
 				.front()
-				.map_or(Instant::now() + *TIMER_UNSET, |(_, time)| *time + self.config.protocol.t1),
+				.map_or(Instant::now() + *TIMER_UNSET, |(_, time)| *time + self.config.t1),
 		);
 
 		// The modulo is to avoid overflow
@@ -271,38 +292,47 @@ impl<'a> ReceiveHandler<'a> {
 			&mut self.unacknowledged_seq_num,
 			s.receive_sequence_number,
 			self.sent_counter,
-		)
-		.whatever_context("Error checking sequence acknowledge")?;
+		)?;
 
-		self.out_buffer_full.store(
-			self.unacknowledged_seq_num.len() >= self.config.protocol.k as usize,
-			std::sync::atomic::Ordering::Relaxed,
-		);
+		self.notify_acknowledged();
 
 		self.t1_i.as_mut().reset(
 			self.unacknowledged_seq_num
 				.front()
-				.map_or(Instant::now() + *TIMER_UNSET, |(_, time)| *time + self.config.protocol.t1),
+				.map_or(Instant::now() + *TIMER_UNSET, |(_, time)| *time + self.config.t1),
 		);
 		Ok(())
 	}
 
+	/// Notifies the senders of the I-frames that are no longer waiting for an
+	/// acknowledge.
+	fn notify_acknowledged(&mut self) {
+		while let Some((sequence_number, _)) = self.acknowledgements.front()
+			&& !self.unacknowledged_seq_num.iter().any(|(seq, _)| seq == sequence_number)
+		{
+			if let Some((_, acknowledged)) = self.acknowledgements.pop_front() {
+				// The sender might not be waiting anymore
+				let _ = acknowledged.send(());
+			}
+		}
+	}
+
 	#[instrument(level = "debug", skip_all)]
 	async fn handle_receive_u_frame(&mut self, u: &UFrame) -> Result<bool, Error> {
 		tracing::debug!("Received U frame: {u:?}");
 		if u.test_fr_activation {
-			Self::send_frame(&mut self.write_connection, &TEST_FR_CON_FRAME)
+			Self::send_frame(&mut self.write_connection, &TEST_FR_CON_FRAME, self.tap)
 				.await
 				.whatever_context("Error sending test frame")?;
 		} else if u.start_dt_activation {
 			tracing::debug!("StartDT activation");
 			//TODO: We already stated. We shouldn't be receiving this frame
 			//TODO: What else should we do here?
-			Self::send_frame(&mut self.write_connection, &START_DT_CON_FRAME)
+			Self::send_frame(&mut self.write_connection, &START_DT_CON_FRAME, self.tap)
 				.await
 				.whatever_context("Error sending test frame")?;
 		} else if u.stop_dt_activation {
-			Self::send_frame(&mut self.write_connection, &STOP_DT_CON_FRAME)
+			Self::send_frame(&mut self.write_connection, &STOP_DT_CON_FRAME, self.tap)
 				.await
 				.whatever_context("Error sending test frame")?;
 			return Ok(true);
@@ -325,12 +355,12 @@ impl<'a> ReceiveHandler<'a> {
 				"Outstanding test frame confirmation messages is greater than 2. Closing connection"
 			);
 		}
-		Self::send_frame(&mut self.write_connection, &TEST_FR_ACT_FRAME)
+		Self::send_frame(&mut self.write_connection, &TEST_FR_ACT_FRAME, self.tap)
 			.await
 			.whatever_context("Error sending test frame")?;
 		self.outstanding_test_fr_con_messages += 1;
-		self.t3.as_mut().reset(Instant::now() + self.config.protocol.t3);
-		self.t1_u.as_mut().reset(Instant::now() + self.config.protocol.t1);
+		self.t3.as_mut().reset(Instant::now() + self.config.t3);
+		self.t1_u.as_mut().reset(Instant::now() + self.config.t1);
 		Ok(())
 	}
 
@@ -377,15 +407,10 @@ impl<'a> ReceiveHandler<'a> {
 			if let Some(i) = i {
 				unacknowledged_seq_num.drain(0..=i);
 				return Ok(());
-			} else {
-				whatever!(
-					"Received frame with sequence number that is not in the unacknowledged list"
-				);
 			}
 		}
 
-		//TODO: Fix it
-		whatever!("Received frame with invalid sequence number");
+		error::InvalidAcknowledge { received: frame_rss }.fail()
 	}
 
 	#[instrument(level = "debug", skip_all)]
@@ -393,6 +418,7 @@ impl<'a> ReceiveHandler<'a> {
 		Self::send_frame(
 			&mut self.write_connection,
 			&Frame::S(SFrame { receive_sequence_number: self.received_counter }),
+			self.tap,
 		)
 		.await
 		.whatever_context("Error sending S frame")?;
@@ -406,6 +432,26 @@ impl<'a> ReceiveHandler<'a> {
 mod tests {
 	use super::*;
 
+	#[tokio::test]
+	async fn test_receive_split_header() {
+		let (mut writer, mut reader) = tokio::io::duplex(64);
+		// The header arrives in two segments
+		let write = tokio::spawn(async move {
+			writer.write_all(&[TELEGRAN_HEADER]).await.unwrap();
+			tokio::time::sleep(Duration::from_millis(50)).await;
+			writer.write_all(&[4, 0x43, 0, 0, 0]).await.unwrap();
+			writer
+		});
+
+		let mut buffer = [0; 255];
+		let apdu =
+			ReceiveHandler::receive_apdu(&mut reader, &mut buffer, AsduProfile::IEC_104, None)
+				.await
+				.unwrap();
+		assert_eq!(apdu.frame, *TEST_FR_ACT_FRAME);
+		drop(write.await.unwrap());
+	}
+
 	#[test]
 	fn test_empty_buffer_valid_sequence() {
 		let mut k_buffer = VecDeque::new();
@@ -0,0 +1,163 @@
+use snafu::{ResultExt as _, whatever};
+use tokio::{
+	io::AsyncReadExt as _,
+	select,
+	sync::{mpsc, oneshot},
+	task::JoinHandle,
+	time::{Instant, sleep_until},
+};
+use tracing::instrument;
+
+use crate::{
+	apdu::{APUD_MAX_LENGTH, Apdu, Frame, TELEGRAN_HEADER},
+	client::{
+		Connection, TEST_FR_ACT_FRAME, TEST_FR_CON_FRAME, connection_handler::ConnectionHandler,
+		receive_handler::ReceiveHandler,
+	},
+	config::{ClientConfig, Endpoint},
+	error::Error,
+};
+
+type TakeRequest = oneshot::Sender<Connection>;
+
+/// A standby connection of a redundancy group.
+///
+/// The connection is kept open without starting the data transfer and is
+/// tested with TESTFR every t3. It's reestablished after t0 if it fails.
+#[derive(Debug)]
+pub(crate) struct StandbyLink {
+	endpoint: Endpoint,
+	take_tx: mpsc::Sender<TakeRequest>,
+	task: JoinHandle<()>,
+}
+
+impl StandbyLink {
+	pub fn spawn(endpoint: Endpoint, config: ClientConfig) -> Self {
+		let (take_tx, take_rx) = mpsc::channel(1);
+		let task = tokio::spawn(Self::run(endpoint.clone(), config, take_rx));
+		Self { endpoint, take_tx, task }
+	}
+
+	pub const fn endpoint(&self) -> &Endpoint {
+		&self.endpoint
+	}
+
+	/// Takes over the connection to start the data transfer on it. Returns
+	/// `None` if the link is down or its last test is still unconfirmed. Once
+	/// taken, the link doesn't reconnect anymore.
+	pub async fn take(&self) -> Option<Connection> {
+		let (tx, rx) = oneshot::channel();
+		self.take_tx.send(tx).await.ok()?;
+		rx.await.ok()
+	}
+
+	#[instrument(level = "debug", skip(config, take_rx))]
+	async fn run(
+		endpoint: Endpoint,
+		config: ClientConfig,
+		mut take_rx: mpsc::Receiver<TakeRequest>,
+	) {
+		loop {
+			let connecting = ConnectionHandler::make_connection(&config, &endpoint);
+			tokio::pin!(connecting);
+			let connection = loop {
+				select! {
+					connection = &mut connecting => break connection,
+					// Not connected, the requests are dropped unanswered
+					request = take_rx.recv() => if request.is_none() { return },
+				}
+			};
+
+			match connection {
+				Ok(connection) => match Self::keep_alive(connection, &config, &mut take_rx).await {
+					Ok(()) => return,
+					Err(e) => tracing::warn!("Standby connection to {endpoint:?} failed: {e}"),
+				},
+				Err(e) => tracing::warn!("Error making standby connection to {endpoint:?}: {e}"),
+			}
+
+			let retry = sleep_until(Instant::now() + config.protocol.t0);
+			tokio::pin!(retry);
+			loop {
+				select! {
+					() = &mut retry => break,
+					request = take_rx.recv() => if request.is_none() { return },
+				}
+			}
+		}
+	}
+
+	/// Tests the connection until it fails or is taken. Returns `Ok` once the
+	/// connection is handed over or the link is dropped.
+	async fn keep_alive(
+		mut connection: Connection,
+		config: &ClientConfig,
+		take_rx: &mut mpsc::Receiver<TakeRequest>,
+	) -> Result<(), Error> {
+		// Bytes are read into a buffer because a frame might be interrupted by a
+		// take request
+		let mut pending = Vec::with_capacity(2 * APUD_MAX_LENGTH as usize);
+		let mut chunk = [0; APUD_MAX_LENGTH as usize + 2];
+		let mut next_test = Instant::now() + config.protocol.t3;
+		let mut test_deadline: Option<Instant> = None;
+
+		loop {
+			select! {
+				request = take_rx.recv() => {
+					let Some(reply) = request else { return Ok(()) };
+					if test_deadline.is_none() {
+						// If the requester is gone the connection is simply closed
+						let _ = reply.send(connection);
+						return Ok(());
+					}
+				}
+				read = connection.read(&mut chunk) => {
+					let read = read.whatever_context("Error receiving data")?;
+					if read == 0 {
+						whatever!("Connection closed by the server");
+					}
+					pending.extend_from_slice(&chunk[..read]);
+					while let Some(apdu) = Self::next_apdu(&mut pending, config)? {
+						match apdu.frame {
+							Frame::U(u) if u.test_fr_activation => {
+								ReceiveHandler::send_frame(&mut connection, &TEST_FR_CON_FRAME, None).await?;
+							}
+							Frame::U(u) if u.test_fr_confirmation => test_deadline = None,
+							frame => tracing::warn!("Unexpected frame on standby connection: {frame:?}"),
+						}
+					}
+				}
+				() = sleep_until(next_test) => {
+					ReceiveHandler::send_frame(&mut connection, &TEST_FR_ACT_FRAME, None).await?;
+					test_deadline = Some(Instant::now() + config.protocol.t1);
+					next_test = Instant::now() + config.protocol.t3;
+				}
+				() = sleep_until(test_deadline.unwrap_or(next_test)), if test_deadline.is_some() => {
+					whatever!("t1 timeout waiting for the TESTFR confirmation");
+				}
+			}
+		}
+	}
+
+	/// Removes the first complete APDU from the buffer.
+	fn next_apdu(pending: &mut Vec<u8>, config: &ClientConfig) -> Result<Option<Apdu>, Error> {
+		let [header, length, ..] = pending[..] else { return Ok(None) };
+		if header != TELEGRAN_HEADER {
+			whatever!("Invalid starter byte: {header:02x}");
+		}
+		let length = length as usize + 2;
+		if pending.len() < length {
+			return Ok(None);
+		}
+		let apdu = Apdu::from_bytes_with_profile(&pending[..length], config.protocol.asdu_profile)
+			.whatever_context("Error decoding APDU")?;
+		pending.drain(..length);
+		Ok(Some(apdu))
+	}
+}
+
+impl Drop for StandbyLink {
+	fn drop(&mut self) {
+		self.task.abort();
+	}
+}
@@ -0,0 +1,221 @@
+use tokio::{
+	select,
+	sync::{
+		mpsc::{self, error::TrySendError},
+		oneshot,
+	},
+};
+
+use crate::{asdu::Asdu, types_id::TypeId};
+
+/// The order ASDUs waiting in the send queue are sent in. Within a priority
+/// they are sent in the order they were queued.
+///
+/// The client queues the ASDUs with the priority of their type. The server
+/// queues the responses to the commands as [`Priority::High`], to keep them in
+/// order, and the spontaneous data as [`Priority::Normal`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+	/// Commands, system commands and parameters, in control direction.
+	High,
+	/// Monitoring and file transfer data.
+	Normal,
+}
+
+impl Priority {
+	/// The priority the client queues ASDUs of the type with.
+	#[must_use]
+	pub const fn of(type_id: TypeId) -> Self {
+		match type_id as u8 {
+			45..=69 | 100..=119 => Priority::High,
+			_ => Priority::Normal,
+		}
+	}
+}
+
+/// An ASDU waiting to be sent, with who to notify once the peer acknowledges
+/// it.
+#[derive(Debug)]
+pub(crate) struct QueuedAsdu {
+	pub asdu: Asdu,
+	pub acknowledged: Option<oneshot::Sender<()>>,
+}
+
+/// The sending side of the queue of the ASDUs to send on a connection.
+#[derive(Debug, Clone)]
+pub(crate) struct SendQueue {
+	high: mpsc::Sender<QueuedAsdu>,
+	normal: mpsc::Sender<QueuedAsdu>,
+}
+
+/// A [`SendQueue`] that doesn't keep the queue open.
+#[derive(Debug, Clone)]
+pub(crate) struct WeakSendQueue {
+	high: mpsc::WeakSender<QueuedAsdu>,
+	normal: mpsc::WeakSender<QueuedAsdu>,
+}
+
+/// The receiving side of the queue, taking the ASDUs by priority.
+#[derive(Debug)]
+pub(crate) struct SendQueueReceiver {
+	high: mpsc::Receiver<QueuedAsdu>,
+	normal: mpsc::Receiver<QueuedAsdu>,
+}
+
+/// Creates a queue holding up to `capacity` ASDUs of each priority, and at
+/// least one.
+pub(crate) fn send_queue(capacity: usize) -> (SendQueue, SendQueueReceiver) {
+	let (high_tx, high_rx) = mpsc::channel(capacity.max(1));
+	let (normal_tx, normal_rx) = mpsc::channel(capacity.max(1));
+	(
+		SendQueue { high: high_tx, normal: normal_tx },
+		SendQueueReceiver { high: high_rx, normal: normal_rx },
+	)
+}
+
+impl SendQueue {
+	const fn sender(&self, priority: Priority) -> &mpsc::Sender<QueuedAsdu> {
+		match priority {
+			Priority::High => &self.high,
+			Priority::Normal => &self.normal,
+		}
+	}
+
+	/// Queues the ASDU, waiting for room if the queue is full.
+	pub async fn send(
+		&self,
+		asdu: Asdu,
+		priority: Priority,
+		acknowledged: Option<oneshot::Sender<()>>,
+	) -> Result<(), mpsc::error::SendError<QueuedAsdu>> {
+		self.sender(priority).send(QueuedAsdu { asdu, acknowledged }).await
+	}
+
+	/// Queues the ASDU, failing if the queue is full.
+	pub fn try_send(
+		&self,
+		asdu: Asdu,
+		priority: Priority,
+		acknowledged: Option<oneshot::Sender<()>>,
+	) -> Result<(), TrySendError<QueuedAsdu>> {
+		self.sender(priority).try_send(QueuedAsdu { asdu, acknowledged })
+	}
+
+	pub fn downgrade(&self) -> WeakSendQueue {
+		WeakSendQueue { high: self.high.downgrade(), normal: self.normal.downgrade() }
+	}
+}
+
+impl WeakSendQueue {
+	/// The queue, if it's still open.
+	pub fn upgrade(&self) -> Option<SendQueue> {
+		Some(SendQueue { high: self.high.upgrade()?, normal: self.normal.upgrade()? })
+	}
+}
+
+impl SendQueueReceiver {
+	/// The next ASDU to send, the high priority ones first. Returns `None` once
+	/// every sender is dropped and the queue is empty. Cancel safe.
+	pub async fn recv(&mut self) -> Option<QueuedAsdu> {
+		select! {
+			biased;
+			Some(queued) = self.high.recv() => Some(queued),
+			Some(queued) = self.normal.recv() => Some(queued),
+			else => None,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+
+	use super::*;
+	use crate::{
+		config::{ClientConfig, ProtocolConfig},
+		cot::Cot,
+		test_util::{Commands, connect_client, server_config, start_server},
+		types::{
+			CScNa1, GenericObject, InformationObjects, commands::Sco, information_elements::Spi,
+		},
+	};
+
+	#[test]
+	fn test_priority() {
+		assert_eq!(Priority::of(TypeId::C_SC_NA_1), Priority::High);
+		assert_eq!(Priority::of(TypeId::C_IC_NA_1), Priority::High);
+		assert_eq!(Priority::of(TypeId::M_SP_NA_1), Priority::Normal);
+		assert_eq!(Priority::of(TypeId::F_FR_NA_1), Priority::Normal);
+	}
+
+	#[tokio::test]
+	async fn test_send_queue() {
+		let (command_tx, mut command_rx) = mpsc::unbounded_channel();
+		let server = start_server(server_config(), Commands(command_tx)).await;
+		let (client, _object_rx) = connect_client(
+			server.local_addr().unwrap().port(),
+			ClientConfig {
+				protocol: ProtocolConfig { k: 1, ..Default::default() },
+				..Default::default()
+			},
+		)
+		.await;
+
+		// The commands wait in the queue while the single frame window is full
+		let command = |ioa: u32| Asdu {
+			type_id: TypeId::C_SC_NA_1,
+			cot: Cot::Activation,
+			originator_address: 1,
+			address_field: 1,
+			sequence: false,
+			test: false,
+			positive: false,
+			information_objects: InformationObjects::CScNa1(vec![GenericObject {
+				address: ioa,
+				object: CScNa1 { sco: Sco { scs: Spi::On, ..Default::default() } },
+			}]),
+		};
+		for ioa in 0..5 {
+			client.send_asdu(command(ioa)).await.unwrap();
+		}
+		tokio::time::timeout(Duration::from_secs(5), client.send_asdu_acknowledged(command(5)))
+			.await
+			.unwrap()
+			.unwrap();
+		for ioa in 0..6 {
+			let received = command_rx.recv().await.unwrap();
+			assert_eq!(received.information_objects.addresses(), [ioa]);
+		}
+	}
+
+	#[tokio::test]
+	async fn test_empty_send_queue_capacity() {
+		let (command_tx, mut command_rx) = mpsc::unbounded_channel();
+		let server = start_server(server_config(), Commands(command_tx)).await;
+		// A capacity of zero still lets the ASDUs through one at a time
+		let (client, _object_rx) = connect_client(
+			server.local_addr().unwrap().port(),
+			ClientConfig { send_queue_capacity: 0, ..Default::default() },
+		)
+		.await;
+
+		let command = Asdu {
+			type_id: TypeId::C_SC_NA_1,
+			cot: Cot::Activation,
+			originator_address: 1,
+			address_field: 1,
+			sequence: false,
+			test: false,
+			positive: false,
+			information_objects: InformationObjects::CScNa1(vec![GenericObject {
+				address: 1,
+				object: CScNa1 { sco: Sco { scs: Spi::On, ..Default::default() } },
+			}]),
+		};
+		tokio::time::timeout(Duration::from_secs(5), client.send_asdu_acknowledged(command))
+			.await
+			.unwrap()
+			.unwrap();
+		assert_eq!(command_rx.recv().await.unwrap().information_objects.addresses(), [1]);
+	}
+}
@@ -0,0 +1,91 @@
+use crate::{client::connection_handler::ConnectionHandlerState, error::Error};
+
+/// State of the connection to the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+	/// Not connected yet, the first connection failed or the reconnection was
+	/// given up.
+	Disconnected,
+	/// Connected, waiting for
+	/// [`Client::start_receiving`](crate::client::Client::start_receiving).
+	WaitingForStart,
+	/// StartDT sent, waiting for its confirmation.
+	Starting,
+	/// Data transfer started.
+	Started,
+	/// The connection was lost and is being reestablished.
+	Reconnecting,
+}
+
+/// Why the connection reached its current state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateChangeReason {
+	/// No connection was attempted yet.
+	NotConnected,
+	/// The TCP, and TLS if configured, connection was established.
+	Connected,
+	/// The data transfer was requested with a StartDT.
+	StartRequested,
+	/// The server confirmed the StartDT.
+	StartDtConfirmed,
+	/// The active connection failed and the data transfer is being moved to a
+	/// standby connection of the redundancy group.
+	Switchover,
+	/// The server didn't confirm the StartDT.
+	StartDtRejected,
+	/// The data transfer was stopped with a StopDT, by either side.
+	StopDt,
+	/// The server didn't acknowledge a frame within t1.
+	T1Timeout,
+	/// The server sent an I-frame out of sequence or acknowledged an unknown
+	/// sequence number.
+	SequenceError,
+	/// The TLS handshake failed.
+	TlsFailure,
+	/// The TCP connection couldn't be established.
+	ConnectionFailed,
+	/// The connection was closed or a frame couldn't be exchanged.
+	ConnectionLost,
+	/// The reconnection was given up after the maximum number of attempts.
+	ReconnectFailed,
+}
+
+/// A state of the connection together with the reason of the transition to
+/// it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionStatus {
+	pub state: ConnectionState,
+	pub reason: StateChangeReason,
+}
+
+impl Default for ConnectionStatus {
+	fn default() -> Self {
+		Self { state: ConnectionState::Disconnected, reason: StateChangeReason::NotConnected }
+	}
+}
+
+impl From<ConnectionHandlerState> for ConnectionState {
+	fn from(state: ConnectionHandlerState) -> Self {
+		match state {
+			ConnectionHandlerState::WaitingForStart => ConnectionState::WaitingForStart,
+			ConnectionHandlerState::Starting => ConnectionState::Starting,
+			ConnectionHandlerState::Started => ConnectionState::Started,
+			ConnectionHandlerState::Reconnecting => ConnectionState::Reconnecting,
+		}
+	}
+}
+
+impl StateChangeReason {
+	/// The reason of a connection failure. `default` is used for the errors
+	/// that don't carry a more specific reason.
+	pub(crate) const fn from_error(error: &Error, default: Self) -> Self {
+		match error {
+			Error::T1Timeout { .. } => StateChangeReason::T1Timeout,
+			Error::SequenceNumber { .. } | Error::InvalidAcknowledge { .. } => {
+				StateChangeReason::SequenceError
+			}
+			Error::TlsHandshake { .. } => StateChangeReason::TlsFailure,
+			_ => default,
+		}
+	}
+}
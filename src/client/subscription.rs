@@ -0,0 +1,144 @@
+use std::{
+	pin::Pin,
+	task::{Context, Poll},
+};
+
+use futures_core::Stream;
+use tokio::sync::mpsc;
+
+use crate::{asdu::Asdu, cot::Cot, types_id::TypeId};
+
+/// Selects the ASDUs delivered to a [`Subscription`]. Every criterion left to
+/// `None` matches any ASDU.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SubscriptionFilter {
+	pub type_id: Option<TypeId>,
+	pub cot: Option<Cot>,
+	pub common_address: Option<u16>,
+}
+
+impl SubscriptionFilter {
+	/// Whether the ASDU meets all the criteria of the filter.
+	#[must_use]
+	pub fn matches(&self, asdu: &Asdu) -> bool {
+		self.type_id.is_none_or(|type_id| type_id == asdu.type_id)
+			&& self.cot.is_none_or(|cot| cot == asdu.cot)
+			&& self.common_address.is_none_or(|address| address == asdu.address_field)
+	}
+}
+
+/// The ASDUs received by the client that match a filter, in order, as
+/// returned by [`Client::subscribe`](crate::client::Client::subscribe).
+///
+/// Can be consumed with [`Subscription::recv`] or as a [`Stream`]. The
+/// subscription ends when it's dropped. At most
+/// [`ClientConfig::subscription_capacity`](crate::config::ClientConfig::subscription_capacity)
+/// ASDUs are queued until they are read. The ASDUs received while the queue is
+/// full are dropped, and how many were is reported with a [`Lagged`] in their
+/// place, once there is room again.
+#[derive(Debug)]
+pub struct Subscription {
+	asdus: mpsc::Receiver<Result<Asdu, Lagged>>,
+}
+
+/// Some ASDUs matching the filter were dropped because the subscription was
+/// not read fast enough.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lagged {
+	/// How many ASDUs were dropped.
+	pub missed: u64,
+}
+
+impl Subscription {
+	pub(crate) const fn new(asdus: mpsc::Receiver<Result<Asdu, Lagged>>) -> Self {
+		Self { asdus }
+	}
+
+	/// The next ASDU, or how many were dropped at this point of the sequence.
+	/// Returns `None` once the client is dropped.
+	pub async fn recv(&mut self) -> Option<Result<Asdu, Lagged>> {
+		self.asdus.recv().await
+	}
+}
+
+impl Stream for Subscription {
+	type Item = Result<Asdu, Lagged>;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		self.asdus.poll_recv(cx)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{sync::Arc, time::Duration};
+
+	use super::*;
+	use crate::{
+		client::{OnNewObjects as _, dispatcher::Dispatcher},
+		test_util::{Commands, Objects, start},
+		types::{GenericObject, InformationObjects, MSpNa1},
+	};
+
+	fn spontaneous(common_address: u16, ioa: u32) -> Asdu {
+		Asdu {
+			type_id: TypeId::M_SP_NA_1,
+			cot: Cot::SpontaneousData,
+			originator_address: 0,
+			address_field: common_address,
+			sequence: false,
+			test: false,
+			positive: false,
+			information_objects: InformationObjects::MSpNa1(vec![GenericObject {
+				address: ioa,
+				object: MSpNa1::default(),
+			}]),
+		}
+	}
+
+	#[tokio::test]
+	async fn test_subscribe() {
+		let (command_tx, _command_rx) = mpsc::unbounded_channel();
+		let (server, client, mut object_rx) = start(Commands(command_tx)).await;
+		let mut subscription = client.subscribe(SubscriptionFilter {
+			type_id: Some(TypeId::M_SP_NA_1),
+			cot: Some(Cot::SpontaneousData),
+			common_address: Some(1),
+		});
+
+		// The server may not have seen the StartDT confirmation sent yet
+		tokio::time::timeout(Duration::from_secs(5), async {
+			while server.send_asdu(spontaneous(2, 100)).is_err()
+				|| tokio::time::timeout(Duration::from_millis(100), object_rx.recv()).await.is_err()
+			{
+			}
+		})
+		.await
+		.unwrap();
+
+		server.send_asdu(spontaneous(1, 200)).unwrap();
+		// The callback keeps receiving every ASDU
+		assert_eq!(object_rx.recv().await.unwrap(), spontaneous(1, 200));
+		assert_eq!(subscription.recv().await.unwrap().unwrap(), spontaneous(1, 200));
+		assert!(subscription.asdus.is_empty());
+	}
+
+	#[tokio::test]
+	async fn test_subscription_lag() {
+		let (object_tx, _object_rx) = mpsc::unbounded_channel();
+		let dispatcher = Dispatcher::new(Arc::new(Objects(object_tx)), None);
+		let mut subscription = Subscription::new(dispatcher.subscribe_bounded(2, |_| true));
+
+		for ioa in 1..=5 {
+			dispatcher.on_new_objects(spontaneous(1, ioa)).await;
+		}
+		assert_eq!(subscription.recv().await.unwrap().unwrap(), spontaneous(1, 1));
+		assert_eq!(subscription.recv().await.unwrap().unwrap(), spontaneous(1, 2));
+		assert!(subscription.asdus.is_empty());
+
+		// The missed ASDUs are reported where they were dropped
+		dispatcher.on_new_objects(spontaneous(1, 6)).await;
+		assert_eq!(subscription.recv().await.unwrap(), Err(Lagged { missed: 3 }));
+		assert_eq!(subscription.recv().await.unwrap().unwrap(), spontaneous(1, 6));
+	}
+}
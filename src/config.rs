@@ -2,6 +2,11 @@ use std::{path::PathBuf, time::Duration};
 
 use serde::{Deserialize, Serialize};
 
+use crate::{
+	asdu::{AsduProfile, BROADCAST_ADDRESS},
+	types::commands::Qoi,
+};
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ProtocolConfig {
 	/// The period between sending test frames. The default is 20 seconds.
@@ -26,6 +31,17 @@ pub struct ProtocolConfig {
 	pub w: u16,
 	/// The originator address for the IEC 104 connection.
 	pub originator_address: u8,
+	/// The sizes of the cause of transmission, common address and information
+	/// object address fields of the ASDUs. The default is the IEC 104 one.
+	#[serde(default)]
+	pub asdu_profile: AsduProfile,
+	/// When set, the pcapng file every APDU sent and received is written to.
+	/// It's replaced when first opened, then kept across the reconnections and
+	/// restarts, and shared by the clients and servers of the process with the
+	/// same path. The APDUs exchanged while the file is written too slowly are
+	/// left out, and how many were is logged.
+	#[serde(default)]
+	pub capture: Option<PathBuf>,
 }
 
 /// The client TLS configuration
@@ -54,6 +70,15 @@ pub struct TlsClientConfig {
 	pub danger_disable_tls_verify: bool,
 }
 
+/// The server TLS configuration
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TlsServerConfig {
+	/// Path to the server key in PKCS #8 PEM format.
+	pub server_key: PathBuf,
+	/// Path to the server certificate in PEM format.
+	pub server_certificate: PathBuf,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ClientConfig {
 	/// The address of the server.
@@ -66,6 +91,270 @@ pub struct ClientConfig {
 	/// The TLS configuration.
 	#[serde(default)]
 	pub tls: Option<TlsClientConfig>,
+	/// How long to wait for each reply of the server to a command. The default
+	/// is 10 seconds.
+	#[serde(with = "humantime_serde", default = "default_duration::<10>")]
+	pub command_timeout: Duration,
+	/// How long a selected command stays selected before it must be executed.
+	/// The default is 10 seconds.
+	#[serde(with = "humantime_serde", default = "default_duration::<10>")]
+	pub select_timeout: Duration,
+	/// When set, the CP24Time2a time tags of the received objects are expanded
+	/// to absolute times using this reference and delivered with
+	/// [`OnNewObjects::on_new_objects_with_times`](crate::client::OnNewObjects::on_new_objects_with_times).
+	#[serde(default)]
+	pub cp24_reference: Option<Cp24Reference>,
+	/// The other endpoints of the redundancy group of the server. A connection
+	/// to each of them is kept open and tested with TESTFR, and the data
+	/// transfer is switched over to one of them when the active connection
+	/// fails.
+	#[serde(default)]
+	pub redundancy_group: Vec<Endpoint>,
+	/// How the connection is reestablished after it's lost.
+	#[serde(default)]
+	pub reconnect: ReconnectPolicy,
+	/// When set, the interrogations sent every time the data transfer is
+	/// started, and periodically if configured.
+	#[serde(default)]
+	pub interrogation: Option<InterrogationConfig>,
+	/// How many ASDUs of each priority can wait in the send queue, while the k
+	/// window is full, and at least one. The default is 1024.
+	#[serde(default = "default_number::<1024>")]
+	pub send_queue_capacity: u16,
+	/// How many ASDUs each [`Subscription`](crate::client::subscription::Subscription)
+	/// queues until they are read. The ASDUs received while it's full are
+	/// dropped and reported. The default is 1024.
+	#[serde(default = "default_number::<1024>")]
+	pub subscription_capacity: u16,
+}
+
+/// The interrogations sent automatically by the client, so that the state
+/// changes missed while the data transfer was stopped are received.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct InterrogationConfig {
+	/// The common addresses to interrogate. The default is the broadcast
+	/// address.
+	#[serde(default = "default_common_addresses")]
+	pub common_addresses: Vec<u16>,
+	/// The qualifiers of the interrogations sent to every common address. The
+	/// default is the global interrogation.
+	#[serde(default = "default_qualifiers")]
+	pub qualifiers: Vec<Qoi>,
+	/// The period of the interrogations once the data transfer is started. By
+	/// default they are only sent when it starts.
+	#[serde(with = "humantime_serde", default)]
+	pub period: Option<Duration>,
+}
+
+/// How the client reconnects after the connection is lost.
+///
+/// The first attempt is made right away. After every failed attempt the client
+/// waits t0, multiplied by `backoff_factor` for every previous failure and
+/// bounded by `max_delay`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReconnectPolicy {
+	/// The factor the delay between attempts grows by. The default is 1, a
+	/// fixed delay of t0.
+	#[serde(default = "default_backoff_factor")]
+	pub backoff_factor: f64,
+	/// The longest delay between attempts. The default is 5 minutes.
+	#[serde(with = "humantime_serde", default = "default_duration::<300>")]
+	pub max_delay: Duration,
+	/// The fraction, between 0 and 1, of every delay randomly added or removed
+	/// so that many clients don't reconnect in lockstep. The default is 0.
+	#[serde(default)]
+	pub jitter: f64,
+	/// The number of failed attempts after which the client gives up. The
+	/// status then becomes disconnected with
+	/// [`StateChangeReason::ReconnectFailed`](crate::client::state::StateChangeReason::ReconnectFailed)
+	/// and the client must be connected again. By default it never gives up.
+	#[serde(default)]
+	pub max_attempts: Option<u32>,
+	/// Whether to restart the data transfer with a StartDT once reconnected.
+	/// Otherwise it must be started again with
+	/// [`Client::start_receiving`](crate::client::Client::start_receiving).
+	/// The switchovers to a standby link of the redundancy group always
+	/// restart it. The default is true.
+	#[serde(default = "default_true")]
+	pub restart: bool,
+	/// Whether to interrogate the server once the data transfer is restarted,
+	/// to refresh the data image. The interrogations of
+	/// [`ClientConfig::interrogation`] are used, or a general interrogation of
+	/// the broadcast address if not set, in which case only the reconnections
+	/// trigger it. The default is false.
+	#[serde(default)]
+	pub interrogate: bool,
+}
+
+/// The configuration of a [`ClientPool`](crate::client::pool::ClientPool).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PoolConfig {
+	/// The stations to connect to.
+	pub stations: Vec<StationConfig>,
+	/// How many ASDUs the merged stream of the stations queues until they are
+	/// read. The ASDUs received while it's full are dropped and reported. The
+	/// default is 1024.
+	#[serde(default = "default_number::<1024>")]
+	pub asdu_capacity: u16,
+}
+
+/// A station of a [`ClientPool`](crate::client::pool::ClientPool).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StationConfig {
+	/// The name identifying the station in the pool.
+	pub name: String,
+	/// The common addresses of the station. The commands sent to the pool for
+	/// these addresses are routed to this station.
+	#[serde(default)]
+	pub common_addresses: Vec<u16>,
+	/// The configuration of the client connected to the station.
+	#[serde(flatten)]
+	pub client: ClientConfig,
+}
+
+/// An address the server can be reached on.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Endpoint {
+	/// The address of the server.
+	pub address: String,
+	/// The port of the server.
+	pub port: u16,
+}
+
+/// The reference clock used to expand the CP24Time2a time tags.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Cp24Reference {
+	/// The time the ASDU is received.
+	Reception,
+	/// The station clock, estimated from the last clock synchronization and
+	/// the time elapsed since. The reception time is used until the first
+	/// synchronization.
+	ClockSync,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ServerConfig {
+	/// The address to listen on.
+	pub address: String,
+	/// The port to listen on.
+	pub port: u16,
+	/// The protocol configuration.
+	#[serde(default)]
+	pub protocol: ProtocolConfig,
+	/// The TLS configuration.
+	#[serde(default)]
+	pub tls: Option<TlsServerConfig>,
+	/// Whether the general and group interrogations are answered from the
+	/// [`PointDatabase`](crate::server::database::PointDatabase). Otherwise
+	/// they are passed to the [`OnNewCommand`](crate::server::OnNewCommand)
+	/// callback like the other commands. The default is true.
+	#[serde(default = "default_true")]
+	pub answer_interrogations: bool,
+}
+
+/// The configuration of an IEC 60870-5-101 FT1.2 link.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LinkConfig {
+	/// The transmission procedure of the link. The default is unbalanced.
+	#[serde(default)]
+	pub mode: LinkMode,
+	/// Which end of the link this station is.
+	pub role: LinkRole,
+	/// The link address of the controlled station.
+	pub address: u16,
+	/// The link addresses of the other controlled stations sharing the
+	/// transport, polled in turn with the one of
+	/// [`address`](Self::address). Only used by the controlling station of an
+	/// unbalanced link.
+	#[serde(default)]
+	pub polled_addresses: Vec<u16>,
+	/// The size of the link address: 1 or 2, or 0 on balanced links where it
+	/// can be left out. The default is 1.
+	#[serde(default = "default_link_address_size")]
+	pub address_size: u8,
+	/// The sizes of the fields of the ASDUs. The default is the IEC 104 one,
+	/// IEC 101 links commonly use a 1 byte cause of transmission and common
+	/// address and a 2 byte information object address.
+	#[serde(default)]
+	pub asdu_profile: AsduProfile,
+	/// How long to wait for the response of the other station before the frame
+	/// is repeated. The default is 1 second.
+	#[serde(with = "humantime_serde", default = "default_duration::<1>")]
+	pub response_timeout: Duration,
+	/// How many times a frame is repeated without response before the link is
+	/// considered down. The default is 3.
+	#[serde(default = "default_link_retries")]
+	pub retries: u8,
+	/// The period between the requests of class 2 data of the controlling
+	/// station of an unbalanced link, and the delay before a link that went
+	/// down is brought up again. The default is 1 second.
+	#[serde(with = "humantime_serde", default = "default_duration::<1>")]
+	pub poll_interval: Duration,
+}
+
+/// The configuration of a gateway between IEC 101 stations and IEC 104
+/// controlling stations.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GatewayConfig {
+	/// The IEC 104 side, the controlling stations connect to. To forward the
+	/// interrogations to the stations instead of answering them from the
+	/// process image of the gateway, unset
+	/// [`ServerConfig::answer_interrogations`].
+	pub server: ServerConfig,
+	/// The IEC 101 stations behind the gateway.
+	pub stations: Vec<GatewayStationConfig>,
+}
+
+/// An IEC 101 station behind a gateway.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GatewayStationConfig {
+	/// The name the transport of the station is attached with.
+	pub name: String,
+	/// The link to the station. The gateway is usually the controlling
+	/// station.
+	pub link: LinkConfig,
+	/// The common addresses of the station. The IEC 101 ones are unique
+	/// across the stations polled on the link, since the ASDUs they send are
+	/// told apart by their common address.
+	pub common_addresses: Vec<CommonAddressMapping>,
+}
+
+/// A common address of a station behind a gateway, as seen from each side.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CommonAddressMapping {
+	/// The common address on the IEC 104 side.
+	pub iec104: u16,
+	/// The common address on the IEC 101 link.
+	pub iec101: u16,
+	/// The link address of the station the common address is reached through,
+	/// either [`LinkConfig::address`] or one of the
+	/// [`LinkConfig::polled_addresses`]. The default is
+	/// [`LinkConfig::address`].
+	#[serde(default)]
+	pub link_address: Option<u16>,
+}
+
+/// The transmission procedure of an FT1.2 link.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkMode {
+	/// Only the controlling station initiates transmissions, it polls the
+	/// controlled station for its data.
+	#[default]
+	Unbalanced,
+	/// Both stations initiate transmissions.
+	Balanced,
+}
+
+/// The end of an FT1.2 link a station is.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkRole {
+	/// The master, or station A of a balanced link.
+	Controlling,
+	/// The outstation, or station B of a balanced link.
+	Controlled,
 }
 
 impl Default for ProtocolConfig {
@@ -78,6 +367,8 @@ impl Default for ProtocolConfig {
 			k: 12,
 			w: 8,
 			originator_address: 1,
+			asdu_profile: AsduProfile::default(),
+			capture: None,
 		}
 	}
 }
@@ -89,6 +380,49 @@ impl Default for ClientConfig {
 			port: 2404,
 			protocol: ProtocolConfig::default(),
 			tls: None,
+			command_timeout: Duration::from_secs(10),
+			select_timeout: Duration::from_secs(10),
+			cp24_reference: None,
+			redundancy_group: Vec::new(),
+			reconnect: ReconnectPolicy::default(),
+			interrogation: None,
+			send_queue_capacity: 1024,
+			subscription_capacity: 1024,
+		}
+	}
+}
+
+impl Default for ReconnectPolicy {
+	fn default() -> Self {
+		Self {
+			backoff_factor: 1.0,
+			max_delay: Duration::from_secs(300),
+			jitter: 0.0,
+			max_attempts: None,
+			restart: true,
+			interrogate: false,
+		}
+	}
+}
+
+impl Default for InterrogationConfig {
+	fn default() -> Self {
+		Self {
+			common_addresses: default_common_addresses(),
+			qualifiers: default_qualifiers(),
+			period: None,
+		}
+	}
+}
+
+impl Default for ServerConfig {
+	fn default() -> Self {
+		Self {
+			address: "0.0.0.0".to_owned(),
+			port: 2404,
+			protocol: ProtocolConfig::default(),
+			tls: None,
+			answer_interrogations: true,
 		}
 	}
 }
@@ -100,3 +434,27 @@ const fn default_number<const N: u16>() -> u16 {
 const fn default_duration<const N: u64>() -> Duration {
 	Duration::from_secs(N)
 }
+
+const fn default_link_address_size() -> u8 {
+	1
+}
+
+const fn default_link_retries() -> u8 {
+	3
+}
+
+const fn default_backoff_factor() -> f64 {
+	1.0
+}
+
+const fn default_true() -> bool {
+	true
+}
+
+fn default_common_addresses() -> Vec<u16> {
+	vec![BROADCAST_ADDRESS]
+}
+
+fn default_qualifiers() -> Vec<Qoi> {
+	vec![Qoi::Global]
+}
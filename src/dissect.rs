@@ -0,0 +1,388 @@
+//! Human readable dissection of the APDUs, in the spirit of the packet
+//! details of Wireshark.
+//!
+//! The [`Display`](fmt::Display) implementations of [`Apdu`], [`Frame`],
+//! [`Asdu`] and [`InformationObjects`] print a tree of their fields, one per
+//! line:
+//!
+//! ```text
+//! APDU, length 21
+//!   I-frame: N(S) 3, N(R) 7
+//!   ASDU
+//!     Type: M_SP_TB_1 (30)
+//!     VSQ: SQ 0, 1 object
+//!     COT: SpontaneousData (3), P/N 0 (positive), T 0
+//!     Originator address: 0
+//!     Common address: 1
+//!     IOA 100: Single(On), quality good, time 2023-11-14 22:13:20.000 UTC
+//! ```
+
+use std::fmt::{self, Debug, Write as _};
+
+use time::OffsetDateTime;
+
+use crate::{
+	apdu::{Apdu, Frame, TELEGRAN_HEADER, UFrame},
+	asdu::{Asdu, AsduProfile},
+	point::{PointUpdate, Quality},
+	types::{FromBytes, GenericObject, InformationObjects, ToBytes},
+};
+
+/// Writes the nodes of a tree, indented by their depth.
+struct Tree<'a, 'b> {
+	f: &'a mut fmt::Formatter<'b>,
+	depth: usize,
+	started: bool,
+}
+
+impl<'a, 'b> Tree<'a, 'b> {
+	const fn new(f: &'a mut fmt::Formatter<'b>) -> Self {
+		Self { f, depth: 0, started: false }
+	}
+
+	fn node(&mut self, args: fmt::Arguments<'_>) -> fmt::Result {
+		if self.started {
+			self.f.write_char('\n')?;
+		}
+		self.started = true;
+		write!(self.f, "{:indent$}{args}", "", indent = 2 * self.depth)
+	}
+
+	/// Writes the children of the last node.
+	fn children(&mut self, write: impl FnOnce(&mut Self) -> fmt::Result) -> fmt::Result {
+		self.depth += 1;
+		let result = write(self);
+		self.depth -= 1;
+		result
+	}
+}
+
+trait Dissect {
+	fn dissect(&self, tree: &mut Tree<'_, '_>) -> fmt::Result;
+}
+
+impl Dissect for Apdu {
+	fn dissect(&self, tree: &mut Tree<'_, '_>) -> fmt::Result {
+		tree.node(format_args!("APDU, length {}", self.length))?;
+		tree.children(|tree| self.frame.dissect(tree))
+	}
+}
+
+impl Dissect for Frame {
+	fn dissect(&self, tree: &mut Tree<'_, '_>) -> fmt::Result {
+		match self {
+			Frame::I(i) => {
+				tree.node(format_args!(
+					"I-frame: N(S) {}, N(R) {}",
+					i.send_sequence_number, i.receive_sequence_number
+				))?;
+				i.asdu.dissect(tree)
+			}
+			Frame::S(s) => tree.node(format_args!("S-frame: N(R) {}", s.receive_sequence_number)),
+			Frame::U(u) => tree.node(format_args!("U-frame: {}", u_functions(u))),
+		}
+	}
+}
+
+/// The functions of a U-frame, usually a single one.
+fn u_functions(u: &UFrame) -> String {
+	let functions = [
+		(u.start_dt_activation, "STARTDT act"),
+		(u.start_dt_confirmation, "STARTDT con"),
+		(u.stop_dt_activation, "STOPDT act"),
+		(u.stop_dt_confirmation, "STOPDT con"),
+		(u.test_fr_activation, "TESTFR act"),
+		(u.test_fr_confirmation, "TESTFR con"),
+	];
+	let functions: Vec<_> =
+		functions.iter().filter(|(set, _)| *set).map(|(_, function)| *function).collect();
+	if functions.is_empty() { "no function".to_owned() } else { functions.join(", ") }
+}
+
+impl Dissect for Asdu {
+	fn dissect(&self, tree: &mut Tree<'_, '_>) -> fmt::Result {
+		tree.node(format_args!("ASDU"))?;
+		tree.children(|tree| {
+			tree.node(format_args!("Type: {:?} ({})", self.type_id, self.type_id as u8))?;
+			let count = self.information_objects.len();
+			tree.node(format_args!(
+				"VSQ: SQ {}, {count} object{}",
+				u8::from(self.sequence),
+				if count == 1 { "" } else { "s" }
+			))?;
+			tree.node(format_args!(
+				"COT: {:?} ({}), P/N {}, T {}",
+				self.cot,
+				self.cot as u8,
+				if self.positive { "1 (negative)" } else { "0 (positive)" },
+				if self.test { "1 (test)" } else { "0" }
+			))?;
+			tree.node(format_args!("Originator address: {}", self.originator_address))?;
+			tree.node(format_args!("Common address: {}", self.address_field))?;
+
+			// The monitoring objects are decoded to their value, quality and time tag
+			let updates = PointUpdate::from_asdu(self, OffsetDateTime::UNIX_EPOCH);
+			if updates.is_empty() {
+				return self.information_objects.dissect(tree);
+			}
+			let cp24_times = self.information_objects.cp24_times();
+			for (update, cp24_time) in updates.iter().zip(cp24_times) {
+				let time = match (cp24_time, update.timestamp) {
+					// Only the minutes and milliseconds are known
+					(Some(time), _) => format!(
+						", time xx:{:02}:{:02}.{:03}{}",
+						time.min,
+						time.ms / 1000,
+						time.ms % 1000,
+						if time.iv { " (invalid)" } else { "" }
+					),
+					(None, Some(timestamp)) => format!(", time {}", human_time(timestamp)),
+					(None, None) => String::new(),
+				};
+				tree.node(format_args!(
+					"IOA {}: {:?}, quality {}{time}",
+					update.ioa,
+					update.value,
+					quality(update.quality)
+				))?;
+			}
+			Ok(())
+		})
+	}
+}
+
+/// The set flags of the quality, or `good` if none is.
+fn quality(quality: Quality) -> String {
+	if quality.is_good() {
+		return "good".to_owned();
+	}
+	let flags = [
+		(quality.iv, "IV"),
+		(quality.nt, "NT"),
+		(quality.sb, "SB"),
+		(quality.bl, "BL"),
+		(quality.ov, "OV"),
+		(quality.ei, "EI"),
+	];
+	flags.iter().filter(|(set, _)| *set).map(|(_, flag)| *flag).collect::<Vec<_>>().join(" ")
+}
+
+fn human_time(time: OffsetDateTime) -> String {
+	format!(
+		"{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:03} UTC",
+		time.year(),
+		time.month() as u8,
+		time.day(),
+		time.hour(),
+		time.minute(),
+		time.second(),
+		time.millisecond()
+	)
+}
+
+impl Dissect for InformationObjects {
+	fn dissect(&self, tree: &mut Tree<'_, '_>) -> fmt::Result {
+		for (address, object) in self.debug() {
+			tree.node(format_args!("IOA {address}: {object}"))?;
+		}
+		Ok(())
+	}
+}
+
+impl InformationObjects {
+	/// The address and the fields of every object.
+	#[allow(clippy::too_many_lines)]
+	fn debug(&self) -> Vec<(u32, String)> {
+		match self {
+			InformationObjects::MSpNa1(objs) => Self::debug_objects(objs),
+			InformationObjects::MSpTa1(objs) => Self::debug_objects(objs),
+			InformationObjects::MDpNa1(objs) => Self::debug_objects(objs),
+			InformationObjects::MDpTa1(objs) => Self::debug_objects(objs),
+			InformationObjects::MStNa1(objs) => Self::debug_objects(objs),
+			InformationObjects::MStTa1(objs) => Self::debug_objects(objs),
+			InformationObjects::MBoNa1(objs) => Self::debug_objects(objs),
+			InformationObjects::MMeNa1(objs) => Self::debug_objects(objs),
+			InformationObjects::MMeTa1(objs) => Self::debug_objects(objs),
+			InformationObjects::MMeNb1(objs) => Self::debug_objects(objs),
+			InformationObjects::MMeTb1(objs) => Self::debug_objects(objs),
+			InformationObjects::MMeNc1(objs) => Self::debug_objects(objs),
+			InformationObjects::MMeTc1(objs) => Self::debug_objects(objs),
+			InformationObjects::MItNa1(objs) => Self::debug_objects(objs),
+			InformationObjects::MEpTa1(objs) => Self::debug_objects(objs),
+			InformationObjects::MEpTb1(objs) => Self::debug_objects(objs),
+			InformationObjects::MEpTc1(objs) => Self::debug_objects(objs),
+			InformationObjects::MPsNa1(objs) => Self::debug_objects(objs),
+			InformationObjects::MMeNd1(objs) => Self::debug_objects(objs),
+			InformationObjects::MSpTb1(objs) => Self::debug_objects(objs),
+			InformationObjects::MDpTb1(objs) => Self::debug_objects(objs),
+			InformationObjects::MStTb1(objs) => Self::debug_objects(objs),
+			InformationObjects::MBoTb1(objs) => Self::debug_objects(objs),
+			InformationObjects::MMeTd1(objs) => Self::debug_objects(objs),
+			InformationObjects::MMeTe1(objs) => Self::debug_objects(objs),
+			InformationObjects::MMeTf1(objs) => Self::debug_objects(objs),
+			InformationObjects::MItTb1(objs) => Self::debug_objects(objs),
+			InformationObjects::MEpTd1(objs) => Self::debug_objects(objs),
+			InformationObjects::MEpTe1(objs) => Self::debug_objects(objs),
+			InformationObjects::MEpTf1(objs) => Self::debug_objects(objs),
+			InformationObjects::MEiNa1(objs) => Self::debug_objects(objs),
+			InformationObjects::CScNa1(objs) => Self::debug_objects(objs),
+			InformationObjects::CdcNa1(objs) => Self::debug_objects(objs),
+			InformationObjects::CrcNa1(objs) => Self::debug_objects(objs),
+			InformationObjects::CSeNa1(objs) => Self::debug_objects(objs),
+			InformationObjects::CSeNb1(objs) => Self::debug_objects(objs),
+			InformationObjects::CSeNc1(objs) => Self::debug_objects(objs),
+			InformationObjects::CBoNa1(objs) => Self::debug_objects(objs),
+			InformationObjects::CScTa1(objs) => Self::debug_objects(objs),
+			InformationObjects::CdcTa1(objs) => Self::debug_objects(objs),
+			InformationObjects::CrcTa1(objs) => Self::debug_objects(objs),
+			InformationObjects::CSeTa1(objs) => Self::debug_objects(objs),
+			InformationObjects::CSeTb1(objs) => Self::debug_objects(objs),
+			InformationObjects::CSeTc1(objs) => Self::debug_objects(objs),
+			InformationObjects::CBoTa1(objs) => Self::debug_objects(objs),
+			InformationObjects::CIcNa1(objs) => Self::debug_objects(objs),
+			InformationObjects::CCiNa1(objs) => Self::debug_objects(objs),
+			InformationObjects::CRdNa1(objs) => Self::debug_objects(objs),
+			InformationObjects::CCsNa1(objs) => Self::debug_objects(objs),
+			InformationObjects::CTsNa1(objs) => Self::debug_objects(objs),
+			InformationObjects::CRpNa1(objs) => Self::debug_objects(objs),
+			InformationObjects::CCdNa1(objs) => Self::debug_objects(objs),
+			InformationObjects::CTsTa1(objs) => Self::debug_objects(objs),
+			InformationObjects::PMeNa1(objs) => Self::debug_objects(objs),
+			InformationObjects::PMeNb1(objs) => Self::debug_objects(objs),
+			InformationObjects::PMeNc1(objs) => Self::debug_objects(objs),
+			InformationObjects::PAcNa1(objs) => Self::debug_objects(objs),
+			InformationObjects::FFrNa1(objs) => Self::debug_objects(objs),
+			InformationObjects::FSrNa1(objs) => Self::debug_objects(objs),
+			InformationObjects::FScNa1(objs) => Self::debug_objects(objs),
+			InformationObjects::FLsNa1(objs) => Self::debug_objects(objs),
+			InformationObjects::FFaNa1(objs) => Self::debug_objects(objs),
+			InformationObjects::FSgNa1(objs) => Self::debug_objects(objs),
+			InformationObjects::FDrTa1(objs) => Self::debug_objects(objs),
+			InformationObjects::Raw(objs) => Self::debug_objects(objs),
+		}
+	}
+
+	fn debug_objects<T: FromBytes + ToBytes + Default + Debug>(
+		objects: &[GenericObject<T>],
+	) -> Vec<(u32, String)> {
+		objects.iter().map(|obj| (obj.address, format!("{:?}", obj.object))).collect()
+	}
+}
+
+impl fmt::Display for Apdu {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		self.dissect(&mut Tree::new(f))
+	}
+}
+
+impl fmt::Display for Frame {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		self.dissect(&mut Tree::new(f))
+	}
+}
+
+impl fmt::Display for Asdu {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		self.dissect(&mut Tree::new(f))
+	}
+}
+
+impl fmt::Display for InformationObjects {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		self.dissect(&mut Tree::new(f))
+	}
+}
+
+/// Dissects the APDUs of a byte stream, as captured on the wire, decoding the
+/// ASDUs with the field sizes of the profile.
+///
+/// The APDUs that can't be decoded are printed as hex with the reason, and the
+/// dissection stops at the first bytes that don't start an APDU.
+#[must_use]
+pub fn explain(mut bytes: &[u8], profile: AsduProfile) -> String {
+	let mut explanation = Vec::new();
+	while !bytes.is_empty() {
+		let length = match bytes {
+			[TELEGRAN_HEADER, length, ..] => *length as usize + 2,
+			_ => {
+				explanation.push(format!("Not an APDU: {}", hex(bytes)));
+				break;
+			}
+		};
+		let Some((apdu, rest)) = bytes.split_at_checked(length) else {
+			explanation.push(format!("Truncated APDU: {}", hex(bytes)));
+			break;
+		};
+		explanation.push(match Apdu::from_bytes_with_profile(apdu, profile) {
+			Ok(decoded) => decoded.to_string(),
+			Err(e) => format!("Invalid APDU ({e}): {}", hex(apdu)),
+		});
+		bytes = rest;
+	}
+	explanation.join("\n")
+}
+
+/// The bytes as space separated hex.
+#[must_use]
+pub fn hex(bytes: &[u8]) -> String {
+	bytes.iter().map(|byte| format!("{byte:02X}")).collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		apdu::IFrame,
+		cot::Cot,
+		types::{
+			MSpTb1,
+			information_elements::{Siq, Spi},
+			time::Cp56Time2a,
+		},
+		types_id::TypeId,
+	};
+
+	#[test]
+	fn test_explain() {
+		// 2023-11-14 22:13:20 UTC
+		let time = OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+		let frame = Frame::I(IFrame {
+			send_sequence_number: 3,
+			receive_sequence_number: 7,
+			asdu: Asdu {
+				type_id: TypeId::M_SP_TB_1,
+				cot: Cot::SpontaneousData,
+				originator_address: 0,
+				address_field: 1,
+				sequence: false,
+				test: false,
+				positive: false,
+				information_objects: InformationObjects::MSpTb1(vec![GenericObject {
+					address: 100,
+					object: MSpTb1 {
+						siq: Siq { spi: Spi::On, nt: true, ..Default::default() },
+						time: Cp56Time2a::try_from(time).unwrap(),
+					},
+				}]),
+			},
+		});
+		let mut bytes = frame.to_apdu_bytes().unwrap();
+		bytes.extend_from_slice(&[0x68, 0x04, 0x0B, 0x00, 0x00, 0x00, 0x68, 0x04]);
+
+		assert_eq!(
+			explain(&bytes, AsduProfile::IEC_104),
+			"APDU, length 21
+  I-frame: N(S) 3, N(R) 7
+  ASDU
+    Type: M_SP_TB_1 (30)
+    VSQ: SQ 0, 1 object
+    COT: SpontaneousData (3), P/N 0 (positive), T 0
+    Originator address: 0
+    Common address: 1
+    IOA 100: Single(On), quality NT, time 2023-11-14 22:13:20.000 UTC
+APDU, length 4
+  U-frame: STARTDT con
+Truncated APDU: 68 04"
+		);
+	}
+}
@@ -93,6 +93,34 @@ pub enum Error {
 		context: Box<SpanTraceWrapper>,
 	},
 
+	#[snafu(display("t1 timeout waiting for the {frame}{context}"))]
+	T1Timeout {
+		frame: &'static str,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display(
+		"Received I-frame with wrong sequence number. Expected: {expected}, Received: {received}{context}"
+	))]
+	SequenceNumber {
+		expected: u16,
+		received: u16,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Received acknowledge of invalid sequence number {received}{context}"))]
+	InvalidAcknowledge {
+		received: u16,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("TLS handshake failed{context}"))]
+	TlsHandshake {
+		source: tokio_native_tls::native_tls::Error,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+
 	#[snafu(whatever, display("{message}{context}\n{source:?}"))]
 	Whatever {
 		message: String,
@@ -0,0 +1,868 @@
+use std::{
+	collections::{HashMap, hash_map::Entry},
+	fmt::Debug,
+	net::SocketAddr,
+	sync::{Arc, Mutex, OnceLock, PoisonError},
+};
+
+use async_trait::async_trait;
+use snafu::{OptionExt as _, ResultExt as _, Snafu, ensure};
+use tokio::{
+	io::{AsyncRead, AsyncWrite},
+	sync::{broadcast, mpsc, watch},
+	task::JoinSet,
+};
+use tracing::instrument;
+
+use crate::{
+	asdu::{Asdu, BROADCAST_ADDRESS},
+	config::{GatewayConfig, LinkConfig, LinkMode, LinkRole},
+	cot::Cot,
+	error::{Error, SpanTraceWrapper},
+	iec101::{
+		errors::LinkError,
+		link::{Link, LinkSender},
+	},
+	server::{OnNewCommand, Server, database::PointDatabase},
+	types::{CIcNa1, GenericObject, InformationObjects, commands::Qoi},
+	types_id::TypeId,
+};
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub), context(suffix(false)))]
+pub enum GatewayError {
+	#[snafu(display("Station {name} is configured more than once"))]
+	DuplicateStation {
+		name: String,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Common address {common_address} is mapped more than once"))]
+	DuplicateCommonAddress {
+		common_address: u16,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Station {name} has no link to the link address {address}"))]
+	UnknownLinkAddress {
+		name: String,
+		address: u16,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("There is no station named {name}"))]
+	UnknownStation {
+		name: String,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("The transport of station {name} is already attached"))]
+	AlreadyAttached {
+		name: String,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Error starting the link of station {name}"))]
+	StartLink {
+		name: String,
+		#[snafu(source(from(LinkError, Box::new)))]
+		source: Box<LinkError>,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+}
+
+/// A command received on the IEC 104 side, as received and as sent to the
+/// station with the link address.
+#[derive(Debug)]
+struct Forwarded {
+	upstream: Asdu,
+	link_address: u16,
+	downstream: Asdu,
+}
+
+#[derive(Debug)]
+struct Station {
+	name: String,
+	/// The IEC 104 common address of every IEC 101 one of the station.
+	upstream_addresses: HashMap<u16, u16>,
+	/// The link address every IEC 101 common address of the station is
+	/// reached through.
+	link_addresses: HashMap<u16, u16>,
+	ioa_size: u8,
+	/// The queue of the commands to send to the station, once its transport is
+	/// attached.
+	commands: OnceLock<mpsc::UnboundedSender<Forwarded>>,
+	/// Who sent the last command of every type to every information object of
+	/// every IEC 101 common address, so that the originator address is
+	/// restored in the responses of the station since IEC 101 links usually
+	/// don't carry it.
+	originators: Mutex<HashMap<CommandKey, Originator>>,
+}
+
+impl Station {
+	/// The link addresses the ASDUs to the IEC 101 common address are sent to,
+	/// every one of the station for the broadcast address.
+	fn link_addresses(&self, common_address: u16) -> Vec<u16> {
+		let mut addresses: Vec<_> = self
+			.link_addresses
+			.iter()
+			.filter(|(iec101, _)| common_address == BROADCAST_ADDRESS || **iec101 == common_address)
+			.map(|(_, link_address)| *link_address)
+			.collect();
+		addresses.sort_unstable();
+		addresses.dedup();
+		addresses
+	}
+}
+
+/// The type id, the IEC 101 common address and the first information object
+/// address of a command.
+type CommandKey = (u8, u16, u32);
+
+fn command_key(asdu: &Asdu) -> CommandKey {
+	let address = asdu.information_objects.addresses().first().copied().unwrap_or_default();
+	(asdu.type_id as u8, asdu.address_field, address)
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Originator {
+	/// A controlling station with the originator address.
+	Upstream(u8),
+	/// The gateway itself, for the interrogations of the stations once their
+	/// link is up.
+	Gateway,
+}
+
+/// The state shared by the IEC 104 command callback and the station tasks.
+#[derive(Debug)]
+struct Routes {
+	stations: Vec<Station>,
+	/// The station and the IEC 101 common address of every IEC 104 one.
+	downstream_addresses: HashMap<u16, (usize, u16)>,
+	/// The IEC 104 side, set once the server is created.
+	upstream: OnceLock<Upstream>,
+	/// The stations are interrogated once their link is up, to fill the
+	/// process image the interrogations are answered from.
+	interrogate: bool,
+}
+
+#[derive(Debug)]
+struct Upstream {
+	monitor_tx: broadcast::Sender<Asdu>,
+	database: Arc<PointDatabase>,
+}
+
+impl Routes {
+	/// Sends an ASDU received from a station to the controlling stations,
+	/// translated to its IEC 104 common address. The monitor data is also
+	/// stored in the process image of the gateway.
+	#[instrument(level = "debug", skip(self))]
+	fn upstream(&self, station: usize, mut asdu: Asdu) {
+		let (Some(upstream), station) = (self.upstream.get(), &self.stations[station]) else {
+			return;
+		};
+		let Some(&common_address) = station.upstream_addresses.get(&asdu.address_field) else {
+			tracing::warn!(
+				"Dropping ASDU of the unmapped common address {} of station {}",
+				asdu.address_field,
+				station.name
+			);
+			return;
+		};
+
+		if matches!(
+			asdu.cot,
+			Cot::ActivationConfirmation
+				| Cot::DeactivationConfirmation
+				| Cot::ActivationTermination
+				| Cot::UnknownType
+				| Cot::UnknownCause
+				| Cot::UnknownAsduAddress
+				| Cot::UnknownObjectAddress
+		) {
+			match station
+				.originators
+				.lock()
+				.unwrap_or_else(PoisonError::into_inner)
+				.get(&command_key(&asdu))
+			{
+				Some(Originator::Upstream(originator)) => asdu.originator_address = *originator,
+				// Nobody else asked for it
+				Some(Originator::Gateway) => return,
+				None => (),
+			}
+		}
+		asdu.address_field = common_address;
+		if (asdu.type_id as u8) < 45 {
+			upstream.database.update(common_address, &asdu.information_objects);
+		}
+
+		// Nobody to notify if no controlling station is connected
+		let _ = upstream.monitor_tx.send(asdu);
+	}
+
+	/// Sends the commands of a station one after the other. The failed ones are
+	/// confirmed negatively to the controlling stations.
+	async fn send_commands(
+		self: Arc<Self>,
+		sender: LinkSender,
+		mut commands: mpsc::UnboundedReceiver<Forwarded>,
+	) {
+		while let Some(Forwarded { upstream, link_address, downstream }) = commands.recv().await {
+			if let Err(e) = sender.send_asdu_to(link_address, downstream).await {
+				tracing::warn!("Error forwarding command: {e}");
+				if let Some(Upstream { monitor_tx, .. }) = self.upstream.get() {
+					let _ = monitor_tx.send(Asdu {
+						cot: Cot::ActivationConfirmation,
+						positive: true,
+						..upstream
+					});
+				}
+			}
+		}
+	}
+
+	/// Interrogates every common address reached through the link address
+	/// each time the link to it comes up, so that the process image is filled
+	/// and stays up to date after the station was unreachable.
+	async fn interrogate(
+		self: Arc<Self>,
+		station: usize,
+		link_address: u16,
+		sender: LinkSender,
+		mut link_up: watch::Receiver<bool>,
+	) {
+		loop {
+			if link_up.wait_for(|up| *up).await.is_err() {
+				return;
+			}
+			let station = &self.stations[station];
+			let common_addresses = station
+				.link_addresses
+				.iter()
+				.filter(|(_, address)| **address == link_address)
+				.map(|(common_address, _)| *common_address);
+			for common_address in common_addresses {
+				let interrogation = Asdu {
+					type_id: TypeId::C_IC_NA_1,
+					cot: Cot::Activation,
+					originator_address: 0,
+					address_field: common_address,
+					sequence: false,
+					test: false,
+					positive: false,
+					information_objects: InformationObjects::CIcNa1(vec![GenericObject {
+						address: 0,
+						object: CIcNa1 { qoi: Qoi::Global },
+					}]),
+				};
+				station
+					.originators
+					.lock()
+					.unwrap_or_else(PoisonError::into_inner)
+					.insert(command_key(&interrogation), Originator::Gateway);
+				if let Err(e) = sender.send_asdu_to(link_address, interrogation).await {
+					tracing::warn!("Error interrogating station {}: {e}", station.name);
+				}
+			}
+			if link_up.wait_for(|up| !*up).await.is_err() {
+				return;
+			}
+		}
+	}
+}
+
+#[async_trait]
+impl OnNewCommand for Arc<Routes> {
+	async fn on_new_command(&self, asdu: Asdu) -> Vec<Asdu> {
+		let negative = |cot| vec![Asdu { cot, positive: true, ..asdu.clone() }];
+
+		// The commands to the broadcast address are fanned out to every station
+		let targets: Vec<_> = if asdu.address_field == BROADCAST_ADDRESS {
+			(0..self.stations.len()).map(|station| (station, BROADCAST_ADDRESS)).collect()
+		} else if let Some(&target) = self.downstream_addresses.get(&asdu.address_field) {
+			vec![target]
+		} else {
+			return negative(Cot::UnknownAsduAddress);
+		};
+
+		// Every target is checked before the command is forwarded to any of them
+		let mut forwards = Vec::with_capacity(targets.len());
+		for (station, common_address) in targets {
+			let station = &self.stations[station];
+			let Some(commands) = station.commands.get() else {
+				tracing::warn!("The transport of station {} is not attached", station.name);
+				if asdu.address_field == BROADCAST_ADDRESS {
+					continue;
+				}
+				return negative(Cot::ActivationConfirmation);
+			};
+			let fits = asdu
+				.information_objects
+				.addresses()
+				.iter()
+				.all(|address| *address < 1 << (8 * u32::from(station.ioa_size)));
+			if !fits {
+				return negative(Cot::UnknownObjectAddress);
+			}
+			forwards.push((
+				station,
+				commands,
+				Asdu { address_field: common_address, ..asdu.clone() },
+			));
+		}
+
+		for (station, commands, downstream) in forwards {
+			// The responses to a broadcast come from every common address
+			let (type_id, common_address, object) = command_key(&downstream);
+			let common_addresses = if common_address == BROADCAST_ADDRESS {
+				station.upstream_addresses.keys().copied().collect()
+			} else {
+				vec![common_address]
+			};
+			station.originators.lock().unwrap_or_else(PoisonError::into_inner).extend(
+				common_addresses.into_iter().map(|common_address| {
+					(
+						(type_id, common_address, object),
+						Originator::Upstream(asdu.originator_address),
+					)
+				}),
+			);
+			for link_address in station.link_addresses(common_address) {
+				let forwarded = Forwarded {
+					upstream: asdu.clone(),
+					link_address,
+					downstream: downstream.clone(),
+				};
+				if commands.send(forwarded).is_err() {
+					return negative(Cot::ActivationConfirmation);
+				}
+			}
+		}
+		// The responses come from the stations
+		Vec::new()
+	}
+}
+
+/// A gateway between IEC 101 stations and IEC 104 controlling stations.
+///
+/// The gateway is an IEC 104 controlled station of its own. The commands it
+/// receives are forwarded to the IEC 101 station their common address is
+/// mapped to, or to every station for the broadcast address, and the ASDUs of
+/// the stations are sent to the controlling stations. The common addresses are
+/// translated both ways, and the ASDUs are encoded with the field sizes of
+/// each side. When several stations are polled on the same link, the commands
+/// go to the link address their common address is mapped to.
+///
+/// The monitor data of the stations is kept in the process image of the
+/// gateway. The interrogations are answered from it, the gateway interrogating
+/// every station itself each time its link comes up, or forwarded to the
+/// stations like the other commands if
+/// [`ServerConfig::answer_interrogations`](crate::config::ServerConfig::answer_interrogations)
+/// is unset.
+pub struct Gateway {
+	server: Server,
+	routes: Arc<Routes>,
+	links: HashMap<String, (usize, LinkConfig)>,
+	tasks: JoinSet<()>,
+}
+
+impl Gateway {
+	/// Creates the gateway. The server is not started and the transports of
+	/// the stations are not attached yet.
+	///
+	/// Fails if a station name or a common address is configured twice, or if
+	/// a common address is mapped to a link address that is not one of the
+	/// link of its station.
+	pub fn new(config: GatewayConfig) -> Result<Self, GatewayError> {
+		let mut stations = Vec::with_capacity(config.stations.len());
+		let mut downstream_addresses = HashMap::new();
+		let mut links = HashMap::new();
+
+		for (index, station) in config.stations.into_iter().enumerate() {
+			let mut upstream_addresses = HashMap::new();
+			let mut link_addresses = HashMap::new();
+			let link = &station.link;
+			let polls =
+				matches!((link.mode, link.role), (LinkMode::Unbalanced, LinkRole::Controlling));
+			for mapping in station.common_addresses {
+				let link_address = mapping.link_address.unwrap_or(link.address);
+				ensure!(
+					link_address == link.address
+						|| (polls && link.polled_addresses.contains(&link_address)),
+					UnknownLinkAddress { name: &station.name, address: link_address }
+				);
+				link_addresses.insert(mapping.iec101, link_address);
+				ensure!(
+					downstream_addresses.insert(mapping.iec104, (index, mapping.iec101)).is_none(),
+					DuplicateCommonAddress { common_address: mapping.iec104 }
+				);
+				ensure!(
+					upstream_addresses.insert(mapping.iec101, mapping.iec104).is_none(),
+					DuplicateCommonAddress { common_address: mapping.iec101 }
+				);
+			}
+			let Entry::Vacant(entry) = links.entry(station.name.clone()) else {
+				return DuplicateStation { name: station.name }.fail();
+			};
+			entry.insert((index, station.link.clone()));
+			stations.push(Station {
+				name: station.name,
+				upstream_addresses,
+				link_addresses,
+				ioa_size: station.link.asdu_profile.ioa_size,
+				commands: OnceLock::new(),
+				originators: Mutex::new(HashMap::new()),
+			});
+		}
+
+		let routes = Arc::new(Routes {
+			stations,
+			downstream_addresses,
+			upstream: OnceLock::new(),
+			interrogate: config.server.answer_interrogations,
+		});
+		let server = Server::new(config.server, routes.clone());
+		let _ = routes
+			.upstream
+			.set(Upstream { monitor_tx: server.monitor_sender(), database: server.database() });
+		Ok(Self { server, routes, links, tasks: JoinSet::new() })
+	}
+
+	/// Starts the link to the station over its transport, usually a serial
+	/// port. The commands for the station are rejected until it's attached.
+	#[instrument(level = "debug", skip(self, transport))]
+	pub fn attach<T>(&mut self, name: &str, transport: T) -> Result<(), GatewayError>
+	where
+		T: AsyncRead + AsyncWrite + Send + 'static,
+	{
+		let (station, config) = self.links.get(name).cloned().context(UnknownStation { name })?;
+		ensure!(self.routes.stations[station].commands.get().is_none(), AlreadyAttached { name });
+
+		let mut link = Link::spawn(transport, config).context(StartLink { name })?;
+		let (commands_tx, commands_rx) = mpsc::unbounded_channel();
+		let _ = self.routes.stations[station].commands.set(commands_tx);
+
+		self.tasks.spawn(self.routes.clone().send_commands(link.sender(), commands_rx));
+		if self.routes.interrogate {
+			for link_address in self.routes.stations[station].link_addresses(BROADCAST_ADDRESS) {
+				// The link addresses were checked against the configuration of the link
+				let Some(link_up) = link.link_up_to(link_address) else { continue };
+				self.tasks.spawn(self.routes.clone().interrogate(
+					station,
+					link_address,
+					link.sender(),
+					link_up,
+				));
+			}
+		}
+		let routes = self.routes.clone();
+		self.tasks.spawn(async move {
+			while let Some(asdu) = link.recv().await {
+				routes.upstream(station, asdu);
+			}
+			if let Err(e) = link.stopped().await {
+				tracing::error!("Link of station {} stopped: {e}", routes.stations[station].name);
+			}
+		});
+		Ok(())
+	}
+
+	/// Starts listening for the IEC 104 controlling stations.
+	#[instrument(level = "debug")]
+	pub async fn start(&mut self) -> Result<(), Error> {
+		self.server.start().await
+	}
+
+	/// Stops listening, closes the IEC 104 connections and stops the links.
+	#[instrument(level = "debug")]
+	pub fn stop(&mut self) {
+		self.server.stop();
+		self.tasks.abort_all();
+	}
+
+	/// The address the IEC 104 side is listening on, if started.
+	#[must_use]
+	pub const fn local_addr(&self) -> Option<SocketAddr> {
+		self.server.local_addr()
+	}
+
+	/// The process image of the gateway, with the IEC 104 common addresses.
+	#[must_use]
+	pub fn database(&self) -> Arc<PointDatabase> {
+		self.server.database()
+	}
+
+	/// The names of the stations, in the configured order.
+	pub fn stations(&self) -> impl Iterator<Item = &str> {
+		self.routes.stations.iter().map(|station| station.name.as_str())
+	}
+}
+
+impl Debug for Gateway {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("Gateway")
+			.field("server", &self.server)
+			.field("stations", &self.stations().collect::<Vec<_>>())
+			.finish_non_exhaustive()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+
+	use super::*;
+	use crate::{
+		asdu::AsduProfile,
+		client::{Client, OnNewObjects},
+		config::{
+			ClientConfig, CommonAddressMapping, GatewayStationConfig, LinkMode, LinkRole,
+			ServerConfig,
+		},
+		test_util::bus,
+		types::{CIcNa1, CScNa1, GenericObject, InformationObjects, MSpNa1, commands::Qoi},
+		types_id::TypeId,
+	};
+
+	struct Objects(mpsc::UnboundedSender<Asdu>);
+
+	#[async_trait]
+	impl OnNewObjects for Objects {
+		async fn on_new_objects(&self, asdu: Asdu) {
+			self.0.send(asdu).unwrap();
+		}
+	}
+
+	fn asdu(common_address: u16, cot: Cot, information_objects: InformationObjects) -> Asdu {
+		Asdu {
+			type_id: information_objects.type_id().unwrap(),
+			cot,
+			originator_address: 1,
+			address_field: common_address,
+			sequence: false,
+			test: false,
+			positive: false,
+			information_objects,
+		}
+	}
+
+	fn command(common_address: u16, address: u32) -> Asdu {
+		asdu(
+			common_address,
+			Cot::Activation,
+			InformationObjects::CScNa1(vec![GenericObject { address, object: CScNa1::default() }]),
+		)
+	}
+
+	fn link_config(ioa_size: u8) -> LinkConfig {
+		LinkConfig {
+			mode: LinkMode::Unbalanced,
+			role: LinkRole::Controlling,
+			address: 3,
+			polled_addresses: Vec::new(),
+			address_size: 1,
+			asdu_profile: AsduProfile { cot_size: 1, common_address_size: 1, ioa_size },
+			response_timeout: Duration::from_millis(200),
+			retries: 3,
+			poll_interval: Duration::from_millis(20),
+		}
+	}
+
+	/// Starts a gateway with a station per link configuration, mapping the
+	/// IEC 104 common address 47 + index to the common address 1 of the
+	/// station, and the other end of the links. Returns a client receiving from
+	/// the gateway.
+	async fn start_gateway(
+		links: &[LinkConfig],
+		answer_interrogations: bool,
+	) -> (Gateway, Vec<Link>, Client, mpsc::UnboundedReceiver<Asdu>) {
+		let stations = links
+			.iter()
+			.zip(47..)
+			.map(|(link, iec104)| GatewayStationConfig {
+				name: format!("rtu{iec104}"),
+				link: link.clone(),
+				common_addresses: vec![CommonAddressMapping {
+					iec104,
+					iec101: 1,
+					link_address: None,
+				}],
+			})
+			.collect();
+		let mut gateway = Gateway::new(GatewayConfig {
+			server: ServerConfig {
+				address: "127.0.0.1".to_owned(),
+				port: 0,
+				answer_interrogations,
+				..Default::default()
+			},
+			stations,
+		})
+		.unwrap();
+		let mut rtus = Vec::new();
+		for (link, iec104) in links.iter().zip(47..) {
+			let (gateway_side, rtu_side) = tokio::io::duplex(1024);
+			gateway.attach(&format!("rtu{iec104}"), gateway_side).unwrap();
+			rtus.push(
+				Link::spawn(rtu_side, LinkConfig { role: LinkRole::Controlled, ..link.clone() })
+					.unwrap(),
+			);
+		}
+		gateway.start().await.unwrap();
+
+		let (object_tx, object_rx) = mpsc::unbounded_channel();
+		let mut client = Client::new(
+			ClientConfig { port: gateway.local_addr().unwrap().port(), ..Default::default() },
+			Objects(object_tx),
+		);
+		client.connect().await.unwrap();
+		client.start_receiving().await.unwrap();
+		(gateway, rtus, client, object_rx)
+	}
+
+	#[tokio::test]
+	async fn test_gateway() {
+		let (gateway, mut rtus, client, mut object_rx) =
+			start_gateway(&[link_config(2)], false).await;
+		let rtu = &mut rtus[0];
+
+		// The general interrogation of the broadcast address is fanned out to the
+		// station with its own broadcast address
+		let interrogation = asdu(
+			BROADCAST_ADDRESS,
+			Cot::Activation,
+			InformationObjects::CIcNa1(vec![GenericObject {
+				address: 0,
+				object: CIcNa1 { qoi: Qoi::Global },
+			}]),
+		);
+		tokio::time::timeout(Duration::from_secs(5), async {
+			while client.send_asdu(interrogation.clone()).await.is_err() {
+				tokio::time::sleep(Duration::from_millis(10)).await;
+			}
+		})
+		.await
+		.unwrap();
+		let received = rtu.recv().await.unwrap();
+		assert_eq!(received.type_id, TypeId::C_IC_NA_1);
+		assert_eq!(received.address_field, BROADCAST_ADDRESS);
+
+		// The station answers with its common address, which is translated back
+		let point = InformationObjects::MSpNa1(vec![GenericObject {
+			address: 200,
+			object: MSpNa1::default(),
+		}]);
+		let responses = [
+			Asdu { cot: Cot::ActivationConfirmation, address_field: 1, ..received.clone() },
+			asdu(1, Cot::InterrogationGeneral, point.clone()),
+			Asdu { cot: Cot::ActivationTermination, address_field: 1, ..received },
+		];
+		for response in responses {
+			rtu.send_asdu(response.clone()).await.unwrap();
+			let forwarded = object_rx.recv().await.unwrap();
+			assert_eq!(forwarded.type_id, response.type_id);
+			assert_eq!(forwarded.cot, response.cot);
+			assert_eq!(forwarded.address_field, 47);
+			assert_eq!(forwarded.information_objects, response.information_objects);
+		}
+		assert_eq!(gateway.database().get(47, 200), Some(point));
+
+		client.send_asdu(command(47, 100)).await.unwrap();
+		let received = rtu.recv().await.unwrap();
+		assert_eq!(received.type_id, TypeId::C_SC_NA_1);
+		assert_eq!(received.address_field, 1);
+		assert_eq!(received.information_objects.addresses(), vec![100]);
+
+		// Rejected by the gateway: the address doesn't fit the 2 bytes of the link, or
+		// the common address is not mapped
+		for (command, cot) in [
+			(command(47, 0x1_0000), Cot::UnknownObjectAddress),
+			(command(48, 100), Cot::UnknownAsduAddress),
+		] {
+			client.send_asdu(command).await.unwrap();
+			let rejected = object_rx.recv().await.unwrap();
+			assert_eq!(rejected.cot, cot);
+			assert!(rejected.positive);
+		}
+
+		let spontaneous = asdu(
+			1,
+			Cot::SpontaneousData,
+			InformationObjects::MSpNa1(vec![GenericObject {
+				address: 201,
+				object: MSpNa1::default(),
+			}]),
+		);
+		rtu.send_asdu(spontaneous.clone()).await.unwrap();
+		let forwarded = object_rx.recv().await.unwrap();
+		assert_eq!(forwarded.address_field, 47);
+		assert_eq!(forwarded.information_objects, spontaneous.information_objects);
+		assert!(gateway.database().get(47, 201).is_some());
+	}
+
+	#[tokio::test]
+	async fn test_originators() {
+		let (_gateway, mut rtus, client, mut object_rx) =
+			start_gateway(&[link_config(2)], false).await;
+		let rtu = &mut rtus[0];
+
+		// Commands of the same type from two controlling stations, to different
+		// objects
+		for (address, originator_address) in [(100, 7), (101, 8)] {
+			let command = Asdu { originator_address, ..command(47, address) };
+			tokio::time::timeout(Duration::from_secs(5), async {
+				while client.send_asdu(command.clone()).await.is_err() {
+					tokio::time::sleep(Duration::from_millis(10)).await;
+				}
+			})
+			.await
+			.unwrap();
+			assert_eq!(rtu.recv().await.unwrap().information_objects.addresses(), [address]);
+		}
+
+		// The originator is not carried by the link, it's restored per object
+		for (address, originator_address) in [(101, 8), (100, 7)] {
+			let confirmation = Asdu {
+				cot: Cot::ActivationConfirmation,
+				originator_address: 0,
+				..command(1, address)
+			};
+			rtu.send_asdu(confirmation).await.unwrap();
+			let forwarded = object_rx.recv().await.unwrap();
+			assert_eq!(forwarded.cot, Cot::ActivationConfirmation);
+			assert_eq!(forwarded.information_objects.addresses(), [address]);
+			assert_eq!(forwarded.originator_address, originator_address);
+		}
+	}
+
+	#[tokio::test]
+	async fn test_broadcast_checked_first() {
+		let (_gateway, mut rtus, client, mut object_rx) =
+			start_gateway(&[link_config(3), link_config(2)], false).await;
+
+		// The address fits the first station but not the second one
+		let command = command(BROADCAST_ADDRESS, 0x1_0000);
+		tokio::time::timeout(Duration::from_secs(5), async {
+			while client.send_asdu(command.clone()).await.is_err() {
+				tokio::time::sleep(Duration::from_millis(10)).await;
+			}
+		})
+		.await
+		.unwrap();
+		let rejected = object_rx.recv().await.unwrap();
+		assert_eq!(rejected.cot, Cot::UnknownObjectAddress);
+		assert!(rejected.positive);
+		for rtu in &mut rtus {
+			assert!(tokio::time::timeout(Duration::from_millis(200), rtu.recv()).await.is_err());
+		}
+	}
+
+	#[tokio::test]
+	async fn test_interrogation_on_link_up() {
+		let (gateway, mut rtus, _client, mut object_rx) =
+			start_gateway(&[link_config(2)], true).await;
+		let rtu = &mut rtus[0];
+
+		let received = rtu.recv().await.unwrap();
+		assert_eq!(received.type_id, TypeId::C_IC_NA_1);
+		assert_eq!(received.address_field, 1);
+
+		// The confirmations of the own interrogation of the gateway are not
+		// forwarded, the data is
+		let point = InformationObjects::MSpNa1(vec![GenericObject {
+			address: 200,
+			object: MSpNa1::default(),
+		}]);
+		for response in [
+			Asdu { cot: Cot::ActivationConfirmation, ..received.clone() },
+			asdu(1, Cot::InterrogationGeneral, point.clone()),
+			Asdu { cot: Cot::ActivationTermination, ..received },
+		] {
+			rtu.send_asdu(response).await.unwrap();
+		}
+		let forwarded = object_rx.recv().await.unwrap();
+		assert_eq!(forwarded.cot, Cot::InterrogationGeneral);
+		assert_eq!(forwarded.address_field, 47);
+		assert!(tokio::time::timeout(Duration::from_millis(200), object_rx.recv()).await.is_err());
+		assert_eq!(gateway.database().get(47, 200), Some(point));
+	}
+
+	#[tokio::test]
+	async fn test_polled_stations() {
+		let link = LinkConfig { polled_addresses: vec![4], ..link_config(2) };
+		let config = |link_address| GatewayConfig {
+			server: ServerConfig {
+				address: "127.0.0.1".to_owned(),
+				port: 0,
+				answer_interrogations: true,
+				..Default::default()
+			},
+			stations: vec![GatewayStationConfig {
+				name: "bus".to_owned(),
+				link: link.clone(),
+				common_addresses: vec![
+					CommonAddressMapping { iec104: 47, iec101: 1, link_address: None },
+					CommonAddressMapping {
+						iec104: 48,
+						iec101: 2,
+						link_address: Some(link_address),
+					},
+				],
+			}],
+		};
+		assert!(matches!(
+			Gateway::new(config(5)),
+			Err(GatewayError::UnknownLinkAddress { address: 5, .. })
+		));
+
+		let mut gateway = Gateway::new(config(4)).unwrap();
+		let (gateway_side, gateway_bus) = tokio::io::duplex(1024);
+		let (rtu_3, rtu_3_bus) = tokio::io::duplex(1024);
+		let (rtu_4, rtu_4_bus) = tokio::io::duplex(1024);
+		tokio::spawn(bus(gateway_bus, rtu_3_bus, rtu_4_bus));
+		gateway.attach("bus", gateway_side).unwrap();
+		let controlled = LinkConfig { role: LinkRole::Controlled, ..link };
+		let mut rtu_3 = Link::spawn(rtu_3, controlled.clone()).unwrap();
+		let mut rtu_4 = Link::spawn(rtu_4, LinkConfig { address: 4, ..controlled }).unwrap();
+		gateway.start().await.unwrap();
+
+		// Every station is interrogated for its own common address once its link is up
+		for (rtu, common_address) in [(&mut rtu_3, 1), (&mut rtu_4, 2)] {
+			let received =
+				tokio::time::timeout(Duration::from_secs(5), rtu.recv()).await.unwrap().unwrap();
+			assert_eq!(received.type_id, TypeId::C_IC_NA_1);
+			assert_eq!(received.address_field, common_address);
+		}
+
+		let (object_tx, _object_rx) = mpsc::unbounded_channel();
+		let mut client = Client::new(
+			ClientConfig { port: gateway.local_addr().unwrap().port(), ..Default::default() },
+			Objects(object_tx),
+		);
+		client.connect().await.unwrap();
+		client.start_receiving().await.unwrap();
+
+		// The commands are sent to the station their common address is reached through
+		tokio::time::timeout(Duration::from_secs(5), async {
+			while client.send_asdu(command(48, 100)).await.is_err() {
+				tokio::time::sleep(Duration::from_millis(10)).await;
+			}
+		})
+		.await
+		.unwrap();
+		let received = rtu_4.recv().await.unwrap();
+		assert_eq!(received.type_id, TypeId::C_SC_NA_1);
+		assert_eq!(received.address_field, 2);
+		assert!(tokio::time::timeout(Duration::from_millis(200), rtu_3.recv()).await.is_err());
+
+		// And to every station for the broadcast address
+		client.send_asdu(command(BROADCAST_ADDRESS, 100)).await.unwrap();
+		for rtu in [&mut rtu_3, &mut rtu_4] {
+			let received =
+				tokio::time::timeout(Duration::from_secs(5), rtu.recv()).await.unwrap().unwrap();
+			assert_eq!(received.address_field, BROADCAST_ADDRESS);
+		}
+	}
+}
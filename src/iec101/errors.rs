@@ -0,0 +1,103 @@
+use snafu::Snafu;
+
+use crate::{
+	asdu::AsduError,
+	error::SpanTraceWrapper,
+	iec101::frame::{PrimaryFunction, SecondaryFunction},
+};
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub), context(suffix(false)))]
+pub enum LinkError {
+	#[snafu(display("Invalid start character {byte:#04x}"))]
+	InvalidStartCharacter {
+		byte: u8,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("The two length fields differ: {first} and {second}"))]
+	LengthMismatch {
+		first: u8,
+		second: u8,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Invalid checksum. Expected: {expected:#04x}, Received: {received:#04x}"))]
+	InvalidChecksum {
+		expected: u8,
+		received: u8,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Invalid end character {byte:#04x}"))]
+	InvalidEndCharacter {
+		byte: u8,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Frame too short for its link address"))]
+	FrameTooShort {
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("User data of {len} bytes doesn't fit in a frame"))]
+	FrameTooLong {
+		len: usize,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Invalid link address size {size}"))]
+	InvalidAddressSize {
+		size: u8,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Link address {address} doesn't fit in the link address field"))]
+	AddressTooLarge {
+		address: u16,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Invalid ASDU"))]
+	InvalidAsdu {
+		source: AsduError,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Error exchanging data with the other station"))]
+	Io {
+		source: std::io::Error,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("The transport was closed"))]
+	TransportClosed {
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("No response to the {function:?} after {attempts} attempts"))]
+	NoResponse {
+		function: PrimaryFunction,
+		attempts: u8,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("The other station answered the {function:?} with {response:?}"))]
+	Rejected {
+		function: PrimaryFunction,
+		response: SecondaryFunction,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("No station of the link has the link address {address}"))]
+	UnknownStation {
+		address: u16,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("The link is not running anymore"))]
+	LinkStopped {
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+}
@@ -0,0 +1,405 @@
+use snafu::{OptionExt as _, ResultExt as _, ensure};
+use tracing::instrument;
+
+use crate::{
+	asdu::{Asdu, AsduProfile},
+	iec101::errors::{self, LinkError},
+};
+
+/// The single character acknowledge.
+pub const ACK_CHARACTER: u8 = 0xE5;
+const FIXED_START: u8 = 0x10;
+const VARIABLE_START: u8 = 0x68;
+const END_CHARACTER: u8 = 0x16;
+
+/// The function codes of the frames sent by the primary station, the one
+/// initiating the transmission.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrimaryFunction {
+	ResetLink,
+	ResetProcess,
+	TestLink,
+	UserDataConfirm,
+	UserDataNoReply,
+	RequestAccessDemand,
+	RequestStatus,
+	RequestClass1,
+	RequestClass2,
+	Reserved(u8),
+}
+
+/// The function codes of the frames sent by the secondary station in response
+/// to a primary frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecondaryFunction {
+	Ack,
+	Nack,
+	UserData,
+	NoData,
+	Status,
+	NotFunctioning,
+	NotImplemented,
+	Reserved(u8),
+}
+
+impl PrimaryFunction {
+	const fn from_code(code: u8) -> Self {
+		match code {
+			0 => PrimaryFunction::ResetLink,
+			1 => PrimaryFunction::ResetProcess,
+			2 => PrimaryFunction::TestLink,
+			3 => PrimaryFunction::UserDataConfirm,
+			4 => PrimaryFunction::UserDataNoReply,
+			8 => PrimaryFunction::RequestAccessDemand,
+			9 => PrimaryFunction::RequestStatus,
+			10 => PrimaryFunction::RequestClass1,
+			11 => PrimaryFunction::RequestClass2,
+			code => PrimaryFunction::Reserved(code),
+		}
+	}
+
+	const fn code(self) -> u8 {
+		match self {
+			PrimaryFunction::ResetLink => 0,
+			PrimaryFunction::ResetProcess => 1,
+			PrimaryFunction::TestLink => 2,
+			PrimaryFunction::UserDataConfirm => 3,
+			PrimaryFunction::UserDataNoReply => 4,
+			PrimaryFunction::RequestAccessDemand => 8,
+			PrimaryFunction::RequestStatus => 9,
+			PrimaryFunction::RequestClass1 => 10,
+			PrimaryFunction::RequestClass2 => 11,
+			PrimaryFunction::Reserved(code) => code,
+		}
+	}
+
+	/// Whether the frame count bit is valid with this function, so that the
+	/// frame is repeated with the same FCB if unanswered.
+	#[must_use]
+	pub const fn has_valid_fcb(self) -> bool {
+		matches!(
+			self,
+			PrimaryFunction::TestLink
+				| PrimaryFunction::UserDataConfirm
+				| PrimaryFunction::RequestClass1
+				| PrimaryFunction::RequestClass2
+		)
+	}
+}
+
+impl SecondaryFunction {
+	const fn from_code(code: u8) -> Self {
+		match code {
+			0 => SecondaryFunction::Ack,
+			1 => SecondaryFunction::Nack,
+			8 => SecondaryFunction::UserData,
+			9 => SecondaryFunction::NoData,
+			11 => SecondaryFunction::Status,
+			14 => SecondaryFunction::NotFunctioning,
+			15 => SecondaryFunction::NotImplemented,
+			code => SecondaryFunction::Reserved(code),
+		}
+	}
+
+	const fn code(self) -> u8 {
+		match self {
+			SecondaryFunction::Ack => 0,
+			SecondaryFunction::Nack => 1,
+			SecondaryFunction::UserData => 8,
+			SecondaryFunction::NoData => 9,
+			SecondaryFunction::Status => 11,
+			SecondaryFunction::NotFunctioning => 14,
+			SecondaryFunction::NotImplemented => 15,
+			SecondaryFunction::Reserved(code) => code,
+		}
+	}
+}
+
+/// The control field of a fixed or variable length frame.
+///
+/// The direction bit is only used on balanced links, where it's set on the
+/// frames sent by the controlling station. It's reserved, and left unset, on
+/// unbalanced links.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlField {
+	Primary {
+		dir: bool,
+		/// Frame count bit, toggled for every new transmission.
+		fcb: bool,
+		/// Frame count bit valid.
+		fcv: bool,
+		function: PrimaryFunction,
+	},
+	Secondary {
+		dir: bool,
+		/// Access demand: the controlled station has class 1 data to send.
+		acd: bool,
+		/// Data flow control: further messages may overflow the controlled
+		/// station.
+		dfc: bool,
+		function: SecondaryFunction,
+	},
+}
+
+impl ControlField {
+	const fn from_byte(byte: u8) -> Self {
+		let dir = byte & 0b1000_0000 != 0;
+		let bit_5 = byte & 0b0010_0000 != 0;
+		let bit_4 = byte & 0b0001_0000 != 0;
+		let code = byte & 0b0000_1111;
+		if byte & 0b0100_0000 != 0 {
+			ControlField::Primary {
+				dir,
+				fcb: bit_5,
+				fcv: bit_4,
+				function: PrimaryFunction::from_code(code),
+			}
+		} else {
+			ControlField::Secondary {
+				dir,
+				acd: bit_5,
+				dfc: bit_4,
+				function: SecondaryFunction::from_code(code),
+			}
+		}
+	}
+
+	const fn to_byte(self) -> u8 {
+		let (prm, dir, bit_5, bit_4, code) = match self {
+			ControlField::Primary { dir, fcb, fcv, function } => {
+				(true, dir, fcb, fcv, function.code())
+			}
+			ControlField::Secondary { dir, acd, dfc, function } => {
+				(false, dir, acd, dfc, function.code())
+			}
+		};
+		((dir as u8) << 7)
+			| ((prm as u8) << 6)
+			| ((bit_5 as u8) << 5)
+			| ((bit_4 as u8) << 4)
+			| (code & 0b0000_1111)
+	}
+}
+
+/// An FT1.2 frame.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Ft12Frame {
+	/// The single character acknowledge, which can replace a positive
+	/// acknowledge or a "no data" response.
+	SingleAck,
+	/// A frame without user data.
+	Fixed { control: ControlField, address: u16 },
+	/// A frame carrying an ASDU.
+	Variable { control: ControlField, address: u16, asdu: Asdu },
+}
+
+impl Ft12Frame {
+	/// The control field of the frame, `None` for the single character.
+	#[must_use]
+	pub const fn control(&self) -> Option<ControlField> {
+		match self {
+			Ft12Frame::SingleAck => None,
+			Ft12Frame::Fixed { control, .. } | Ft12Frame::Variable { control, .. } => {
+				Some(*control)
+			}
+		}
+	}
+}
+
+/// Encodes and decodes FT1.2 frames with the link address size and the ASDU
+/// profile of a link.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ft12Codec {
+	address_size: u8,
+	profile: AsduProfile,
+}
+
+impl Ft12Codec {
+	/// Fails if the link address size is larger than 2 bytes.
+	pub fn new(address_size: u8, profile: AsduProfile) -> Result<Self, LinkError> {
+		ensure!(address_size <= 2, errors::InvalidAddressSize { size: address_size });
+		Ok(Self { address_size, profile })
+	}
+
+	/// Decodes the frame at the start of the bytes. Returns the frame and the
+	/// number of bytes it took, or `None` if the frame is not complete yet.
+	#[instrument(level = "trace")]
+	pub fn decode(&self, bytes: &[u8]) -> Result<Option<(Ft12Frame, usize)>, LinkError> {
+		let address_size = self.address_size as usize;
+		let Some(&start) = bytes.first() else { return Ok(None) };
+		match start {
+			ACK_CHARACTER => Ok(Some((Ft12Frame::SingleAck, 1))),
+			FIXED_START => {
+				// Start, control field, address, checksum and end
+				let length = address_size + 4;
+				let Some(frame) = bytes.get(..length) else { return Ok(None) };
+				Self::check_trailer(&frame[1..length - 2], frame[length - 2], frame[length - 1])?;
+				let control = ControlField::from_byte(frame[1]);
+				let address = Self::read_address(&frame[2..2 + address_size]);
+				Ok(Some((Ft12Frame::Fixed { control, address }, length)))
+			}
+			VARIABLE_START => {
+				let [_, first, second, start, ..] = bytes[..] else { return Ok(None) };
+				ensure!(first == second, errors::LengthMismatch { first, second });
+				ensure!(start == VARIABLE_START, errors::InvalidStartCharacter { byte: start });
+				// The length counts the control field, the address and the ASDU
+				let user_data_length = first as usize;
+				let length = user_data_length + 6;
+				let Some(frame) = bytes.get(..length) else { return Ok(None) };
+				let user_data = &frame[4..4 + user_data_length];
+				Self::check_trailer(user_data, frame[length - 2], frame[length - 1])?;
+				let asdu = user_data.get(1 + address_size..).context(errors::FrameTooShort)?;
+				Ok(Some((
+					Ft12Frame::Variable {
+						control: ControlField::from_byte(user_data[0]),
+						address: Self::read_address(&user_data[1..=address_size]),
+						asdu: Asdu::parse_with_profile(asdu, self.profile)
+							.context(errors::InvalidAsdu)?,
+					},
+					length,
+				)))
+			}
+			byte => errors::InvalidStartCharacter { byte }.fail(),
+		}
+	}
+
+	/// Appends the encoded frame to the buffer.
+	pub fn encode(&self, frame: &Ft12Frame, buffer: &mut Vec<u8>) -> Result<(), LinkError> {
+		match frame {
+			Ft12Frame::SingleAck => buffer.push(ACK_CHARACTER),
+			Ft12Frame::Fixed { control, address } => {
+				buffer.push(FIXED_START);
+				let user_data = buffer.len();
+				buffer.push(control.to_byte());
+				self.write_address(*address, buffer)?;
+				Self::push_trailer(buffer, user_data);
+			}
+			Ft12Frame::Variable { control, address, asdu } => {
+				buffer.extend_from_slice(&[VARIABLE_START, 0, 0, VARIABLE_START]);
+				let user_data = buffer.len();
+				buffer.push(control.to_byte());
+				self.write_address(*address, buffer)?;
+				asdu.to_bytes_with_profile(self.profile, buffer).context(errors::InvalidAsdu)?;
+				let len = buffer.len() - user_data;
+				let length = u8::try_from(len).ok().context(errors::FrameTooLong { len })?;
+				buffer[user_data - 3] = length;
+				buffer[user_data - 2] = length;
+				Self::push_trailer(buffer, user_data);
+			}
+		}
+		Ok(())
+	}
+
+	fn checksum(user_data: &[u8]) -> u8 {
+		user_data.iter().fold(0, |sum, byte| sum.wrapping_add(*byte))
+	}
+
+	fn check_trailer(user_data: &[u8], checksum: u8, end: u8) -> Result<(), LinkError> {
+		let expected = Self::checksum(user_data);
+		ensure!(checksum == expected, errors::InvalidChecksum { expected, received: checksum });
+		ensure!(end == END_CHARACTER, errors::InvalidEndCharacter { byte: end });
+		Ok(())
+	}
+
+	/// Appends the checksum of the user data starting at `user_data` and the
+	/// end character.
+	fn push_trailer(buffer: &mut Vec<u8>, user_data: usize) {
+		buffer.push(Self::checksum(&buffer[user_data..]));
+		buffer.push(END_CHARACTER);
+	}
+
+	fn read_address(bytes: &[u8]) -> u16 {
+		bytes.iter().rev().fold(0, |address, byte| (address << 8) | u16::from(*byte))
+	}
+
+	fn write_address(self, address: u16, buffer: &mut Vec<u8>) -> Result<(), LinkError> {
+		match self.address_size {
+			0 => {}
+			1 => buffer
+				.push(u8::try_from(address).ok().context(errors::AddressTooLarge { address })?),
+			_ => buffer.extend_from_slice(&address.to_le_bytes()),
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		cot::Cot,
+		types::{CIcNa1, GenericObject, InformationObjects, commands::Qoi},
+		types_id::TypeId,
+	};
+
+	#[test]
+	fn test_ft12_frames() -> Result<(), LinkError> {
+		let profile = AsduProfile { cot_size: 1, common_address_size: 1, ioa_size: 2 };
+		let codec = Ft12Codec::new(1, profile)?;
+
+		// Reset of the remote link of the station 1
+		let bytes = [0x10, 0x40, 0x01, 0x41, 0x16];
+		let Some((frame, length)) = codec.decode(&bytes)? else {
+			panic!("Frame was expected to be complete")
+		};
+		assert_eq!(length, bytes.len());
+		assert_eq!(
+			frame,
+			Ft12Frame::Fixed {
+				control: ControlField::Primary {
+					dir: false,
+					fcb: false,
+					fcv: false,
+					function: PrimaryFunction::ResetLink,
+				},
+				address: 1,
+			}
+		);
+		let mut buffer = Vec::new();
+		codec.encode(&frame, &mut buffer)?;
+		assert_eq!(buffer, bytes);
+
+		// General interrogation sent with SEND/CONFIRM and the FCB set
+		let bytes = [
+			0x68, 0x09, 0x09, 0x68, 0x73, 0x01, 0x64, 0x01, 0x06, 0x01, 0x00, 0x00, 0x14, 0xF4,
+			0x16,
+		];
+		assert!(codec.decode(&bytes[..bytes.len() - 1])?.is_none());
+		let Some((frame, length)) = codec.decode(&bytes)? else {
+			panic!("Frame was expected to be complete")
+		};
+		assert_eq!(length, bytes.len());
+		let Ft12Frame::Variable { control, address, asdu } = &frame else {
+			panic!("Frame was expected to be a variable length frame")
+		};
+		assert_eq!(
+			*control,
+			ControlField::Primary {
+				dir: false,
+				fcb: true,
+				fcv: true,
+				function: PrimaryFunction::UserDataConfirm,
+			}
+		);
+		assert_eq!(*address, 1);
+		assert_eq!(asdu.type_id, TypeId::C_IC_NA_1);
+		assert_eq!(asdu.cot, Cot::Activation);
+		assert_eq!(asdu.address_field, 1);
+		assert_eq!(
+			asdu.information_objects,
+			InformationObjects::CIcNa1(vec![GenericObject {
+				address: 0,
+				object: CIcNa1 { qoi: Qoi::Global }
+			}])
+		);
+		buffer.clear();
+		codec.encode(&frame, &mut buffer)?;
+		assert_eq!(buffer, bytes);
+
+		let mut corrupted = bytes;
+		corrupted[13] = 0;
+		assert!(matches!(codec.decode(&corrupted), Err(LinkError::InvalidChecksum { .. })));
+
+		Ok(())
+	}
+}
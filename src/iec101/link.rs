@@ -0,0 +1,1025 @@
+use std::collections::VecDeque;
+
+use snafu::{OptionExt as _, ResultExt as _};
+use tokio::{
+	io::{AsyncRead, AsyncReadExt as _, AsyncWrite, AsyncWriteExt as _, ReadHalf, WriteHalf},
+	select,
+	sync::{mpsc, oneshot, watch},
+	task::JoinHandle,
+	time::{Instant, sleep_until},
+};
+use tracing::instrument;
+
+use crate::{
+	asdu::Asdu,
+	config::{LinkConfig, LinkMode, LinkRole},
+	cot::Cot,
+	iec101::{
+		errors::{self, LinkError},
+		frame::{ControlField, Ft12Codec, Ft12Frame, PrimaryFunction, SecondaryFunction},
+	},
+};
+
+/// An ASDU to send to the station with the link address, with who to notify
+/// once it's delivered.
+#[derive(Debug)]
+struct Outgoing {
+	address: u16,
+	asdu: Asdu,
+	done: oneshot::Sender<Result<(), LinkError>>,
+}
+
+/// A handle to send ASDUs on a [`Link`] from other tasks.
+#[derive(Debug, Clone)]
+pub struct LinkSender {
+	address: u16,
+	outgoing: mpsc::Sender<Outgoing>,
+}
+
+impl LinkSender {
+	/// Sends the ASDU to the other station. See [`Link::send_asdu`].
+	#[instrument(level = "debug", skip(self))]
+	pub async fn send_asdu(&self, asdu: Asdu) -> Result<(), LinkError> {
+		self.send_asdu_to(self.address, asdu).await
+	}
+
+	/// Sends the ASDU to the controlled station with the link address. See
+	/// [`Link::send_asdu_to`].
+	#[instrument(level = "debug", skip(self))]
+	pub async fn send_asdu_to(&self, address: u16, asdu: Asdu) -> Result<(), LinkError> {
+		let (done, result) = oneshot::channel();
+		self.outgoing
+			.send(Outgoing { address, asdu, done })
+			.await
+			.ok()
+			.context(errors::LinkStopped)?;
+		result.await.ok().context(errors::LinkStopped)?
+	}
+}
+
+/// An IEC 60870-5-101 link over an FT1.2 transport, usually a serial port.
+///
+/// The link procedures run in their own task. The controlling station of an
+/// unbalanced link, and both stations of a balanced link, request the status
+/// of the link and reset it before sending user data, and do it again once the
+/// other station stops responding. The controlling station of an unbalanced
+/// link polls the class 2 data every poll interval, and the class 1 data as
+/// long as the controlled station demands access.
+///
+/// The controlling station of an unbalanced link can share the transport with
+/// several controlled stations, on a party line or an RS-485 bus, listed in
+/// [`LinkConfig::polled_addresses`]. Each of them has its own link, brought up
+/// and polled in turn, and only one request is outstanding on the transport at
+/// a time.
+pub struct Link {
+	sender: LinkSender,
+	asdus: mpsc::UnboundedReceiver<Asdu>,
+	/// Whether the link to every station is up, by link address.
+	link_up: Vec<(u16, watch::Receiver<bool>)>,
+	task: JoinHandle<Result<(), LinkError>>,
+}
+
+impl Link {
+	/// Starts the link procedures on the transport.
+	///
+	/// Fails if the link address size of the configuration is invalid.
+	pub fn spawn<T>(transport: T, config: LinkConfig) -> Result<Self, LinkError>
+	where
+		T: AsyncRead + AsyncWrite + Send + 'static,
+	{
+		let codec = Ft12Codec::new(config.address_size, config.asdu_profile)?;
+		let (outgoing_tx, outgoing_rx) = mpsc::channel(1);
+		let (asdus_tx, asdus_rx) = mpsc::unbounded_channel();
+		let (reader, writer) = tokio::io::split(transport);
+		let address = config.address;
+		let mut addresses = vec![address];
+		if matches!((config.mode, config.role), (LinkMode::Unbalanced, LinkRole::Controlling)) {
+			for &polled in &config.polled_addresses {
+				if !addresses.contains(&polled) {
+					addresses.push(polled);
+				}
+			}
+		}
+		let (link_up_tx, link_up_rx): (Vec<_>, Vec<_>) = addresses
+			.iter()
+			.map(|&address| {
+				let (tx, rx) = watch::channel(false);
+				(tx, (address, rx))
+			})
+			.unzip();
+		let task = LinkTask {
+			reader,
+			writer,
+			codec,
+			asdus: asdus_tx,
+			link_up: link_up_tx,
+			stations: addresses.into_iter().map(Station::new).collect(),
+			next_station: 0,
+			pending: None,
+			last_fcb: None,
+			last_response: None,
+			class_1: VecDeque::new(),
+			class_2: VecDeque::new(),
+			config,
+		};
+		Ok(Self {
+			sender: LinkSender { address, outgoing: outgoing_tx },
+			asdus: asdus_rx,
+			link_up: link_up_rx,
+			task: tokio::spawn(task.run(outgoing_rx)),
+		})
+	}
+
+	/// Sends the ASDU to the other station.
+	///
+	/// Waits until the link is up and returns once the other station confirmed
+	/// the frame. On the controlled station of an unbalanced link the ASDU
+	/// waits to be polled instead, as class 2 data if it's cyclic or
+	/// background data and as class 1 data otherwise, and this returns once
+	/// it's sent.
+	pub async fn send_asdu(&self, asdu: Asdu) -> Result<(), LinkError> {
+		self.sender.send_asdu(asdu).await
+	}
+
+	/// Sends the ASDU to the controlled station with the link address, which
+	/// is either [`LinkConfig::address`] or one of the
+	/// [`LinkConfig::polled_addresses`]. The same as [`Link::send_asdu`] on the
+	/// other stations, which only have one other end.
+	///
+	/// Fails if the address is not one of the stations of the link.
+	pub async fn send_asdu_to(&self, address: u16, asdu: Asdu) -> Result<(), LinkError> {
+		self.sender.send_asdu_to(address, asdu).await
+	}
+
+	/// A handle to send ASDUs while the link is used to receive them.
+	#[must_use]
+	pub fn sender(&self) -> LinkSender {
+		self.sender.clone()
+	}
+
+	/// Whether the link to the station with [`LinkConfig::address`] is up, as
+	/// seen by the link procedures of the primary station. Always `false` on
+	/// the controlled station of an unbalanced link, which doesn't run them.
+	#[must_use]
+	pub fn link_up(&self) -> watch::Receiver<bool> {
+		self.link_up[0].1.clone()
+	}
+
+	/// Whether the link to the controlled station with the link address is up,
+	/// like [`Link::link_up`]. The address is either [`LinkConfig::address`] or
+	/// one of the [`LinkConfig::polled_addresses`], `None` otherwise.
+	#[must_use]
+	pub fn link_up_to(&self, address: u16) -> Option<watch::Receiver<bool>> {
+		self.link_up
+			.iter()
+			.find(|(link_address, _)| *link_address == address)
+			.map(|(_, rx)| rx.clone())
+	}
+
+	/// The next ASDU received from the other station. Returns `None` once the
+	/// link stopped.
+	pub async fn recv(&mut self) -> Option<Asdu> {
+		self.asdus.recv().await
+	}
+
+	/// Waits for the link to stop, returning why it stopped. A link only stops
+	/// when the transport fails or is closed.
+	pub async fn stopped(&mut self) -> Result<(), LinkError> {
+		(&mut self.task).await.ok().context(errors::LinkStopped)?
+	}
+}
+
+impl std::fmt::Debug for Link {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("Link").field("task", &self.task).finish_non_exhaustive()
+	}
+}
+
+impl Drop for Link {
+	fn drop(&mut self) {
+		self.task.abort();
+	}
+}
+
+/// The primary frame waiting for the response of the other station.
+#[derive(Debug)]
+struct Pending {
+	/// The index of the station the frame was sent to.
+	station: usize,
+	function: PrimaryFunction,
+	frame: Ft12Frame,
+	attempts: u8,
+	deadline: Instant,
+	done: Option<oneshot::Sender<Result<(), LinkError>>>,
+}
+
+/// The state of the link to one of the stations the primary station sends
+/// requests to.
+#[derive(Debug)]
+struct Station {
+	address: u16,
+	link_up: bool,
+	status_received: bool,
+	next_fcb: bool,
+	/// The controlled station has class 1 data to send.
+	access_demand: bool,
+	/// When the class 2 data is polled next, or the link is brought up again
+	/// after it went down.
+	next_poll: Instant,
+	/// The user data waiting for the link to be up.
+	waiting: VecDeque<Outgoing>,
+}
+
+impl Station {
+	fn new(address: u16) -> Self {
+		Self {
+			address,
+			link_up: false,
+			status_received: false,
+			next_fcb: false,
+			access_demand: false,
+			next_poll: Instant::now(),
+			waiting: VecDeque::new(),
+		}
+	}
+}
+
+struct LinkTask<T> {
+	reader: ReadHalf<T>,
+	writer: WriteHalf<T>,
+	codec: Ft12Codec,
+	config: LinkConfig,
+	asdus: mpsc::UnboundedSender<Asdu>,
+	/// Whether the link to every station is up, in the order of the stations.
+	link_up: Vec<watch::Sender<bool>>,
+
+	// Primary station
+	/// The stations of the link, only the controlled stations polled by the
+	/// controlling station of an unbalanced link can be more than one.
+	stations: Vec<Station>,
+	/// The station the next request is looked for first, so that they are
+	/// served in turn.
+	next_station: usize,
+	pending: Option<Pending>,
+
+	// Secondary station
+	/// The FCB of the last primary frame with a valid FCB, to detect the
+	/// repetitions.
+	last_fcb: Option<bool>,
+	last_response: Option<Ft12Frame>,
+	class_1: VecDeque<Outgoing>,
+	class_2: VecDeque<Outgoing>,
+}
+
+impl<T: AsyncRead + AsyncWrite> LinkTask<T> {
+	const fn is_polled(&self) -> bool {
+		matches!(self.config.mode, LinkMode::Unbalanced)
+			&& matches!(self.config.role, LinkRole::Controlled)
+	}
+
+	const fn polls(&self) -> bool {
+		matches!(self.config.mode, LinkMode::Unbalanced)
+			&& matches!(self.config.role, LinkRole::Controlling)
+	}
+
+	const fn dir(&self) -> bool {
+		matches!(self.config.mode, LinkMode::Balanced)
+			&& matches!(self.config.role, LinkRole::Controlling)
+	}
+
+	async fn run(mut self, mut outgoing: mpsc::Receiver<Outgoing>) -> Result<(), LinkError> {
+		let mut received = Vec::new();
+		let mut chunk = [0; 256];
+
+		loop {
+			if self.pending.is_none() && !self.is_polled() {
+				self.send_next_request().await?;
+			}
+			let idle = self.pending.is_none() && !self.is_polled();
+			let next_poll = self.next_poll();
+
+			select! {
+				read = self.reader.read(&mut chunk) => {
+					let read = read.context(errors::Io)?;
+					if read == 0 {
+						return errors::TransportClosed.fail();
+					}
+					received.extend_from_slice(&chunk[..read]);
+					self.handle_received(&mut received).await?;
+				}
+				Some(outgoing) = outgoing.recv(), if self.is_polled() || idle => {
+					if self.is_polled() {
+						match outgoing.asdu.cot {
+							Cot::Cyclic | Cot::Background => self.class_2.push_back(outgoing),
+							_ => self.class_1.push_back(outgoing),
+						}
+					} else if let Some(station) =
+						self.stations.iter_mut().find(|station| station.address == outgoing.address)
+					{
+						station.waiting.push_back(outgoing);
+					} else {
+						let address = outgoing.address;
+						// The sender might be gone, nobody is waiting for the result then
+						let _ = outgoing.done.send(errors::UnknownStation { address }.fail());
+					}
+				}
+				() = sleep_until(self.pending.as_ref().map_or_else(Instant::now, |p| p.deadline)), if self.pending.is_some() => {
+					self.repeat_pending().await?;
+				}
+				() = sleep_until(next_poll.unwrap_or_else(Instant::now)), if idle && next_poll.is_some() => {}
+			}
+		}
+	}
+
+	/// Sends the next request the stations need, if any: the user data
+	/// waiting for a station whose link is up first, then the requests to
+	/// bring the links up and the polls that are due. The stations are served
+	/// in turn.
+	async fn send_next_request(&mut self) -> Result<(), LinkError> {
+		let now = Instant::now();
+		let count = self.stations.len();
+		let order = (0..count).map(|offset| (self.next_station + offset) % count);
+		let user_data = order.clone().find(|&index| {
+			let station = &self.stations[index];
+			station.link_up && !station.waiting.is_empty()
+		});
+		let request =
+			user_data.map(|index| (index, PrimaryFunction::UserDataConfirm)).or_else(|| {
+				order.clone().find_map(|index| {
+					let station = &self.stations[index];
+					let function = if !station.status_received {
+						(station.next_poll <= now).then_some(PrimaryFunction::RequestStatus)
+					} else if !station.link_up {
+						Some(PrimaryFunction::ResetLink)
+					} else if self.polls() && station.access_demand {
+						Some(PrimaryFunction::RequestClass1)
+					} else if self.polls() && station.next_poll <= now {
+						Some(PrimaryFunction::RequestClass2)
+					} else {
+						None
+					};
+					function.map(|function| (index, function))
+				})
+			});
+
+		let Some((index, function)) = request else { return Ok(()) };
+		self.next_station = (index + 1) % count;
+		let outgoing = if function == PrimaryFunction::UserDataConfirm {
+			self.stations[index].waiting.pop_front()
+		} else {
+			None
+		};
+		match outgoing {
+			Some(outgoing) => {
+				self.send_primary(index, function, Some(outgoing.asdu), Some(outgoing.done)).await
+			}
+			None => self.send_primary(index, function, None, None).await,
+		}
+	}
+
+	/// When a station needs the next request that isn't sent right away: a
+	/// class 2 poll, or bringing a link up again after it went down.
+	fn next_poll(&self) -> Option<Instant> {
+		self.stations
+			.iter()
+			.filter(|station| !station.status_received || (self.polls() && station.link_up))
+			.map(|station| station.next_poll)
+			.min()
+	}
+
+	/// Decodes and handles the complete frames of the received bytes.
+	async fn handle_received(&mut self, received: &mut Vec<u8>) -> Result<(), LinkError> {
+		loop {
+			match self.codec.decode(received) {
+				Ok(Some((frame, length))) => {
+					received.drain(..length);
+					self.handle_frame(frame).await?;
+				}
+				Ok(None) => return Ok(()),
+				Err(e) => {
+					// The frame is dropped, the other station repeats it if needed
+					tracing::warn!("Discarding invalid FT1.2 data: {e}");
+					received.clear();
+					return Ok(());
+				}
+			}
+		}
+	}
+
+	#[instrument(level = "debug", skip(self))]
+	async fn handle_frame(&mut self, frame: Ft12Frame) -> Result<(), LinkError> {
+		// The responses come from the station the pending request was sent to
+		let expected = match (frame.control(), &self.pending) {
+			(Some(ControlField::Primary { .. }), _) | (_, None) => self.config.address,
+			(_, Some(pending)) => self.stations[pending.station].address,
+		};
+		let address = match &frame {
+			Ft12Frame::SingleAck => expected,
+			Ft12Frame::Fixed { address, .. } | Ft12Frame::Variable { address, .. } => *address,
+		};
+		if self.config.address_size > 0 && address != expected {
+			tracing::debug!("Ignoring frame for the link address {address}");
+			return Ok(());
+		}
+
+		match (frame.control(), frame) {
+			(Some(ControlField::Primary { fcb, fcv, function, .. }), frame) => {
+				let asdu = match frame {
+					Ft12Frame::Variable { asdu, .. } => Some(asdu),
+					_ => None,
+				};
+				self.handle_primary(fcb, fcv, function, asdu).await
+			}
+			(Some(ControlField::Secondary { acd, function, .. }), frame) => {
+				let asdu = match frame {
+					Ft12Frame::Variable { asdu, .. } => Some(asdu),
+					_ => None,
+				};
+				self.handle_secondary(acd, function, asdu);
+				Ok(())
+			}
+			(None, _) => {
+				self.handle_secondary(false, SecondaryFunction::Ack, None);
+				Ok(())
+			}
+		}
+	}
+
+	/// Handles a frame of the primary station of the other end, and responds
+	/// to it.
+	async fn handle_primary(
+		&mut self,
+		fcb: bool,
+		fcv: bool,
+		function: PrimaryFunction,
+		asdu: Option<Asdu>,
+	) -> Result<(), LinkError> {
+		if self.polls() {
+			tracing::warn!("Unexpected primary frame {function:?} on the controlling station");
+			return Ok(());
+		}
+		if fcv && self.last_fcb == Some(fcb) {
+			tracing::debug!("Repeating the response to the repeated {function:?}");
+			if let Some(response) = self.last_response.clone() {
+				self.write_frame(&response).await?;
+			}
+			return Ok(());
+		}
+
+		let mut polled = None;
+		let (function, asdu) = match function {
+			PrimaryFunction::ResetLink => {
+				self.last_fcb = None;
+				(SecondaryFunction::Ack, None)
+			}
+			PrimaryFunction::ResetProcess | PrimaryFunction::TestLink => {
+				(SecondaryFunction::Ack, None)
+			}
+			PrimaryFunction::UserDataConfirm | PrimaryFunction::UserDataNoReply => {
+				if let Some(asdu) = asdu {
+					// The receiver might have been dropped, the ASDU is simply discarded then
+					let _ = self.asdus.send(asdu);
+				}
+				if function == PrimaryFunction::UserDataNoReply {
+					return Ok(());
+				}
+				(SecondaryFunction::Ack, None)
+			}
+			PrimaryFunction::RequestStatus | PrimaryFunction::RequestAccessDemand => {
+				(SecondaryFunction::Status, None)
+			}
+			PrimaryFunction::RequestClass1 | PrimaryFunction::RequestClass2 if self.is_polled() => {
+				polled = if function == PrimaryFunction::RequestClass1 {
+					self.class_1.pop_front()
+				} else {
+					self.class_2.pop_front()
+				};
+				match &polled {
+					Some(outgoing) => (SecondaryFunction::UserData, Some(outgoing.asdu.clone())),
+					None => (SecondaryFunction::NoData, None),
+				}
+			}
+			PrimaryFunction::RequestClass1
+			| PrimaryFunction::RequestClass2
+			| PrimaryFunction::Reserved(_) => (SecondaryFunction::NotImplemented, None),
+		};
+
+		let control = ControlField::Secondary {
+			dir: self.dir(),
+			acd: self.is_polled() && !self.class_1.is_empty(),
+			dfc: false,
+			function,
+		};
+		let address = self.config.address;
+		let response = match asdu {
+			Some(asdu) => Ft12Frame::Variable { control, address, asdu },
+			None => Ft12Frame::Fixed { control, address },
+		};
+		self.write_frame(&response).await?;
+		if fcv {
+			self.last_fcb = Some(fcb);
+			self.last_response = Some(response);
+		}
+		if let Some(polled) = polled {
+			// The sender might be gone, nobody needs to know it was sent then
+			let _ = polled.done.send(Ok(()));
+		}
+		Ok(())
+	}
+
+	/// Handles the response of the other end to the pending primary frame.
+	fn handle_secondary(&mut self, acd: bool, function: SecondaryFunction, asdu: Option<Asdu>) {
+		let Some(mut pending) = self.pending.take() else {
+			tracing::warn!("Unexpected response {function:?} without a pending request");
+			return;
+		};
+		let polls = self.polls();
+		let station = &mut self.stations[pending.station];
+		if polls {
+			station.access_demand = acd;
+		}
+
+		let result = match (pending.function, function) {
+			(PrimaryFunction::RequestStatus, SecondaryFunction::Status) => {
+				station.status_received = true;
+				Ok(())
+			}
+			(PrimaryFunction::ResetLink, SecondaryFunction::Ack) => {
+				station.link_up = true;
+				station.next_fcb = true;
+				self.link_up[pending.station].send_replace(true);
+				Ok(())
+			}
+			(
+				PrimaryFunction::RequestClass1 | PrimaryFunction::RequestClass2,
+				SecondaryFunction::UserData | SecondaryFunction::NoData | SecondaryFunction::Ack,
+			) => {
+				if let Some(asdu) = asdu {
+					// The receiver might have been dropped, the ASDU is simply discarded then
+					let _ = self.asdus.send(asdu);
+				}
+				Ok(())
+			}
+			(
+				PrimaryFunction::TestLink | PrimaryFunction::UserDataConfirm,
+				SecondaryFunction::Ack,
+			) => Ok(()),
+			(PrimaryFunction::ResetLink | PrimaryFunction::RequestStatus, response) => {
+				// The link is not up yet, the request is repeated once it times out
+				tracing::warn!("The {:?} was answered with {response:?}", pending.function);
+				self.pending = Some(pending);
+				return;
+			}
+			(function, response) => errors::Rejected { function, response }.fail(),
+		};
+
+		if pending.function.has_valid_fcb() {
+			station.next_fcb = !station.next_fcb;
+		}
+		if let Some(done) = pending.done.take() {
+			// The sender might be gone, nobody is waiting for the result then
+			let _ = done.send(result);
+		} else if let Err(e) = result {
+			tracing::warn!("{e}");
+		}
+	}
+
+	/// Sends a primary frame to the station and waits for its response.
+	async fn send_primary(
+		&mut self,
+		station: usize,
+		function: PrimaryFunction,
+		asdu: Option<Asdu>,
+		done: Option<oneshot::Sender<Result<(), LinkError>>>,
+	) -> Result<(), LinkError> {
+		let control = ControlField::Primary {
+			dir: self.dir(),
+			fcb: function.has_valid_fcb() && self.stations[station].next_fcb,
+			fcv: function.has_valid_fcb(),
+			function,
+		};
+		let address = self.stations[station].address;
+		let frame = match asdu {
+			Some(asdu) => Ft12Frame::Variable { control, address, asdu },
+			None => Ft12Frame::Fixed { control, address },
+		};
+		if let Err(e) = self.write_frame(&frame).await {
+			if let Some(done) = done {
+				let _ = done.send(errors::LinkStopped.fail());
+			}
+			return Err(e);
+		}
+		if matches!(function, PrimaryFunction::RequestClass1 | PrimaryFunction::RequestClass2) {
+			self.stations[station].next_poll = Instant::now() + self.config.poll_interval;
+		}
+		self.pending = Some(Pending {
+			station,
+			function,
+			frame,
+			attempts: 1,
+			deadline: Instant::now() + self.config.response_timeout,
+			done,
+		});
+		Ok(())
+	}
+
+	/// Repeats the pending frame after the response timeout, or considers the
+	/// link to the station down once all the attempts are made. The link is
+	/// brought up again after the poll interval.
+	async fn repeat_pending(&mut self) -> Result<(), LinkError> {
+		let Some(pending) = self.pending.as_mut() else { return Ok(()) };
+		if pending.attempts <= self.config.retries {
+			tracing::debug!("No response to the {:?}, repeating it", pending.function);
+			pending.attempts += 1;
+			pending.deadline = Instant::now() + self.config.response_timeout;
+			let frame = pending.frame.clone();
+			return self.write_frame(&frame).await;
+		}
+
+		let Some(pending) = self.pending.take() else { return Ok(()) };
+		let station = &mut self.stations[pending.station];
+		tracing::warn!(
+			"No response of the station {} to the {:?}, the link is down",
+			station.address,
+			pending.function
+		);
+		station.link_up = false;
+		station.status_received = false;
+		station.access_demand = false;
+		station.next_poll = Instant::now() + self.config.poll_interval;
+		self.link_up[pending.station].send_replace(false);
+		let error = errors::NoResponse { function: pending.function, attempts: pending.attempts };
+		if let Some(done) = pending.done {
+			// The sender might be gone, nobody is waiting for the result then
+			let _ = done.send(error.fail());
+		}
+		Ok(())
+	}
+
+	async fn write_frame(&mut self, frame: &Ft12Frame) -> Result<(), LinkError> {
+		let mut buffer = Vec::new();
+		self.codec.encode(frame, &mut buffer)?;
+		self.writer.write_all(&buffer).await.context(errors::Io)?;
+		self.writer.flush().await.context(errors::Io)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+
+	use tokio::io::DuplexStream;
+
+	use super::*;
+	use crate::{
+		asdu::AsduProfile,
+		test_util::bus,
+		types::{CScNa1, GenericObject, InformationObjects, MSpNa1},
+		types_id::TypeId,
+	};
+
+	/// The other end of a link, exchanging raw frames with the link address 3.
+	struct Peer {
+		io: DuplexStream,
+		codec: Ft12Codec,
+		received: Vec<u8>,
+	}
+
+	impl Peer {
+		fn new(io: DuplexStream, config: &LinkConfig) -> Result<Self, LinkError> {
+			let codec = Ft12Codec::new(config.address_size, config.asdu_profile)?;
+			Ok(Self { io, codec, received: Vec::new() })
+		}
+
+		/// The next frame, or `None` if none is received within the timeout.
+		async fn recv(&mut self, timeout: Duration) -> Result<Option<Ft12Frame>, LinkError> {
+			let deadline = Instant::now() + timeout;
+			loop {
+				if let Some((frame, length)) = self.codec.decode(&self.received)? {
+					self.received.drain(..length);
+					return Ok(Some(frame));
+				}
+				let mut chunk = [0; 256];
+				let Ok(read) = tokio::time::timeout_at(deadline, self.io.read(&mut chunk)).await
+				else {
+					return Ok(None);
+				};
+				let read = read.context(errors::Io)?;
+				self.received.extend_from_slice(&chunk[..read]);
+			}
+		}
+
+		async fn send(
+			&mut self,
+			control: ControlField,
+			asdu: Option<Asdu>,
+		) -> Result<(), LinkError> {
+			let frame = match asdu {
+				Some(asdu) => Ft12Frame::Variable { control, address: 3, asdu },
+				None => Ft12Frame::Fixed { control, address: 3 },
+			};
+			let mut buffer = Vec::new();
+			self.codec.encode(&frame, &mut buffer)?;
+			self.io.write_all(&buffer).await.context(errors::Io)
+		}
+
+		async fn respond(&mut self, function: SecondaryFunction) -> Result<(), LinkError> {
+			let control = ControlField::Secondary { dir: false, acd: false, dfc: false, function };
+			self.send(control, None).await
+		}
+
+		/// Answers the requests of the controlling station until the link is up.
+		async fn bring_up(&mut self) -> Result<(), LinkError> {
+			for (request, response) in [
+				(PrimaryFunction::RequestStatus, SecondaryFunction::Status),
+				(PrimaryFunction::ResetLink, SecondaryFunction::Ack),
+			] {
+				assert_eq!(self.recv_primary().await?.0, request);
+				self.respond(response).await?;
+			}
+			Ok(())
+		}
+
+		/// The function and FCB of the next primary frame.
+		async fn recv_primary(&mut self) -> Result<(PrimaryFunction, bool), LinkError> {
+			match self.recv(Duration::from_secs(5)).await?.and_then(|frame| frame.control()) {
+				Some(ControlField::Primary { function, fcb, .. }) => Ok((function, fcb)),
+				control => panic!("Expected a primary frame, got {control:?}"),
+			}
+		}
+	}
+
+	fn primary(function: PrimaryFunction, fcb: bool) -> ControlField {
+		ControlField::Primary { dir: false, fcb, fcv: function.has_valid_fcb(), function }
+	}
+
+	fn link_config(mode: LinkMode, role: LinkRole) -> LinkConfig {
+		LinkConfig {
+			mode,
+			role,
+			address: 3,
+			polled_addresses: Vec::new(),
+			address_size: 1,
+			asdu_profile: AsduProfile { cot_size: 1, common_address_size: 1, ioa_size: 2 },
+			response_timeout: Duration::from_millis(200),
+			retries: 3,
+			poll_interval: Duration::from_millis(20),
+		}
+	}
+
+	fn asdu(type_id: TypeId, cot: Cot, information_objects: InformationObjects) -> Asdu {
+		Asdu {
+			type_id,
+			cot,
+			originator_address: 0,
+			address_field: 7,
+			sequence: false,
+			test: false,
+			positive: false,
+			information_objects,
+		}
+	}
+
+	#[tokio::test]
+	async fn test_link_exchange() -> Result<(), LinkError> {
+		for mode in [LinkMode::Unbalanced, LinkMode::Balanced] {
+			let (controlling, controlled) = tokio::io::duplex(1024);
+			let mut controlling =
+				Link::spawn(controlling, link_config(mode, LinkRole::Controlling))?;
+			let mut controlled = Link::spawn(controlled, link_config(mode, LinkRole::Controlled))?;
+
+			let command = asdu(
+				TypeId::C_SC_NA_1,
+				Cot::Activation,
+				InformationObjects::CScNa1(vec![GenericObject {
+					address: 100,
+					object: CScNa1::default(),
+				}]),
+			);
+			let spontaneous = asdu(
+				TypeId::M_SP_NA_1,
+				Cot::SpontaneousData,
+				InformationObjects::MSpNa1(vec![GenericObject {
+					address: 200,
+					object: MSpNa1::default(),
+				}]),
+			);
+
+			tokio::time::timeout(Duration::from_secs(5), async {
+				let (sent, received) =
+					tokio::join!(controlling.send_asdu(command.clone()), controlled.recv());
+				sent?;
+				let received = received.context(errors::LinkStopped)?;
+				assert_eq!(received.type_id, command.type_id);
+				assert_eq!(received.address_field, command.address_field);
+
+				// Polled as class 1 data on the unbalanced link
+				let (sent, received) =
+					tokio::join!(controlled.send_asdu(spontaneous.clone()), controlling.recv());
+				sent?;
+				assert_eq!(received, Some(spontaneous.clone()));
+				Ok::<_, LinkError>(())
+			})
+			.await
+			.ok()
+			.context(errors::LinkStopped)??;
+		}
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_controlled_station_repetitions() -> Result<(), LinkError> {
+		let config = link_config(LinkMode::Unbalanced, LinkRole::Controlled);
+		let (controlling, controlled) = tokio::io::duplex(1024);
+		let mut link = Link::spawn(controlled, config.clone())?;
+		let mut peer = Peer::new(controlling, &config)?;
+		let command = asdu(
+			TypeId::C_SC_NA_1,
+			Cot::Activation,
+			InformationObjects::CScNa1(vec![GenericObject {
+				address: 100,
+				object: CScNa1::default(),
+			}]),
+		);
+
+		peer.send(primary(PrimaryFunction::ResetLink, false), None).await?;
+		let ack = peer.recv(Duration::from_secs(5)).await?;
+		for fcb in [true, true, false] {
+			peer.send(primary(PrimaryFunction::UserDataConfirm, fcb), Some(command.clone()))
+				.await?;
+			assert_eq!(peer.recv(Duration::from_secs(5)).await?, ack);
+		}
+		// The repeated frame is acknowledged again but not delivered twice
+		for _ in 0..2 {
+			assert_eq!(link.recv().await, Some(command.clone()));
+		}
+		assert!(tokio::time::timeout(Duration::from_millis(50), link.recv()).await.is_err());
+
+		// A lost response to a poll is repeated, and the data is sent once
+		let spontaneous = asdu(
+			TypeId::M_SP_NA_1,
+			Cot::SpontaneousData,
+			InformationObjects::MSpNa1(vec![GenericObject {
+				address: 200,
+				object: MSpNa1::default(),
+			}]),
+		);
+		let sender = link.sender();
+		let sent = tokio::spawn(async move { sender.send_asdu(spontaneous).await });
+		tokio::time::sleep(Duration::from_millis(50)).await;
+		peer.send(primary(PrimaryFunction::RequestClass1, true), None).await?;
+		let response = peer.recv(Duration::from_secs(5)).await?;
+		assert!(matches!(response, Some(Ft12Frame::Variable { .. })));
+		peer.send(primary(PrimaryFunction::RequestClass1, true), None).await?;
+		assert_eq!(peer.recv(Duration::from_secs(5)).await?, response);
+		peer.send(primary(PrimaryFunction::RequestClass1, false), None).await?;
+		let no_data = peer.recv(Duration::from_secs(5)).await?.and_then(|frame| frame.control());
+		assert!(matches!(
+			no_data,
+			Some(ControlField::Secondary { function: SecondaryFunction::NoData, .. })
+		));
+		sent.await.ok().context(errors::LinkStopped)??;
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_controlling_station_repetitions() -> Result<(), LinkError> {
+		let config = link_config(LinkMode::Unbalanced, LinkRole::Controlling);
+		let (controlling, controlled) = tokio::io::duplex(1024);
+		let _link = Link::spawn(controlling, config.clone())?;
+		let mut peer = Peer::new(controlled, &config)?;
+		peer.bring_up().await?;
+
+		// The unanswered poll is repeated with the same FCB, the next one toggles it
+		let first = peer.recv_primary().await?;
+		assert_eq!(first, (PrimaryFunction::RequestClass2, true));
+		assert_eq!(peer.recv_primary().await?, first);
+		peer.respond(SecondaryFunction::NoData).await?;
+		assert_eq!(peer.recv_primary().await?, (PrimaryFunction::RequestClass2, false));
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_link_down_after_retries() -> Result<(), LinkError> {
+		let config = LinkConfig {
+			response_timeout: Duration::from_millis(50),
+			retries: 2,
+			poll_interval: Duration::from_millis(500),
+			..link_config(LinkMode::Unbalanced, LinkRole::Controlling)
+		};
+		let (controlling, controlled) = tokio::io::duplex(1024);
+		let link = Link::spawn(controlling, config.clone())?;
+		let mut peer = Peer::new(controlled, &config)?;
+		let link_up = link.link_up();
+		assert!(!*link_up.borrow());
+		peer.bring_up().await?;
+		assert_eq!(peer.recv_primary().await?.0, PrimaryFunction::RequestClass2);
+		assert!(*link_up.borrow());
+		peer.respond(SecondaryFunction::NoData).await?;
+
+		let command = asdu(
+			TypeId::C_SC_NA_1,
+			Cot::Activation,
+			InformationObjects::CScNa1(vec![GenericObject {
+				address: 100,
+				object: CScNa1::default(),
+			}]),
+		);
+		let sender = link.sender();
+		let sent = tokio::spawn(async move { sender.send_asdu(command).await });
+		let first = peer.recv_primary().await?;
+		assert_eq!(first.0, PrimaryFunction::UserDataConfirm);
+		for _ in 0..config.retries {
+			assert_eq!(peer.recv_primary().await?, first);
+		}
+		assert!(matches!(
+			sent.await.ok().context(errors::LinkStopped)?,
+			Err(LinkError::NoResponse {
+				function: PrimaryFunction::UserDataConfirm,
+				attempts: 3,
+				..
+			})
+		));
+		assert!(!*link_up.borrow());
+
+		// The link is brought up again after the poll interval
+		let down = Instant::now();
+		assert_eq!(peer.recv_primary().await?.0, PrimaryFunction::RequestStatus);
+		assert!(down.elapsed() >= Duration::from_millis(400));
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_polled_stations() -> Result<(), LinkError> {
+		let config = LinkConfig {
+			polled_addresses: vec![4],
+			..link_config(LinkMode::Unbalanced, LinkRole::Controlling)
+		};
+		let (controlling, controlling_bus) = tokio::io::duplex(1024);
+		let (station_3, station_3_bus) = tokio::io::duplex(1024);
+		let (station_4, station_4_bus) = tokio::io::duplex(1024);
+		tokio::spawn(bus(controlling_bus, station_3_bus, station_4_bus));
+		let mut controlling = Link::spawn(controlling, config.clone())?;
+		let controlled = LinkConfig { role: LinkRole::Controlled, ..config };
+		let mut station_3 = Link::spawn(station_3, controlled.clone())?;
+		let mut station_4 = Link::spawn(station_4, LinkConfig { address: 4, ..controlled })?;
+
+		let spontaneous = |address| {
+			asdu(
+				TypeId::M_SP_NA_1,
+				Cot::SpontaneousData,
+				InformationObjects::MSpNa1(vec![GenericObject {
+					address,
+					object: MSpNa1::default(),
+				}]),
+			)
+		};
+		let command = asdu(
+			TypeId::C_SC_NA_1,
+			Cot::Activation,
+			InformationObjects::CScNa1(vec![GenericObject {
+				address: 100,
+				object: CScNa1::default(),
+			}]),
+		);
+
+		tokio::time::timeout(Duration::from_secs(5), async {
+			// Both stations are polled
+			let (sent_3, sent_4, (first, second)) = tokio::join!(
+				station_3.send_asdu(spontaneous(3)),
+				station_4.send_asdu(spontaneous(4)),
+				async { (controlling.recv().await, controlling.recv().await) },
+			);
+			sent_3?;
+			sent_4?;
+			let mut addresses: Vec<_> = [first, second]
+				.into_iter()
+				.flatten()
+				.flat_map(|asdu| asdu.information_objects.addresses())
+				.collect();
+			addresses.sort_unstable();
+			assert_eq!(addresses, [3, 4]);
+
+			// The user data is sent to the station with the link address only
+			let (sent, received) =
+				tokio::join!(controlling.send_asdu_to(4, command.clone()), station_4.recv());
+			sent?;
+			assert_eq!(received, Some(command.clone()));
+			assert!(controlling.link_up_to(4).is_some_and(|link_up| *link_up.borrow()));
+			assert!(controlling.link_up_to(9).is_none());
+			assert!(
+				tokio::time::timeout(Duration::from_millis(50), station_3.recv()).await.is_err()
+			);
+			assert!(matches!(
+				controlling.send_asdu_to(9, command).await,
+				Err(LinkError::UnknownStation { address: 9, .. })
+			));
+			Ok::<_, LinkError>(())
+		})
+		.await
+		.ok()
+		.context(errors::LinkStopped)??;
+		Ok(())
+	}
+}
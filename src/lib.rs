@@ -2,9 +2,18 @@
 
 pub mod apdu;
 pub mod asdu;
+pub mod capture;
 pub mod client;
 pub mod config;
 pub mod cot;
+pub mod dissect;
 pub mod error;
+pub mod gateway;
+pub mod iec101;
+pub mod point;
+pub mod server;
 pub mod types;
 pub mod types_id;
+
+#[cfg(test)]
+mod test_util;
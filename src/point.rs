@@ -0,0 +1,381 @@
+use time::OffsetDateTime;
+
+use crate::{
+	asdu::Asdu,
+	cot::Cot,
+	types::{
+		FromBytes, GenericObject, InformationObjects, ToBytes,
+		information_elements::{Diq, Dpi, EventState, Oci, Sep, Siq, Spi, StartEp},
+		quality_descriptors::{Qdp, Qds},
+		time::{Cp16Time2a, Cp24Time2a, Cp56Time2a},
+	},
+	types_id::TypeId,
+};
+
+/// The new value of a single point, decoded from a monitoring ASDU.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PointUpdate {
+	pub common_address: u16,
+	pub ioa: u32,
+	pub type_id: TypeId,
+	pub cot: Cot,
+	pub value: PointValue,
+	pub quality: Quality,
+	/// The time tag of the object, if it has one. CP56Time2a time tags are
+	/// interpreted as UTC and CP24Time2a time tags are expanded with a
+	/// reference time.
+	pub timestamp: Option<OffsetDateTime>,
+}
+
+/// The value of a point, whatever the type it was sent with.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PointValue {
+	/// Single point information
+	Single(Spi),
+	/// Double point information
+	Double(Dpi),
+	/// Step position
+	Step { value: u8, transient: bool },
+	/// Bitstring of 32 bits
+	Bitstring(u32),
+	/// Normalized value, in the range [-1, 1)
+	Normalized(f32),
+	/// Scaled value
+	Scaled(i16),
+	/// Short floating point number
+	Float(f32),
+	/// Binary counter reading
+	Counter(u32),
+	/// Packed single point information with status change detection
+	PackedSingle(u32),
+	/// Single event of protection equipment
+	ProtectionEvent { state: EventState, elapsed: Cp16Time2a },
+	/// Packed start events of protection equipment
+	ProtectionStart { events: StartEp, duration: Cp16Time2a },
+	/// Packed output circuit information of protection equipment
+	ProtectionCircuit { circuits: Oci, operating_time: Cp16Time2a },
+}
+
+impl PointValue {
+	/// The normalized value of the raw two's complement fraction of 2^15.
+	fn normalized(nva: u16) -> Self {
+		PointValue::Normalized(f32::from(nva.cast_signed()) / 32768.0)
+	}
+}
+
+/// The flags of the quality descriptors of all the types merged together.
+/// Flags a type doesn't have are always `false`.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub struct Quality {
+	/// Invalid
+	pub iv: bool,
+	/// Not topical
+	pub nt: bool,
+	/// Substituted
+	pub sb: bool,
+	/// Blocked
+	pub bl: bool,
+	/// Overflow
+	pub ov: bool,
+	/// Elapsed time invalid
+	pub ei: bool,
+}
+
+impl Quality {
+	/// Whether none of the flags is set.
+	#[must_use]
+	pub const fn is_good(&self) -> bool {
+		!(self.iv || self.nt || self.sb || self.bl || self.ov || self.ei)
+	}
+}
+
+impl From<&Qds> for Quality {
+	fn from(qds: &Qds) -> Self {
+		Self { iv: qds.iv, nt: qds.nt, sb: qds.sb, bl: qds.bl, ov: qds.ov, ei: false }
+	}
+}
+
+impl From<&Qdp> for Quality {
+	fn from(qdp: &Qdp) -> Self {
+		Self { iv: qdp.iv, nt: qdp.nt, sb: qdp.sb, bl: qdp.bl, ov: false, ei: qdp.ei }
+	}
+}
+
+impl From<&Siq> for Quality {
+	fn from(siq: &Siq) -> Self {
+		Self { iv: siq.iv, nt: siq.nt, sb: siq.sb, bl: siq.bl, ov: false, ei: false }
+	}
+}
+
+impl From<&Diq> for Quality {
+	fn from(diq: &Diq) -> Self {
+		Self { iv: diq.iv, nt: diq.nt, sb: diq.sb, bl: diq.bl, ov: false, ei: false }
+	}
+}
+
+impl From<&Sep> for Quality {
+	fn from(sep: &Sep) -> Self {
+		Self { iv: sep.iv, nt: sep.nt, sb: sep.sb, bl: sep.bl, ov: false, ei: sep.ei }
+	}
+}
+
+/// The time tag of an object.
+enum Tag<'a> {
+	None,
+	Cp24(Cp24Time2a),
+	Cp56(&'a Cp56Time2a),
+}
+
+impl PointUpdate {
+	/// Decodes every object of a monitoring ASDU into a point update. The
+	/// CP24Time2a time tags are expanded with `reference`, usually the
+	/// reception time.
+	///
+	/// Returns an empty vector for the other ASDUs (commands, parameters, file
+	/// transfer, ...).
+	#[must_use]
+	#[allow(clippy::too_many_lines)]
+	pub fn from_asdu(asdu: &Asdu, reference: OffsetDateTime) -> Vec<Self> {
+		let decode = Decoder { asdu, reference };
+		match &asdu.information_objects {
+			InformationObjects::MSpNa1(objs) => decode
+				.objects(objs, |o| (PointValue::Single(o.siq.spi), (&o.siq).into(), Tag::None)),
+			InformationObjects::MSpTa1(objs) => decode.objects(objs, |o| {
+				(PointValue::Single(o.siq.spi), (&o.siq).into(), Tag::Cp24(o.time))
+			}),
+			InformationObjects::MSpTb1(objs) => decode.objects(objs, |o| {
+				(PointValue::Single(o.siq.spi), (&o.siq).into(), Tag::Cp56(&o.time))
+			}),
+			InformationObjects::MDpNa1(objs) => decode
+				.objects(objs, |o| (PointValue::Double(o.diq.dpi), (&o.diq).into(), Tag::None)),
+			InformationObjects::MDpTa1(objs) => decode.objects(objs, |o| {
+				(PointValue::Double(o.diq.dpi), (&o.diq).into(), Tag::Cp24(o.time))
+			}),
+			InformationObjects::MDpTb1(objs) => decode.objects(objs, |o| {
+				(PointValue::Double(o.diq.dpi), (&o.diq).into(), Tag::Cp56(&o.time))
+			}),
+			InformationObjects::MStNa1(objs) => decode.objects(objs, |o| {
+				let value = PointValue::Step { value: o.vti.value, transient: o.vti.transient };
+				(value, (&o.vti.qds).into(), Tag::None)
+			}),
+			InformationObjects::MStTa1(objs) => decode.objects(objs, |o| {
+				let value = PointValue::Step { value: o.vti.value, transient: o.vti.transient };
+				(value, (&o.vti.qds).into(), Tag::Cp24(o.time))
+			}),
+			InformationObjects::MStTb1(objs) => decode.objects(objs, |o| {
+				let value = PointValue::Step { value: o.vti.value, transient: o.vti.transient };
+				(value, (&o.vti.qds).into(), Tag::Cp56(&o.time))
+			}),
+			InformationObjects::MBoNa1(objs) => {
+				decode.objects(objs, |o| (PointValue::Bitstring(o.bsi), (&o.qds).into(), Tag::None))
+			}
+			InformationObjects::MBoTb1(objs) => decode.objects(objs, |o| {
+				(PointValue::Bitstring(o.bsi), (&o.qds).into(), Tag::Cp56(&o.time))
+			}),
+			InformationObjects::MMeNa1(objs) => decode
+				.objects(objs, |o| (PointValue::normalized(o.nva), (&o.qds).into(), Tag::None)),
+			InformationObjects::MMeTa1(objs) => decode.objects(objs, |o| {
+				(PointValue::normalized(o.nva), (&o.qds).into(), Tag::Cp24(o.time))
+			}),
+			InformationObjects::MMeTd1(objs) => decode.objects(objs, |o| {
+				(PointValue::normalized(o.nva), (&o.qds).into(), Tag::Cp56(&o.time))
+			}),
+			InformationObjects::MMeNd1(objs) => decode
+				.objects(objs, |o| (PointValue::normalized(o.nva), Quality::default(), Tag::None)),
+			InformationObjects::MMeNb1(objs) => decode.objects(objs, |o| {
+				(PointValue::Scaled(o.sva.cast_signed()), (&o.qds).into(), Tag::None)
+			}),
+			InformationObjects::MMeTb1(objs) => decode.objects(objs, |o| {
+				(PointValue::Scaled(o.sva.cast_signed()), (&o.qds).into(), Tag::Cp24(o.time))
+			}),
+			InformationObjects::MMeTe1(objs) => decode.objects(objs, |o| {
+				(PointValue::Scaled(o.sva.cast_signed()), (&o.qds).into(), Tag::Cp56(&o.time))
+			}),
+			InformationObjects::MMeNc1(objs) => {
+				decode.objects(objs, |o| (PointValue::Float(o.value), (&o.qds).into(), Tag::None))
+			}
+			InformationObjects::MMeTc1(objs) => decode.objects(objs, |o| {
+				(PointValue::Float(o.value), (&o.qds).into(), Tag::Cp24(o.time))
+			}),
+			InformationObjects::MMeTf1(objs) => decode.objects(objs, |o| {
+				(PointValue::Float(o.value), (&o.qds).into(), Tag::Cp56(&o.time))
+			}),
+			InformationObjects::MItNa1(objs) => {
+				decode.objects(objs, |o| (PointValue::Counter(o.bcr), (&o.qds).into(), Tag::None))
+			}
+			InformationObjects::MItTb1(objs) => decode.objects(objs, |o| {
+				(PointValue::Counter(o.bcr), (&o.qds).into(), Tag::Cp56(&o.time))
+			}),
+			InformationObjects::MPsNa1(objs) => decode
+				.objects(objs, |o| (PointValue::PackedSingle(o.bsi), (&o.qds).into(), Tag::None)),
+			InformationObjects::MEpTa1(objs) => decode.objects(objs, |o| {
+				let value = PointValue::ProtectionEvent { state: o.sep.es, elapsed: o.elapsed };
+				(value, (&o.sep).into(), Tag::Cp24(o.time))
+			}),
+			InformationObjects::MEpTd1(objs) => decode.objects(objs, |o| {
+				let value = PointValue::ProtectionEvent { state: o.sep.es, elapsed: o.elapsed };
+				(value, (&o.sep).into(), Tag::Cp56(&o.time))
+			}),
+			InformationObjects::MEpTb1(objs) => decode.objects(objs, |o| {
+				let value = PointValue::ProtectionStart {
+					events: o.start_ep.clone(),
+					duration: o.relay_duration,
+				};
+				(value, (&o.qdp).into(), Tag::Cp24(o.time))
+			}),
+			InformationObjects::MEpTe1(objs) => decode.objects(objs, |o| {
+				let value = PointValue::ProtectionStart {
+					events: o.start_ep.clone(),
+					duration: o.relay_duration,
+				};
+				(value, (&o.qdp).into(), Tag::Cp56(&o.time))
+			}),
+			InformationObjects::MEpTc1(objs) => decode.objects(objs, |o| {
+				let value = PointValue::ProtectionCircuit {
+					circuits: o.oci.clone(),
+					operating_time: o.relay_op_time,
+				};
+				(value, (&o.qdp).into(), Tag::Cp24(o.time))
+			}),
+			InformationObjects::MEpTf1(objs) => decode.objects(objs, |o| {
+				let value = PointValue::ProtectionCircuit {
+					circuits: o.oci.clone(),
+					operating_time: o.relay_op_time,
+				};
+				(value, (&o.qdp).into(), Tag::Cp56(&o.time))
+			}),
+			_ => Vec::new(),
+		}
+	}
+}
+
+struct Decoder<'a> {
+	asdu: &'a Asdu,
+	reference: OffsetDateTime,
+}
+
+impl Decoder<'_> {
+	fn objects<'o, T>(
+		&self,
+		objects: &'o [GenericObject<T>],
+		decode: impl Fn(&'o T) -> (PointValue, Quality, Tag<'o>),
+	) -> Vec<PointUpdate>
+	where
+		T: FromBytes + ToBytes + Default,
+	{
+		objects
+			.iter()
+			.map(|obj| {
+				let (value, quality, tag) = decode(&obj.object);
+				let timestamp = match tag {
+					Tag::None => None,
+					Tag::Cp24(time) => time.expand(self.reference).ok(),
+					Tag::Cp56(time) => OffsetDateTime::try_from(time.clone()).ok(),
+				};
+				PointUpdate {
+					common_address: self.asdu.address_field,
+					ioa: obj.address,
+					type_id: self.asdu.type_id,
+					cot: self.asdu.cot,
+					value,
+					quality,
+					timestamp,
+				}
+			})
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::types::{MMeNa1, MMeNb1, MMeTf1, MSpNa1};
+
+	#[test]
+	fn test_point_updates() {
+		let asdu = Asdu {
+			type_id: TypeId::M_SP_NA_1,
+			information_objects: InformationObjects::MSpNa1(vec![
+				GenericObject {
+					address: 1,
+					object: MSpNa1 { siq: Siq { spi: Spi::On, ..Default::default() } },
+				},
+				GenericObject {
+					address: 2,
+					object: MSpNa1 { siq: Siq { iv: true, ..Default::default() } },
+				},
+			]),
+			originator_address: 0,
+			address_field: 47,
+			sequence: false,
+			test: false,
+			cot: Cot::SpontaneousData,
+			positive: false,
+		};
+		let updates = PointUpdate::from_asdu(&asdu, OffsetDateTime::now_utc());
+		assert_eq!(updates.len(), 2);
+		assert_eq!((updates[0].common_address, updates[0].ioa), (47, 1));
+		assert_eq!(updates[0].value, PointValue::Single(Spi::On));
+		assert!(updates[0].quality.is_good());
+		assert_eq!(updates[0].timestamp, None);
+		assert_eq!(updates[1].value, PointValue::Single(Spi::Off));
+		assert!(updates[1].quality.iv);
+
+		// 2023-11-14 22:13:20 UTC
+		let time = OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+		let asdu = Asdu {
+			type_id: TypeId::M_ME_TF_1,
+			information_objects: InformationObjects::MMeTf1(vec![GenericObject {
+				address: 3,
+				object: MMeTf1 {
+					value: 1.5,
+					qds: Qds { ov: true, ..Default::default() },
+					time: Cp56Time2a::try_from(time).unwrap(),
+				},
+			}]),
+			..asdu
+		};
+		let updates = PointUpdate::from_asdu(&asdu, OffsetDateTime::now_utc());
+		assert_eq!(updates[0].value, PointValue::Float(1.5));
+		assert!(updates[0].quality.ov);
+		assert_eq!(updates[0].timestamp, Some(time));
+
+		let asdu = Asdu {
+			type_id: TypeId::M_ME_NA_1,
+			information_objects: InformationObjects::MMeNa1(
+				[0x8000, 0xC000, 0x4000]
+					.into_iter()
+					.map(|nva| GenericObject {
+						address: 4,
+						object: MMeNa1 { nva, ..Default::default() },
+					})
+					.collect(),
+			),
+			..asdu
+		};
+		let values: Vec<_> = PointUpdate::from_asdu(&asdu, OffsetDateTime::now_utc())
+			.into_iter()
+			.map(|update| update.value)
+			.collect();
+		assert_eq!(
+			values,
+			[
+				PointValue::Normalized(-1.0),
+				PointValue::Normalized(-0.5),
+				PointValue::Normalized(0.5)
+			]
+		);
+
+		let asdu = Asdu {
+			type_id: TypeId::M_ME_NB_1,
+			information_objects: InformationObjects::MMeNb1(vec![GenericObject {
+				address: 5,
+				object: MMeNb1 { sva: (-100_i16).cast_unsigned(), ..Default::default() },
+			}]),
+			..asdu
+		};
+		let updates = PointUpdate::from_asdu(&asdu, OffsetDateTime::now_utc());
+		assert_eq!(updates[0].value, PointValue::Scaled(-100));
+	}
+}
@@ -0,0 +1,393 @@
+use std::{fmt::Debug, net::SocketAddr, sync::Arc};
+
+use async_trait::async_trait;
+use snafu::{ResultExt as _, whatever};
+use tokio::{
+	net::TcpListener,
+	select,
+	sync::broadcast,
+	task::{JoinHandle, JoinSet},
+};
+use tokio_native_tls::{TlsAcceptor, native_tls::Identity};
+use tracing::instrument;
+
+use crate::{
+	asdu::Asdu,
+	capture::tap::Tap,
+	client::Connection,
+	config::{ProtocolConfig, ServerConfig, TlsServerConfig},
+	cot::Cot,
+	error::Error,
+	server::{connection_handler::ConnectionHandler, database::PointDatabase, errors::ServerError},
+	types::InformationObjects,
+};
+
+mod connection_handler;
+pub mod database;
+pub mod errors;
+
+/// Handler for the ASDUs sent by the controlling stations.
+#[async_trait]
+pub trait OnNewCommand {
+	/// Called for every ASDU received from a controlling station. The returned
+	/// ASDUs (e.g. the activation confirmation and termination) are sent back
+	/// on the same connection, in order.
+	async fn on_new_command(&self, asdu: Asdu) -> Vec<Asdu>;
+}
+
+/// IEC 104 controlled station.
+///
+/// Listens for connections from controlling stations, handles the
+/// StartDT/StopDT/TestFR procedures and forwards the received ASDUs to the
+/// [`OnNewCommand`] callback. Monitor ASDUs pushed with [`Server::send_asdu`]
+/// are sent to every connection with started data transfer.
+///
+/// The general and group interrogations are answered by the server itself
+/// from its [`PointDatabase`] and never reach the callback, unless
+/// [`ServerConfig::answer_interrogations`] is unset.
+pub struct Server {
+	config: ServerConfig,
+	callback: Arc<dyn OnNewCommand + Send + Sync>,
+	database: Arc<PointDatabase>,
+	listen_task: Option<JoinHandle<Result<(), Error>>>,
+	local_addr: Option<SocketAddr>,
+	monitor_tx: broadcast::Sender<Asdu>,
+	/// The capture of the connections, opened on the first start so that it's
+	/// not replaced when restarting.
+	tap: Option<Tap>,
+}
+
+impl Server {
+	#[must_use]
+	pub fn new(config: ServerConfig, callback: impl OnNewCommand + Send + Sync + 'static) -> Self {
+		let (monitor_tx, _) = broadcast::channel(1024);
+		Self {
+			config,
+			callback: Arc::new(callback),
+			database: Arc::new(PointDatabase::new()),
+			listen_task: None,
+			local_addr: None,
+			monitor_tx,
+			tap: None,
+		}
+	}
+
+	#[instrument(level = "debug")]
+	pub async fn start(&mut self) -> Result<(), Error> {
+		if self.listen_task.is_some() {
+			whatever!("Listen task already running");
+		}
+
+		let acceptor = self.config.tls.as_ref().map(Self::make_tls_acceptor).transpose()?;
+		if self.tap.is_none()
+			&& let Some(path) = &self.config.protocol.capture
+		{
+			self.tap = Some(Tap::open(path).whatever_context("Error creating the capture")?);
+		}
+		let listener = TcpListener::bind(format!("{}:{}", self.config.address, self.config.port))
+			.await
+			.whatever_context("Error binding listener")?;
+		self.local_addr =
+			Some(listener.local_addr().whatever_context("Error getting local address")?);
+
+		let callback = self.callback.clone();
+		let database = self.config.answer_interrogations.then(|| self.database.clone());
+		let config = self.config.protocol.clone();
+		let monitor_tx = self.monitor_tx.clone();
+		let tap = self.tap.clone();
+
+		self.listen_task = Some(tokio::spawn(async move {
+			Self::listen(listener, acceptor, callback, database, config, monitor_tx, tap)
+				.await
+				.inspect_err(|e| tracing::error!("Error in running listener: {e}"))
+		}));
+
+		Ok(())
+	}
+
+	/// Stops listening and closes all the connections.
+	#[instrument(level = "debug")]
+	pub fn stop(&mut self) {
+		if let Some(listen_task) = self.listen_task.take() {
+			listen_task.abort();
+		}
+		self.local_addr = None;
+	}
+
+	/// The address the server is listening on, if started.
+	#[must_use]
+	pub const fn local_addr(&self) -> Option<SocketAddr> {
+		self.local_addr
+	}
+
+	/// Sends a monitor ASDU to every connection with started data transfer.
+	#[instrument(level = "debug")]
+	pub fn send_asdu(&self, asdu: Asdu) -> Result<(), ServerError> {
+		if self.listen_task.is_none() {
+			return errors::NotStarted.fail();
+		}
+
+		if self.monitor_tx.send(asdu).is_err() {
+			return errors::NoConnection.fail();
+		}
+		Ok(())
+	}
+
+	/// The channel the monitor ASDUs are sent to the connections through.
+	pub(crate) fn monitor_sender(&self) -> broadcast::Sender<Asdu> {
+		self.monitor_tx.clone()
+	}
+
+	/// The process image used to answer the interrogations.
+	#[must_use]
+	pub fn database(&self) -> Arc<PointDatabase> {
+		self.database.clone()
+	}
+
+	/// Stores the new values of the points in the database. If `spontaneous`
+	/// is set, they are also sent to every connection with started data
+	/// transfer with [`Cot::SpontaneousData`]. The values are stored even if
+	/// sending them fails.
+	#[instrument(level = "debug")]
+	pub fn update_points(
+		&self,
+		common_address: u16,
+		information_objects: InformationObjects,
+		spontaneous: bool,
+	) -> Result<(), ServerError> {
+		self.database.update(common_address, &information_objects);
+		if !spontaneous {
+			return Ok(());
+		}
+
+		let Some(type_id) = information_objects.type_id() else {
+			return Ok(());
+		};
+		let chunks = information_objects.chunks(Asdu::max_objects(type_id));
+		for information_objects in chunks {
+			let asdu = Asdu {
+				type_id,
+				cot: Cot::SpontaneousData,
+				originator_address: 0,
+				address_field: common_address,
+				sequence: false,
+				test: false,
+				positive: false,
+				information_objects,
+			};
+			match self.send_asdu(asdu) {
+				// Nobody to notify. The value is still in the database for the next interrogation
+				Err(ServerError::NoConnection { .. }) => return Ok(()),
+				result => result?,
+			}
+		}
+		Ok(())
+	}
+
+	#[instrument(level = "debug", skip_all)]
+	async fn listen(
+		listener: TcpListener,
+		acceptor: Option<TlsAcceptor>,
+		callback: Arc<dyn OnNewCommand + Send + Sync>,
+		database: Option<Arc<PointDatabase>>,
+		config: ProtocolConfig,
+		monitor_tx: broadcast::Sender<Asdu>,
+		tap: Option<Tap>,
+	) -> Result<(), Error> {
+		let mut connections = JoinSet::new();
+
+		loop {
+			select! {
+				accepted = listener.accept() => {
+					let (stream, peer) = match accepted {
+						Ok(accepted) => accepted,
+						Err(e) => {
+							tracing::error!("Error accepting connection: {e}");
+							continue;
+						}
+					};
+					tracing::debug!("New connection from {peer}");
+					let connection_tap = tap.as_ref().and_then(|tap| {
+						stream
+							.local_addr()
+							.inspect_err(|e| tracing::warn!("Error getting the address of the connection to capture: {e}"))
+							.ok()
+							.map(|local| tap.connection(local, peer))
+					});
+
+					let acceptor = acceptor.clone();
+					let callback = callback.clone();
+					let database = database.clone();
+					let config = config.clone();
+					let monitor_rx = monitor_tx.subscribe();
+					connections.spawn(async move {
+						let connection = match acceptor {
+							Some(acceptor) => Connection::Tls(
+								acceptor
+									.accept(stream)
+									.await
+									.whatever_context("Error accepting TLS connection")?,
+							),
+							None => Connection::Tcp(stream),
+						};
+						ConnectionHandler::new(connection, callback, database, config, connection_tap)
+							.run(monitor_rx)
+							.await
+							.inspect_err(|e| tracing::debug!("Connection from {peer} closed: {e}"))
+					});
+				}
+				Some(result) = connections.join_next() => {
+					if let Err(e) = result {
+						tracing::error!("Error joining connection task: {e}");
+					}
+				}
+			}
+		}
+	}
+
+	#[instrument(level = "debug")]
+	fn make_tls_acceptor(tls: &TlsServerConfig) -> Result<TlsAcceptor, Error> {
+		let identity = Identity::from_pkcs8(
+			std::fs::read(&tls.server_certificate)
+				.whatever_context("Failed to read server certificate")?
+				.as_slice(),
+			std::fs::read(&tls.server_key)
+				.whatever_context("Failed to read server key")?
+				.as_slice(),
+		)
+		.whatever_context("Could not create server identity")?;
+
+		let acceptor = tokio_native_tls::native_tls::TlsAcceptor::new(identity)
+			.whatever_context("Error building TLS acceptor")?;
+		Ok(TlsAcceptor::from(acceptor))
+	}
+}
+
+impl Drop for Server {
+	fn drop(&mut self) {
+		self.stop();
+	}
+}
+
+impl Debug for Server {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "Server {{local_addr: {:?} }}", self.local_addr)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use tokio::sync::mpsc;
+
+	use super::*;
+	use crate::{
+		cot::Cot,
+		test_util::{Commands, start},
+		types::{
+			CIcNa1, GenericObject, InformationObjects, MMeNc1, MSpNa1,
+			commands::Qoi,
+			information_elements::{Siq, Spi},
+		},
+		types_id::TypeId,
+	};
+
+	#[tokio::test]
+	async fn test_client_server_exchange() {
+		let (command_tx, mut command_rx) = mpsc::unbounded_channel();
+		let (server, client, mut object_rx) = start(Commands(command_tx)).await;
+
+		client.send_command_sp(1, 100, Spi::On, None, None, None).await.unwrap();
+
+		let command = command_rx.recv().await.unwrap();
+		assert_eq!(command.type_id, TypeId::C_SC_NA_1);
+		assert_eq!(command.address_field, 1);
+		let InformationObjects::CScNa1(objects) = &command.information_objects else {
+			panic!("Information objects were expected to be a CScNa1")
+		};
+		assert_eq!(objects[0].address, 100);
+		assert_eq!(objects[0].object.sco.scs, Spi::On);
+
+		let confirmation = object_rx.recv().await.unwrap();
+		assert_eq!(confirmation.type_id, TypeId::C_SC_NA_1);
+		assert_eq!(confirmation.cot, Cot::ActivationConfirmation);
+
+		let spontaneous = Asdu {
+			type_id: TypeId::M_SP_NA_1,
+			cot: Cot::SpontaneousData,
+			originator_address: 0,
+			address_field: 1,
+			sequence: false,
+			test: false,
+			positive: false,
+			information_objects: InformationObjects::MSpNa1(vec![GenericObject {
+				address: 200,
+				object: MSpNa1 { siq: Siq { spi: Spi::On, ..Default::default() } },
+			}]),
+		};
+		server.send_asdu(spontaneous.clone()).unwrap();
+		assert_eq!(object_rx.recv().await.unwrap(), spontaneous);
+	}
+
+	#[tokio::test]
+	async fn test_general_interrogation() {
+		let (command_tx, mut command_rx) = mpsc::unbounded_channel();
+		let (server, client, mut object_rx) = start(Commands(command_tx)).await;
+		server
+			.update_points(
+				1,
+				InformationObjects::MMeNc1(
+					(1..=200)
+						.map(|address| GenericObject { address, object: MMeNc1::default() })
+						.collect(),
+				),
+				false,
+			)
+			.unwrap();
+
+		let interrogation = Asdu {
+			type_id: TypeId::C_IC_NA_1,
+			cot: Cot::Activation,
+			originator_address: 0,
+			address_field: 1,
+			sequence: false,
+			test: false,
+			positive: false,
+			information_objects: InformationObjects::CIcNa1(vec![GenericObject {
+				address: 0,
+				object: CIcNa1 { qoi: Qoi::Global },
+			}]),
+		};
+		client.send_asdu(interrogation.clone()).await.unwrap();
+
+		let confirmation = object_rx.recv().await.unwrap();
+		assert_eq!(confirmation.type_id, TypeId::C_IC_NA_1);
+		assert_eq!(confirmation.cot, Cot::ActivationConfirmation);
+		assert!(!confirmation.positive);
+
+		let mut addresses = Vec::new();
+		let termination = loop {
+			let asdu = object_rx.recv().await.unwrap();
+			if asdu.type_id != TypeId::M_ME_NC_1 {
+				break asdu;
+			}
+			assert_eq!(asdu.cot, Cot::InterrogationGeneral);
+			assert_eq!(
+				asdu.information_objects.len(),
+				Asdu::max_objects(TypeId::M_ME_NC_1).min(200 - addresses.len(),)
+			);
+			addresses.extend(asdu.information_objects.addresses());
+		};
+		assert_eq!(addresses, (1..=200).collect::<Vec<_>>());
+		assert_eq!(termination.type_id, TypeId::C_IC_NA_1);
+		assert_eq!(termination.cot, Cot::ActivationTermination);
+
+		// Unknown station
+		client.send_asdu(Asdu { address_field: 2, ..interrogation }).await.unwrap();
+		let confirmation = object_rx.recv().await.unwrap();
+		assert_eq!(confirmation.cot, Cot::UnknownAsduAddress);
+		assert!(confirmation.positive);
+
+		// The interrogations never reach the callback
+		assert!(command_rx.try_recv().is_err());
+	}
+}
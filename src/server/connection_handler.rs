@@ -0,0 +1,362 @@
+use std::sync::{
+	Arc,
+	atomic::{AtomicBool, Ordering},
+};
+
+use async_trait::async_trait;
+use snafu::ResultExt as _;
+use tokio::{
+	io::{ReadHalf, WriteHalf},
+	sync::{broadcast, mpsc},
+};
+use tracing::instrument;
+
+use crate::{
+	apdu::Frame,
+	asdu::{Asdu, BROADCAST_ADDRESS},
+	capture::tap::ConnectionTap,
+	client::{
+		Connection, OnNewObjects, START_DT_CON_FRAME, STOP_DT_CON_FRAME, TEST_FR_CON_FRAME,
+		receive_handler::ReceiveHandler,
+		send_queue::{Priority, SendQueue, SendQueueReceiver, send_queue},
+	},
+	config::ProtocolConfig,
+	cot::Cot,
+	error::Error,
+	server::{OnNewCommand, database::PointDatabase},
+	types::{InformationObjects, commands::Qoi},
+	types_id::TypeId,
+};
+
+/// Handles a single connection from a controlling station.
+///
+/// The connection starts in the stopped state, where only test frames are
+/// answered. Once a StartDT activation is received, the same k/w/t1/t2/t3
+/// sequencing of the client is used until the controlling station sends a
+/// StopDT activation.
+pub struct ConnectionHandler {
+	read_connection: ReadHalf<Connection>,
+	write_connection: WriteHalf<Connection>,
+	callback: Arc<dyn OnNewObjects + Send + Sync>,
+	config: ProtocolConfig,
+	queue: SendQueue,
+	queue_rx: SendQueueReceiver,
+	started: Arc<AtomicBool>,
+	response_rx: Option<mpsc::UnboundedReceiver<Asdu>>,
+	/// Where the APDUs of the connection are captured, when configured.
+	tap: Option<ConnectionTap>,
+}
+
+impl ConnectionHandler {
+	pub fn new(
+		connection: Connection,
+		callback: Arc<dyn OnNewCommand + Send + Sync>,
+		database: Option<Arc<PointDatabase>>,
+		config: ProtocolConfig,
+		tap: Option<ConnectionTap>,
+	) -> Self {
+		let (read_connection, write_connection) = tokio::io::split(connection);
+		let (queue, queue_rx) = send_queue(1024);
+		let (response_tx, response_rx) = mpsc::unbounded_channel();
+		Self {
+			read_connection,
+			write_connection,
+			callback: Arc::new(CommandDispatcher { callback, database, response_tx }),
+			config,
+			queue,
+			queue_rx,
+			started: Arc::new(AtomicBool::new(false)),
+			response_rx: Some(response_rx),
+			tap,
+		}
+	}
+
+	#[instrument(level = "debug", skip_all)]
+	pub async fn run(mut self, monitor_rx: broadcast::Receiver<Asdu>) -> Result<(), Error> {
+		let monitor_task = tokio::spawn(Self::forward_monitor_asdus(
+			monitor_rx,
+			self.queue.clone(),
+			self.started.clone(),
+		));
+		let response_task = self.response_rx.take().map(|response_rx| {
+			tokio::spawn(Self::forward_responses(response_rx, self.queue.clone()))
+		});
+		let result = self.handle_connection().await;
+		monitor_task.abort();
+		if let Some(response_task) = response_task {
+			response_task.abort();
+		}
+		result
+	}
+
+	#[instrument(level = "debug", skip_all)]
+	async fn handle_connection(&mut self) -> Result<(), Error> {
+		loop {
+			self.wait_for_start_dt().await?;
+			tracing::debug!("StartDT activation confirmed");
+			self.started.store(true, Ordering::Relaxed);
+
+			let result = ReceiveHandler::new(
+				&mut self.read_connection,
+				&mut self.write_connection,
+				self.callback.clone(),
+				self.config.clone(),
+				// The server sends no StopDT or test commands of its own
+				None,
+				&mut self.queue_rx,
+				self.tap.as_ref(),
+			)
+			.receive_task()
+			.await;
+
+			self.started.store(false, Ordering::Relaxed);
+			result?;
+			tracing::debug!("Received a stop. Going back to waiting for start");
+		}
+	}
+
+	#[instrument(level = "debug", skip_all)]
+	async fn wait_for_start_dt(&mut self) -> Result<(), Error> {
+		let mut buffer = [0; 255];
+		loop {
+			let apdu = ReceiveHandler::receive_apdu(
+				&mut self.read_connection,
+				&mut buffer,
+				self.config.asdu_profile,
+				self.tap.as_ref(),
+			)
+			.await
+			.whatever_context("Error receiving APDU")?;
+			match apdu.frame {
+				Frame::U(u) if u.start_dt_activation => {
+					ReceiveHandler::send_frame(
+						&mut self.write_connection,
+						&START_DT_CON_FRAME,
+						self.tap.as_ref(),
+					)
+					.await
+					.whatever_context("Error sending startDT confirmation")?;
+					return Ok(());
+				}
+				Frame::U(u) if u.test_fr_activation => {
+					ReceiveHandler::send_frame(
+						&mut self.write_connection,
+						&TEST_FR_CON_FRAME,
+						self.tap.as_ref(),
+					)
+					.await
+					.whatever_context("Error sending test frame confirmation")?;
+				}
+				Frame::U(u) if u.stop_dt_activation => {
+					ReceiveHandler::send_frame(
+						&mut self.write_connection,
+						&STOP_DT_CON_FRAME,
+						self.tap.as_ref(),
+					)
+					.await
+					.whatever_context("Error sending stopDT confirmation")?;
+				}
+				frame => {
+					tracing::warn!("Ignoring frame received before StartDT: {frame:?}");
+				}
+			}
+		}
+	}
+
+	/// Forwards the ASDUs pushed to the server into this connection while data
+	/// transfer is started. ASDUs pushed while stopped are dropped.
+	#[instrument(level = "debug", skip_all)]
+	async fn forward_monitor_asdus(
+		mut monitor_rx: broadcast::Receiver<Asdu>,
+		queue: SendQueue,
+		started: Arc<AtomicBool>,
+	) {
+		loop {
+			match monitor_rx.recv().await {
+				Ok(asdu) => {
+					if !started.load(Ordering::Relaxed) {
+						tracing::debug!("Data transfer is stopped. Dropping ASDU: {asdu:?}");
+						continue;
+					}
+					if queue.send(asdu, Priority::Normal, None).await.is_err() {
+						return;
+					}
+				}
+				Err(broadcast::error::RecvError::Lagged(skipped)) => {
+					tracing::warn!("Connection is lagging behind. Dropped {skipped} ASDUs");
+				}
+				Err(broadcast::error::RecvError::Closed) => return,
+			}
+		}
+	}
+
+	/// Forwards the responses to the commands into this connection. The
+	/// receive handler is the one draining the send queue, so the
+	/// responses are queued here instead of waiting for room in the receive
+	/// handler itself.
+	#[instrument(level = "debug", skip_all)]
+	async fn forward_responses(mut response_rx: mpsc::UnboundedReceiver<Asdu>, queue: SendQueue) {
+		while let Some(asdu) = response_rx.recv().await {
+			if queue.send(asdu, Priority::High, None).await.is_err() {
+				return;
+			}
+		}
+	}
+}
+
+/// Adapts the server command callback to the receive handler. The
+/// interrogation commands are answered from the point database, if set,
+/// everything else goes to the callback. The responses are queued to be sent on
+/// the same connection.
+struct CommandDispatcher {
+	callback: Arc<dyn OnNewCommand + Send + Sync>,
+	/// The database the interrogations are answered from, if the server
+	/// answers them.
+	database: Option<Arc<PointDatabase>>,
+	response_tx: mpsc::UnboundedSender<Asdu>,
+}
+
+impl CommandDispatcher {
+	/// Builds the responses to a general or group interrogation: the activation
+	/// confirmation, the points of the group and the activation termination for
+	/// every addressed station.
+	#[instrument(level = "debug", skip(database))]
+	fn interrogation(database: &PointDatabase, asdu: &Asdu, qoi: Qoi) -> Vec<Asdu> {
+		let negative = |cot| vec![Asdu { cot, positive: true, ..asdu.clone() }];
+
+		if asdu.cot != Cot::Activation {
+			return negative(Cot::UnknownCause);
+		}
+		if matches!(qoi, Qoi::Unused | Qoi::Other(_)) {
+			return negative(Cot::ActivationConfirmation);
+		}
+		// The qualifiers of the general and group interrogations have the same value
+		// as the cause of transmission of their responses
+		let Ok(response_cot) = Cot::try_from(qoi.to_byte()) else {
+			return negative(Cot::ActivationConfirmation);
+		};
+
+		let common_addresses = if asdu.address_field == BROADCAST_ADDRESS {
+			database.common_addresses()
+		} else {
+			vec![asdu.address_field]
+		};
+		if common_addresses.is_empty() {
+			// A broadcast to a station without points. There is nothing to report, but the
+			// controlling station still waits for the interrogation to end
+			return vec![
+				Asdu { cot: Cot::ActivationConfirmation, ..asdu.clone() },
+				Asdu { cot: Cot::ActivationTermination, ..asdu.clone() },
+			];
+		}
+
+		let mut responses = Vec::new();
+		for common_address in common_addresses {
+			let Some(points) = database.interrogate(common_address, qoi) else {
+				return negative(Cot::UnknownAsduAddress);
+			};
+			let request = Asdu { address_field: common_address, ..asdu.clone() };
+
+			responses.push(Asdu { cot: Cot::ActivationConfirmation, ..request.clone() });
+			responses.extend(points.into_iter().filter_map(|information_objects| {
+				Some(Asdu {
+					type_id: information_objects.type_id()?,
+					cot: response_cot,
+					sequence: false,
+					positive: false,
+					information_objects,
+					..request.clone()
+				})
+			}));
+			responses.push(Asdu { cot: Cot::ActivationTermination, ..request });
+		}
+		responses
+	}
+}
+
+#[async_trait]
+impl OnNewObjects for CommandDispatcher {
+	async fn on_new_objects(&self, asdu: Asdu) {
+		let responses = match &asdu.information_objects {
+			InformationObjects::CIcNa1(objects)
+				if asdu.type_id == TypeId::C_IC_NA_1
+					&& let Some(database) = &self.database =>
+			{
+				match objects.first() {
+					Some(object) => Self::interrogation(database, &asdu, object.object.qoi),
+					None => vec![Asdu { cot: Cot::ActivationConfirmation, positive: true, ..asdu }],
+				}
+			}
+			_ => self.callback.on_new_command(asdu).await,
+		};
+		for response in responses {
+			if let Err(e) = self.response_tx.send(response) {
+				tracing::error!("Error queueing command response: {e}");
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::types::{CIcNa1, GenericObject, MSpNa1};
+
+	fn interrogation(address_field: u16) -> Asdu {
+		Asdu {
+			type_id: TypeId::C_IC_NA_1,
+			cot: Cot::Activation,
+			originator_address: 0,
+			address_field,
+			sequence: false,
+			test: false,
+			positive: false,
+			information_objects: InformationObjects::CIcNa1(vec![GenericObject {
+				address: 0,
+				object: CIcNa1 { qoi: Qoi::Global },
+			}]),
+		}
+	}
+
+	#[test]
+	fn test_broadcast_interrogation() {
+		let database = PointDatabase::new();
+		let broadcast = interrogation(BROADCAST_ADDRESS);
+
+		// Without points the interrogation is still confirmed and terminated
+		let responses = CommandDispatcher::interrogation(&database, &broadcast, Qoi::Global);
+		assert_eq!(
+			responses.iter().map(|asdu| (asdu.cot, asdu.positive)).collect::<Vec<_>>(),
+			[(Cot::ActivationConfirmation, false), (Cot::ActivationTermination, false)]
+		);
+
+		// Every station answers with its own common address
+		for common_address in [1, 2] {
+			database.update(
+				common_address,
+				&InformationObjects::MSpNa1(vec![GenericObject {
+					address: 1,
+					object: MSpNa1::default(),
+				}]),
+			);
+		}
+		let responses = CommandDispatcher::interrogation(&database, &broadcast, Qoi::Global);
+		assert_eq!(
+			responses.iter().map(|asdu| (asdu.address_field, asdu.cot)).collect::<Vec<_>>(),
+			[
+				(1, Cot::ActivationConfirmation),
+				(1, Cot::InterrogationGeneral),
+				(1, Cot::ActivationTermination),
+				(2, Cot::ActivationConfirmation),
+				(2, Cot::InterrogationGeneral),
+				(2, Cot::ActivationTermination),
+			]
+		);
+
+		let responses = CommandDispatcher::interrogation(&database, &interrogation(3), Qoi::Global);
+		assert_eq!(
+			responses.iter().map(|asdu| (asdu.cot, asdu.positive)).collect::<Vec<_>>(),
+			[(Cot::UnknownAsduAddress, true)]
+		);
+	}
+}
@@ -0,0 +1,291 @@
+use std::{
+	collections::BTreeMap,
+	sync::{PoisonError, RwLock},
+};
+
+use tracing::instrument;
+
+use crate::{
+	asdu::Asdu,
+	types::{
+		FromBytes, GenericObject, InformationObjects, MBoNa1, MDpNa1, MMeNa1, MMeNb1, MMeNc1,
+		MSpNa1, MStNa1, ToBytes, commands::Qoi,
+	},
+};
+
+/// Process image of the controlled station.
+///
+/// Stores the last value of every point, keyed by common address and
+/// information object address, and builds the responses to the general and
+/// group interrogations.
+#[derive(Debug, Default)]
+pub struct PointDatabase {
+	stations: RwLock<BTreeMap<u16, BTreeMap<u32, Point>>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Point {
+	/// The last value. Always a single object.
+	object: InformationObjects,
+	/// Bit mask of the interrogation groups, bit 0 being group 1.
+	groups: u16,
+}
+
+impl PointDatabase {
+	#[must_use]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Stores the value of every object as the last value of its point. New
+	/// points are only part of the general interrogation until they are
+	/// assigned to groups with [`PointDatabase::set_groups`].
+	#[instrument(level = "debug", skip(self))]
+	pub fn update(&self, common_address: u16, objects: &InformationObjects) {
+		let mut stations = self.stations.write().unwrap_or_else(PoisonError::into_inner);
+		let points = stations.entry(common_address).or_default();
+		for object in objects.chunks(1) {
+			let Some(&address) = object.addresses().first() else { continue };
+			points
+				.entry(address)
+				.and_modify(|point| point.object = object.clone())
+				.or_insert(Point { object, groups: 0 });
+		}
+	}
+
+	/// Assigns the point to the given interrogation groups. Returns `false` if
+	/// the point doesn't exist.
+	#[instrument(level = "debug", skip(self))]
+	pub fn set_groups(&self, common_address: u16, ioa: u32, groups: &[Qoi]) -> bool {
+		let mut stations = self.stations.write().unwrap_or_else(PoisonError::into_inner);
+		let Some(point) = stations.get_mut(&common_address).and_then(|points| points.get_mut(&ioa))
+		else {
+			return false;
+		};
+		point.groups = groups.iter().filter_map(|qoi| group_mask(*qoi)).fold(0, |acc, m| acc | m);
+		true
+	}
+
+	/// The last value of the point, if it exists.
+	#[must_use]
+	pub fn get(&self, common_address: u16, ioa: u32) -> Option<InformationObjects> {
+		let stations = self.stations.read().unwrap_or_else(PoisonError::into_inner);
+		stations.get(&common_address)?.get(&ioa).map(|point| point.object.clone())
+	}
+
+	/// The common addresses of all the stations with at least one point.
+	#[must_use]
+	pub fn common_addresses(&self) -> Vec<u16> {
+		let stations = self.stations.read().unwrap_or_else(PoisonError::into_inner);
+		stations.keys().copied().collect()
+	}
+
+	/// The objects to report for an interrogation of the station, packed in as
+	/// few ASDUs as possible. Returns `None` if the station is unknown.
+	///
+	/// The time tagged types are reported without the time tag and the types
+	/// that are not part of an interrogation (integrated totals, events of
+	/// protection equipment, ...) are skipped.
+	#[must_use]
+	pub fn interrogate(&self, common_address: u16, qoi: Qoi) -> Option<Vec<InformationObjects>> {
+		let stations = self.stations.read().unwrap_or_else(PoisonError::into_inner);
+		let points = stations.get(&common_address)?;
+
+		let mask = match qoi {
+			Qoi::Global => None,
+			_ => Some(group_mask(qoi)?),
+		};
+
+		let mut packed: Vec<InformationObjects> = Vec::new();
+		let selected = points
+			.values()
+			.filter(|point| mask.is_none_or(|mask| point.groups & mask != 0))
+			.filter_map(|point| interrogation_object(&point.object));
+		for object in selected {
+			if let Some(last) = packed.last_mut() {
+				match last.append(object) {
+					Ok(()) => continue,
+					Err(object) => packed.push(object),
+				}
+			} else {
+				packed.push(object);
+			}
+		}
+
+		Some(
+			packed
+				.iter()
+				.flat_map(|objects| match objects.type_id() {
+					Some(type_id) => objects.chunks(Asdu::max_objects(type_id)),
+					None => vec![objects.clone()],
+				})
+				.collect(),
+		)
+	}
+}
+
+/// The group bit of the interrogation qualifier. `None` for the global
+/// interrogation and the non standard qualifiers.
+const fn group_mask(qoi: Qoi) -> Option<u16> {
+	match qoi.to_byte() {
+		group @ 21..=36 => Some(1 << (group - 21)),
+		_ => None,
+	}
+}
+
+/// The object as reported in an interrogation response, if it's reported at
+/// all.
+fn interrogation_object(object: &InformationObjects) -> Option<InformationObjects> {
+	Some(match object {
+		InformationObjects::MSpNa1(objs) => InformationObjects::MSpNa1(objs.clone()),
+		InformationObjects::MSpTa1(objs) => {
+			InformationObjects::MSpNa1(strip(objs, |o| MSpNa1 { siq: o.siq.clone() }))
+		}
+		InformationObjects::MSpTb1(objs) => {
+			InformationObjects::MSpNa1(strip(objs, |o| MSpNa1 { siq: o.siq.clone() }))
+		}
+		InformationObjects::MDpNa1(objs) => InformationObjects::MDpNa1(objs.clone()),
+		InformationObjects::MDpTa1(objs) => {
+			InformationObjects::MDpNa1(strip(objs, |o| MDpNa1 { diq: o.diq.clone() }))
+		}
+		InformationObjects::MDpTb1(objs) => {
+			InformationObjects::MDpNa1(strip(objs, |o| MDpNa1 { diq: o.diq.clone() }))
+		}
+		InformationObjects::MStNa1(objs) => InformationObjects::MStNa1(objs.clone()),
+		InformationObjects::MStTa1(objs) => {
+			InformationObjects::MStNa1(strip(objs, |o| MStNa1 { vti: o.vti.clone() }))
+		}
+		InformationObjects::MStTb1(objs) => {
+			InformationObjects::MStNa1(strip(objs, |o| MStNa1 { vti: o.vti.clone() }))
+		}
+		InformationObjects::MBoNa1(objs) => InformationObjects::MBoNa1(objs.clone()),
+		InformationObjects::MBoTb1(objs) => {
+			InformationObjects::MBoNa1(strip(objs, |o| MBoNa1 { bsi: o.bsi, qds: o.qds.clone() }))
+		}
+		InformationObjects::MMeNa1(objs) => InformationObjects::MMeNa1(objs.clone()),
+		InformationObjects::MMeTa1(objs) => {
+			InformationObjects::MMeNa1(strip(objs, |o| MMeNa1 { nva: o.nva, qds: o.qds.clone() }))
+		}
+		InformationObjects::MMeTd1(objs) => {
+			InformationObjects::MMeNa1(strip(objs, |o| MMeNa1 { nva: o.nva, qds: o.qds.clone() }))
+		}
+		InformationObjects::MMeNb1(objs) => InformationObjects::MMeNb1(objs.clone()),
+		InformationObjects::MMeTb1(objs) => {
+			InformationObjects::MMeNb1(strip(objs, |o| MMeNb1 { sva: o.sva, qds: o.qds.clone() }))
+		}
+		InformationObjects::MMeTe1(objs) => {
+			InformationObjects::MMeNb1(strip(objs, |o| MMeNb1 { sva: o.sva, qds: o.qds.clone() }))
+		}
+		InformationObjects::MMeNc1(objs) => InformationObjects::MMeNc1(objs.clone()),
+		InformationObjects::MMeTc1(objs) => InformationObjects::MMeNc1(strip(objs, |o| MMeNc1 {
+			value: o.value,
+			qds: o.qds.clone(),
+		})),
+		InformationObjects::MMeTf1(objs) => InformationObjects::MMeNc1(strip(objs, |o| MMeNc1 {
+			value: o.value,
+			qds: o.qds.clone(),
+		})),
+		InformationObjects::MPsNa1(objs) => InformationObjects::MPsNa1(objs.clone()),
+		InformationObjects::MMeNd1(objs) => InformationObjects::MMeNd1(objs.clone()),
+		_ => return None,
+	})
+}
+
+fn strip<T, U>(objects: &[GenericObject<T>], f: impl Fn(&T) -> U) -> Vec<GenericObject<U>>
+where
+	T: FromBytes + ToBytes + Default,
+	U: FromBytes + ToBytes + Default,
+{
+	objects
+		.iter()
+		.map(|obj| GenericObject { address: obj.address, object: f(&obj.object) })
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		types::{
+			MItNa1, MSpTb1,
+			information_elements::{Siq, Spi},
+		},
+		types_id::TypeId,
+	};
+
+	fn single_points(addresses: impl Iterator<Item = u32>) -> InformationObjects {
+		InformationObjects::MSpNa1(
+			addresses
+				.map(|address| GenericObject {
+					address,
+					object: MSpNa1 { siq: Siq { spi: Spi::On, ..Default::default() } },
+				})
+				.collect(),
+		)
+	}
+
+	#[test]
+	fn test_update_keeps_last_value() {
+		let db = PointDatabase::new();
+		db.update(1, &single_points(1..=2));
+		db.update(
+			1,
+			&InformationObjects::MSpTb1(vec![GenericObject {
+				address: 2,
+				object: MSpTb1::default(),
+			}]),
+		);
+
+		assert_eq!(db.common_addresses(), vec![1]);
+		assert_eq!(db.get(1, 1), Some(single_points(1..=1)));
+		assert!(matches!(db.get(1, 2), Some(InformationObjects::MSpTb1(_))));
+		assert_eq!(db.get(1, 3), None);
+		assert_eq!(db.get(2, 1), None);
+	}
+
+	#[test]
+	fn test_interrogation_packing() {
+		let db = PointDatabase::new();
+		db.update(1, &single_points(1..=300));
+		db.update(
+			1,
+			&InformationObjects::MItNa1(vec![GenericObject {
+				address: 1000,
+				object: MItNa1::default(),
+			}]),
+		);
+
+		let response = db.interrogate(1, Qoi::Global).unwrap();
+		let max_objects = Asdu::max_objects(TypeId::M_SP_NA_1);
+		assert_eq!(response.len(), 300_usize.div_ceil(max_objects));
+		assert!(response.iter().all(|objects| objects.type_id() == Some(TypeId::M_SP_NA_1)));
+		assert_eq!(response.iter().map(InformationObjects::len).sum::<usize>(), 300);
+
+		assert!(db.interrogate(2, Qoi::Global).is_none());
+	}
+
+	#[test]
+	fn test_group_interrogation() {
+		let db = PointDatabase::new();
+		db.update(1, &single_points(1..=3));
+		db.update(
+			1,
+			&InformationObjects::MSpTb1(vec![GenericObject {
+				address: 4,
+				object: MSpTb1::default(),
+			}]),
+		);
+		assert!(db.set_groups(1, 2, &[Qoi::Group1, Qoi::Group2]));
+		assert!(db.set_groups(1, 4, &[Qoi::Group1]));
+		assert!(!db.set_groups(1, 5, &[Qoi::Group1]));
+
+		let response = db.interrogate(1, Qoi::Group1).unwrap();
+		assert_eq!(response.len(), 1);
+		assert_eq!(response[0].addresses(), vec![2, 4]);
+		// The time tag is removed from the interrogation response
+		assert_eq!(response[0].type_id(), Some(TypeId::M_SP_NA_1));
+
+		assert_eq!(db.interrogate(1, Qoi::Group2).unwrap()[0].addresses(), vec![2]);
+		assert!(db.interrogate(1, Qoi::Group3).unwrap().is_empty());
+	}
+}
@@ -0,0 +1,18 @@
+use snafu::Snafu;
+
+use crate::error::SpanTraceWrapper;
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub), context(suffix(false)))]
+pub enum ServerError {
+	#[snafu(display("Server is not started"))]
+	NotStarted {
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("There is no connection to send the ASDU to"))]
+	NoConnection {
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+}
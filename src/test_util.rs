@@ -0,0 +1,107 @@
+//! Helpers shared by the tests exchanging ASDUs between a server and a client
+//! over the loopback interface, or between the stations of an IEC 101 link.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::{
+	io::{AsyncReadExt as _, AsyncWriteExt as _, DuplexStream},
+	select,
+	sync::mpsc,
+};
+
+use crate::{
+	asdu::Asdu,
+	client::{Client, OnNewObjects, state::ConnectionState},
+	config::{ClientConfig, ServerConfig},
+	cot::Cot,
+	server::{OnNewCommand, Server},
+};
+
+/// Forwards every command to the channel and confirms it.
+pub struct Commands(pub mpsc::UnboundedSender<Asdu>);
+
+#[async_trait]
+impl OnNewCommand for Commands {
+	async fn on_new_command(&self, asdu: Asdu) -> Vec<Asdu> {
+		let confirmation = Asdu { cot: Cot::ActivationConfirmation, ..asdu.clone() };
+		self.0.send(asdu).unwrap();
+		vec![confirmation]
+	}
+}
+
+/// Forwards every received ASDU to the channel.
+pub struct Objects(pub mpsc::UnboundedSender<Asdu>);
+
+#[async_trait]
+impl OnNewObjects for Objects {
+	async fn on_new_objects(&self, asdu: Asdu) {
+		self.0.send(asdu).unwrap();
+	}
+}
+
+/// The configuration of a server on a random port of the loopback interface.
+pub fn server_config() -> ServerConfig {
+	ServerConfig { address: "127.0.0.1".to_owned(), port: 0, ..Default::default() }
+}
+
+/// Starts a server with the configuration.
+pub async fn start_server(
+	config: ServerConfig,
+	callback: impl OnNewCommand + Send + Sync + 'static,
+) -> Server {
+	let mut server = Server::new(config, callback);
+	server.start().await.unwrap();
+	server
+}
+
+/// Connects a client to the local port and waits until the data transfer is
+/// started. Returns the client and the ASDUs it receives.
+pub async fn connect_client(
+	port: u16,
+	config: ClientConfig,
+) -> (Client, mpsc::UnboundedReceiver<Asdu>) {
+	let (object_tx, object_rx) = mpsc::unbounded_channel();
+	let mut client = Client::new(ClientConfig { port, ..config }, Objects(object_tx));
+	let mut status = client.connection_status();
+	client.connect().await.unwrap();
+	client.start_receiving().await.unwrap();
+	tokio::time::timeout(
+		Duration::from_secs(5),
+		status.wait_for(|status| status.state == ConnectionState::Started),
+	)
+	.await
+	.unwrap()
+	.unwrap();
+	(client, object_rx)
+}
+
+/// Starts a server with the default configuration and connects a client to
+/// it.
+pub async fn start(
+	callback: impl OnNewCommand + Send + Sync + 'static,
+) -> (Server, Client, mpsc::UnboundedReceiver<Asdu>) {
+	let server = start_server(server_config(), callback).await;
+	let port = server.local_addr().unwrap().port();
+	let (client, object_rx) = connect_client(port, ClientConfig::default()).await;
+	(server, client, object_rx)
+}
+
+/// Forwards the frames of the controlling station to every controlled
+/// station, and their responses back, like a bus.
+pub async fn bus(mut controlling: DuplexStream, mut first: DuplexStream, mut second: DuplexStream) {
+	let (mut down, mut up_1, mut up_2) = ([0; 256], [0; 256], [0; 256]);
+	loop {
+		let (data, targets): (&[u8], Vec<&mut DuplexStream>) = select! {
+			Ok(read @ 1..) = controlling.read(&mut down) => (&down[..read], vec![&mut first, &mut second]),
+			Ok(read @ 1..) = first.read(&mut up_1) => (&up_1[..read], vec![&mut controlling]),
+			Ok(read @ 1..) = second.read(&mut up_2) => (&up_2[..read], vec![&mut controlling]),
+			else => return,
+		};
+		for target in targets {
+			if target.write_all(data).await.is_err() {
+				return;
+			}
+		}
+	}
+}
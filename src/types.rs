@@ -1,4 +1,5 @@
 pub mod commands;
+pub mod file_transfer;
 pub mod information_elements;
 pub mod measurements;
 pub mod parameters;
@@ -9,6 +10,7 @@ pub use commands::{
 	CBoNa1, CBoTa1, CCdNa1, CCiNa1, CCsNa1, CIcNa1, CRdNa1, CRpNa1, CScNa1, CScTa1, CSeNa1, CSeNb1,
 	CSeNc1, CSeTa1, CSeTb1, CSeTc1, CTsNa1, CTsTa1, CdcNa1, CdcTa1, CrcNa1, CrcTa1,
 };
+pub use file_transfer::{FDrTa1, FFaNa1, FFrNa1, FLsNa1, FScNa1, FSgNa1, FSrNa1};
 pub use measurements::{
 	MBoNa1, MBoTb1, MDpNa1, MDpTa1, MDpTb1, MEiNa1, MEpTa1, MEpTb1, MEpTc1, MEpTd1, MEpTe1, MEpTf1,
 	MItNa1, MItTb1, MMeNa1, MMeNb1, MMeNc1, MMeNd1, MMeTa1, MMeTb1, MMeTc1, MMeTd1, MMeTe1, MMeTf1,
@@ -18,9 +20,17 @@ pub use parameters::{PAcNa1, PMeNa1, PMeNb1, PMeNc1};
 use snafu::{OptionExt, Snafu};
 use tracing::instrument;
 
-use crate::{error::SpanTraceWrapper, types::time::ParseTimeError, types_id::TypeId};
+use crate::{
+	error::SpanTraceWrapper,
+	types::{
+		information_elements::SelectExecute,
+		time::{Cp24Time2a, ParseTimeError},
+	},
+	types_id::TypeId,
+};
 
-const ADDRESS_SIZE: usize = 3;
+/// Size of the information object address in IEC 104.
+pub(crate) const ADDRESS_SIZE: usize = 3;
 
 pub trait FromBytes: Sized {
 	fn from_bytes(bytes: &[u8]) -> Result<Self, ParseError>;
@@ -50,6 +60,25 @@ pub enum ParseError {
 		#[snafu(implicit)]
 		context: Box<SpanTraceWrapper>,
 	},
+	#[snafu(display("Address {address} doesn't follow the previous address of the sequence"))]
+	NotSequential {
+		address: u32,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Address {address} doesn't fit in {size} bytes"))]
+	AddressTooLarge {
+		address: u32,
+		size: usize,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("Segment of {len} bytes doesn't fit in an ASDU"))]
+	SegmentTooLong {
+		len: usize,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
 	#[snafu(display("Failed to convert to sized slice"))]
 	SizedSlice {
 		source: std::array::TryFromSliceError,
@@ -148,24 +177,45 @@ pub enum InformationObjects {
 	PMeNb1(Vec<GenericObject<PMeNb1>>),
 	PMeNc1(Vec<GenericObject<PMeNc1>>),
 	PAcNa1(Vec<GenericObject<PAcNa1>>),
+	FFrNa1(Vec<GenericObject<FFrNa1>>),
+	FSrNa1(Vec<GenericObject<FSrNa1>>),
+	FScNa1(Vec<GenericObject<FScNa1>>),
+	FLsNa1(Vec<GenericObject<FLsNa1>>),
+	FFaNa1(Vec<GenericObject<FFaNa1>>),
+	FSgNa1(Vec<GenericObject<FSgNa1>>),
+	FDrTa1(Vec<GenericObject<FDrTa1>>),
 	Raw(Vec<GenericObject<RawObject>>),
 }
 
 impl InformationObjects {
+	/// Reads the little endian address in the first `size` bytes.
+	fn read_address(bytes: &[u8], size: usize) -> Result<u32, ParseError> {
+		let bytes = bytes.get(..size).context(NotEnoughBytes)?;
+		Ok(bytes.iter().rev().fold(0, |address, byte| (address << 8) | u32::from(*byte)))
+	}
+
+	/// Writes the address in `size` little endian bytes.
+	fn write_address(address: u32, size: usize, buffer: &mut Vec<u8>) -> Result<(), ParseError> {
+		let bytes = address.to_le_bytes();
+		if bytes[size..].iter().any(|byte| *byte != 0) {
+			return AddressTooLarge { address, size }.fail();
+		}
+		buffer.extend_from_slice(&bytes[..size]);
+		Ok(())
+	}
+
 	#[instrument]
 	fn build_objects<T: FromBytes + ToBytes + Default>(
 		type_id: TypeId,
 		sequence: bool,
 		num_objs: u8,
+		ioa_size: usize,
 		bytes: &[u8],
 	) -> Result<Vec<GenericObject<T>>, ParseError> {
-		if !type_id.is_standard() {
-			tracing::trace!("Building RAW information objects. Bytes: {:?}", bytes);
-			if bytes.len() < 3 {
-				return NotEnoughBytes.fail();
-			}
-			let address = u32::from_be_bytes([0, bytes[2], bytes[1], bytes[0]]);
-			let object = T::from_bytes(&bytes[3..])?;
+		if !type_id.is_standard() || !type_id.has_fixed_size() {
+			tracing::trace!("Building a single information object. Bytes: {:?}", bytes);
+			let address = Self::read_address(bytes, ioa_size)?;
+			let object = T::from_bytes(&bytes[ioa_size..])?;
 			return Ok(vec![GenericObject { address, object }]);
 		}
 		let object_size = type_id.size();
@@ -177,13 +227,10 @@ impl InformationObjects {
 		if sequence {
 			let mut objs = Vec::<GenericObject<T>>::with_capacity(num_objs as usize);
 			let (first_chunk, other_chunks) =
-				bytes.split_at_checked(object_size + ADDRESS_SIZE).context(NotEnoughBytes)?;
+				bytes.split_at_checked(object_size + ioa_size).context(NotEnoughBytes)?;
 
-			// This won't panic because we checked that the first chunk is at least
-			// ADDRESS_SIZE bytes long
-			let first_addr =
-				u32::from_le_bytes([first_chunk[0], first_chunk[1], first_chunk[2], 0]);
-			let first_obj = T::from_bytes(&first_chunk[ADDRESS_SIZE..])?;
+			let first_addr = Self::read_address(first_chunk, ioa_size)?;
+			let first_obj = T::from_bytes(&first_chunk[ioa_size..])?;
 			objs.push(GenericObject { address: first_addr, object: first_obj });
 			let other_chunks = other_chunks.chunks_exact(object_size);
 			// TODO: Do we really need to make sure of this here?
@@ -196,25 +243,25 @@ impl InformationObjects {
 				.map(|(i, chunk)| {
 					tracing::trace!("Building object: {:?}", chunk);
 					// If it's a sequence we only get the address of the first object. So the first
-					// object has object_size + 3 bytes for the address. The subsequent chunks only
-					// have the object_size.
+					// object has object_size + ioa_size bytes. The subsequent chunks only have the
+					// object_size.
 					// Since the i starts at 0, we need to add 1 to the address.
 					let address = first_addr + (i as u32) + 1;
-					let object = T::from_bytes(&chunk[ADDRESS_SIZE..])?;
+					let object = T::from_bytes(chunk)?;
 					Ok(GenericObject { address, object })
 				})
 				.collect::<Result<Vec<_>, ParseError>>()?;
 			objs.extend(other_objs);
 			Ok::<_, ParseError>(objs)
 		} else {
-			// If it's not a sequence we get the address of each object in the first 3
-			// bytes.
+			// If it's not a sequence we get the address of each object in the first
+			// ioa_size bytes.
 			Ok(bytes[0..]
-				.chunks(object_size + 3)
+				.chunks(object_size + ioa_size)
 				.map(|chunk| {
 					tracing::trace!("Building object: {:?}", chunk);
-					let address = u32::from_be_bytes([0, chunk[2], chunk[1], chunk[0]]);
-					let object = T::from_bytes(&chunk[3..])?;
+					let address = Self::read_address(chunk, ioa_size)?;
+					let object = T::from_bytes(&chunk[ioa_size..])?;
 					Ok(GenericObject { address, object })
 				})
 				.collect::<Result<Vec<_>, ParseError>>()?)
@@ -224,15 +271,20 @@ impl InformationObjects {
 	#[instrument(skip_all)]
 	fn serialize_objects<T: FromBytes + ToBytes + Default>(
 		objects: &[GenericObject<T>],
+		sequence: bool,
+		ioa_size: usize,
 		buffer: &mut Vec<u8>,
 	) -> Result<(), ParseError> {
-		//TODO: Handle sequence
-
-		for obj in objects {
-			let address = obj.address.to_le_bytes();
-			buffer.push(address[0]);
-			buffer.push(address[1]);
-			buffer.push(address[2]);
+		for (i, obj) in objects.iter().enumerate() {
+			// In a sequence only the first object carries its address, the others
+			// must follow it
+			if sequence && i > 0 {
+				if obj.address != objects[0].address + i as u32 {
+					return NotSequential { address: obj.address }.fail();
+				}
+			} else {
+				Self::write_address(obj.address, ioa_size, buffer)?;
+			}
 			obj.object.to_bytes(buffer)?;
 		}
 
@@ -244,189 +296,204 @@ impl InformationObjects {
 		type_id: TypeId,
 		sequence: bool,
 		num_objs: u8,
+		ioa_size: usize,
 		bytes: &[u8],
 	) -> Result<Self, ParseError> {
 		Ok(match type_id {
 			TypeId::M_SP_NA_1 => InformationObjects::MSpNa1(Self::build_objects::<MSpNa1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::M_SP_TA_1 => InformationObjects::MSpTa1(Self::build_objects::<MSpTa1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::M_DP_NA_1 => InformationObjects::MDpNa1(Self::build_objects::<MDpNa1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::M_DP_TA_1 => InformationObjects::MDpTa1(Self::build_objects::<MDpTa1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::M_ST_NA_1 => InformationObjects::MStNa1(Self::build_objects::<MStNa1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::M_ST_TA_1 => InformationObjects::MStTa1(Self::build_objects::<MStTa1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::M_BO_NA_1 => InformationObjects::MBoNa1(Self::build_objects::<MBoNa1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::M_ME_NA_1 => InformationObjects::MMeNa1(Self::build_objects::<MMeNa1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::M_ME_TA_1 => InformationObjects::MMeTa1(Self::build_objects::<MMeTa1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::M_ME_NB_1 => InformationObjects::MMeNb1(Self::build_objects::<MMeNb1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::M_ME_TB_1 => InformationObjects::MMeTb1(Self::build_objects::<MMeTb1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::M_ME_NC_1 => InformationObjects::MMeNc1(Self::build_objects::<MMeNc1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::M_ME_TC_1 => InformationObjects::MMeTc1(Self::build_objects::<MMeTc1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::M_IT_NA_1 => InformationObjects::MItNa1(Self::build_objects::<MItNa1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::M_EP_TA_1 => InformationObjects::MEpTa1(Self::build_objects::<MEpTa1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::M_EP_TB_1 => InformationObjects::MEpTb1(Self::build_objects::<MEpTb1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::M_EP_TC_1 => InformationObjects::MEpTc1(Self::build_objects::<MEpTc1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::M_PS_NA_1 => InformationObjects::MPsNa1(Self::build_objects::<MPsNa1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::M_ME_ND_1 => InformationObjects::MMeNd1(Self::build_objects::<MMeNd1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::M_SP_TB_1 => InformationObjects::MSpTb1(Self::build_objects::<MSpTb1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::M_DP_TB_1 => InformationObjects::MDpTb1(Self::build_objects::<MDpTb1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::M_ST_TB_1 => InformationObjects::MStTb1(Self::build_objects::<MStTb1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::M_BO_TB_1 => InformationObjects::MBoTb1(Self::build_objects::<MBoTb1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::M_ME_TD_1 => InformationObjects::MMeTd1(Self::build_objects::<MMeTd1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::M_ME_TE_1 => InformationObjects::MMeTe1(Self::build_objects::<MMeTe1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::M_ME_TF_1 => InformationObjects::MMeTf1(Self::build_objects::<MMeTf1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::M_IT_TB_1 => InformationObjects::MItTb1(Self::build_objects::<MItTb1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::M_EP_TD_1 => InformationObjects::MEpTd1(Self::build_objects::<MEpTd1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::M_EP_TE_1 => InformationObjects::MEpTe1(Self::build_objects::<MEpTe1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::M_EP_TF_1 => InformationObjects::MEpTf1(Self::build_objects::<MEpTf1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::M_EI_NA_1 => InformationObjects::MEiNa1(Self::build_objects::<MEiNa1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::C_SC_NA_1 => InformationObjects::CScNa1(Self::build_objects::<CScNa1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::C_DC_NA_1 => InformationObjects::CdcNa1(Self::build_objects::<CdcNa1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::C_RC_NA_1 => InformationObjects::CrcNa1(Self::build_objects::<CrcNa1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::C_SE_NA_1 => InformationObjects::CSeNa1(Self::build_objects::<CSeNa1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::C_SE_NB_1 => InformationObjects::CSeNb1(Self::build_objects::<CSeNb1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::C_SE_NC_1 => InformationObjects::CSeNc1(Self::build_objects::<CSeNc1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::C_BO_NA_1 => InformationObjects::CBoNa1(Self::build_objects::<CBoNa1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::C_SC_TA_1 => InformationObjects::CScTa1(Self::build_objects::<CScTa1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::C_DC_TA_1 => InformationObjects::CdcTa1(Self::build_objects::<CdcTa1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::C_RC_TA_1 => InformationObjects::CrcTa1(Self::build_objects::<CrcTa1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::C_SE_TA_1 => InformationObjects::CSeTa1(Self::build_objects::<CSeTa1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::C_SE_TB_1 => InformationObjects::CSeTb1(Self::build_objects::<CSeTb1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::C_SE_TC_1 => InformationObjects::CSeTc1(Self::build_objects::<CSeTc1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::C_BO_TA_1 => InformationObjects::CBoTa1(Self::build_objects::<CBoTa1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::C_IC_NA_1 => InformationObjects::CIcNa1(Self::build_objects::<CIcNa1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::C_CI_NA_1 => InformationObjects::CCiNa1(Self::build_objects::<CCiNa1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::C_RD_NA_1 => InformationObjects::CRdNa1(Self::build_objects::<CRdNa1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::C_CS_NA_1 => InformationObjects::CCsNa1(Self::build_objects::<CCsNa1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::C_TS_NA_1 => InformationObjects::CTsNa1(Self::build_objects::<CTsNa1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::C_RP_NA_1 => InformationObjects::CRpNa1(Self::build_objects::<CRpNa1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::C_CD_NA_1 => InformationObjects::CCdNa1(Self::build_objects::<CCdNa1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::C_TS_TA_1 => InformationObjects::CTsTa1(Self::build_objects::<CTsTa1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::P_ME_NA_1 => InformationObjects::PMeNa1(Self::build_objects::<PMeNa1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::P_ME_NB_1 => InformationObjects::PMeNb1(Self::build_objects::<PMeNb1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::P_ME_NC_1 => InformationObjects::PMeNc1(Self::build_objects::<PMeNc1>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 			TypeId::P_AC_NA_1 => InformationObjects::PAcNa1(Self::build_objects::<PAcNa1>(
-				type_id, sequence, num_objs, bytes,
-			)?),
-			TypeId::F_FR_NA_1
-			| TypeId::F_SR_NA_1
-			| TypeId::F_SC_NA_1
-			| TypeId::F_LS_NA_1
-			| TypeId::F_FA_NA_1
-			| TypeId::F_SG_NA_1
-			| TypeId::F_DR_TA_1 => NotImplemented.fail()?,
+				type_id, sequence, num_objs, ioa_size, bytes,
+			)?),
+			TypeId::F_FR_NA_1 => InformationObjects::FFrNa1(Self::build_objects::<FFrNa1>(
+				type_id, sequence, num_objs, ioa_size, bytes,
+			)?),
+			TypeId::F_SR_NA_1 => InformationObjects::FSrNa1(Self::build_objects::<FSrNa1>(
+				type_id, sequence, num_objs, ioa_size, bytes,
+			)?),
+			TypeId::F_SC_NA_1 => InformationObjects::FScNa1(Self::build_objects::<FScNa1>(
+				type_id, sequence, num_objs, ioa_size, bytes,
+			)?),
+			TypeId::F_LS_NA_1 => InformationObjects::FLsNa1(Self::build_objects::<FLsNa1>(
+				type_id, sequence, num_objs, ioa_size, bytes,
+			)?),
+			TypeId::F_FA_NA_1 => InformationObjects::FFaNa1(Self::build_objects::<FFaNa1>(
+				type_id, sequence, num_objs, ioa_size, bytes,
+			)?),
+			TypeId::F_SG_NA_1 => InformationObjects::FSgNa1(Self::build_objects::<FSgNa1>(
+				type_id, sequence, num_objs, ioa_size, bytes,
+			)?),
+			TypeId::F_DR_TA_1 => InformationObjects::FDrTa1(Self::build_objects::<FDrTa1>(
+				type_id, sequence, num_objs, ioa_size, bytes,
+			)?),
 			_ => InformationObjects::Raw(Self::build_objects::<RawObject>(
-				type_id, sequence, num_objs, bytes,
+				type_id, sequence, num_objs, ioa_size, bytes,
 			)?),
 		})
 	}
@@ -491,6 +558,13 @@ impl InformationObjects {
 			InformationObjects::PMeNb1(objs) => objs.len(),
 			InformationObjects::PMeNc1(objs) => objs.len(),
 			InformationObjects::PAcNa1(objs) => objs.len(),
+			InformationObjects::FFrNa1(objs) => objs.len(),
+			InformationObjects::FSrNa1(objs) => objs.len(),
+			InformationObjects::FScNa1(objs) => objs.len(),
+			InformationObjects::FLsNa1(objs) => objs.len(),
+			InformationObjects::FFaNa1(objs) => objs.len(),
+			InformationObjects::FSgNa1(objs) => objs.len(),
+			InformationObjects::FDrTa1(objs) => objs.len(),
 			InformationObjects::Raw(objs) => objs.len(),
 		}
 	}
@@ -555,70 +629,915 @@ impl InformationObjects {
 			InformationObjects::PMeNb1(objs) => objs.is_empty(),
 			InformationObjects::PMeNc1(objs) => objs.is_empty(),
 			InformationObjects::PAcNa1(objs) => objs.is_empty(),
+			InformationObjects::FFrNa1(objs) => objs.is_empty(),
+			InformationObjects::FSrNa1(objs) => objs.is_empty(),
+			InformationObjects::FScNa1(objs) => objs.is_empty(),
+			InformationObjects::FLsNa1(objs) => objs.is_empty(),
+			InformationObjects::FFaNa1(objs) => objs.is_empty(),
+			InformationObjects::FSgNa1(objs) => objs.is_empty(),
+			InformationObjects::FDrTa1(objs) => objs.is_empty(),
 			InformationObjects::Raw(objs) => objs.is_empty(),
 		}
 	}
 
+	/// Serializes every object with its IEC 104 address.
 	pub fn to_bytes(&self, buffer: &mut Vec<u8>) -> Result<(), ParseError> {
+		self.to_bytes_with_layout(false, ADDRESS_SIZE, buffer)
+	}
+
+	/// Serializes the objects with addresses of `ioa_size` bytes. In a
+	/// sequence only the address of the first object is written, and the
+	/// addresses of the others must follow it.
+	#[allow(clippy::too_many_lines)]
+	pub fn to_bytes_with_layout(
+		&self,
+		sequence: bool,
+		ioa_size: usize,
+		buffer: &mut Vec<u8>,
+	) -> Result<(), ParseError> {
+		match self {
+			InformationObjects::MSpNa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::MSpTa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::MDpNa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::MDpTa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::MStNa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::MStTa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::MBoNa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::MMeNa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::MMeTa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::MMeNb1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::MMeTb1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::MMeNc1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::MMeTc1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::MItNa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::MEpTa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::MEpTb1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::MEpTc1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::MPsNa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::MMeNd1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::MSpTb1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::MDpTb1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::MStTb1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::MBoTb1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::MMeTd1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::MMeTe1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::MMeTf1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::MItTb1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::MEpTd1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::MEpTe1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::MEpTf1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::MEiNa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::CScNa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::CdcNa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::CrcNa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::CSeNa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::CSeNb1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::CSeNc1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::CBoNa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::CScTa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::CdcTa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::CrcTa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::CSeTa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::CSeTb1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::CSeTc1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::CBoTa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::CIcNa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::CCiNa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::CRdNa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::CCsNa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::CTsNa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::CRpNa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::CCdNa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::CTsTa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::PMeNa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::PMeNb1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::PMeNc1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::PAcNa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::FFrNa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::FSrNa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::FScNa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::FLsNa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::FFaNa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::FSgNa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::FDrTa1(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+			InformationObjects::Raw(objs) => {
+				Self::serialize_objects(objs, sequence, ioa_size, buffer)
+			}
+		}
+	}
+	/// The type of the information objects. Raw objects don't carry their type.
+	#[must_use]
+	pub const fn type_id(&self) -> Option<TypeId> {
+		match self {
+			InformationObjects::MSpNa1(_) => Some(TypeId::M_SP_NA_1),
+			InformationObjects::MSpTa1(_) => Some(TypeId::M_SP_TA_1),
+			InformationObjects::MDpNa1(_) => Some(TypeId::M_DP_NA_1),
+			InformationObjects::MDpTa1(_) => Some(TypeId::M_DP_TA_1),
+			InformationObjects::MStNa1(_) => Some(TypeId::M_ST_NA_1),
+			InformationObjects::MStTa1(_) => Some(TypeId::M_ST_TA_1),
+			InformationObjects::MBoNa1(_) => Some(TypeId::M_BO_NA_1),
+			InformationObjects::MMeNa1(_) => Some(TypeId::M_ME_NA_1),
+			InformationObjects::MMeTa1(_) => Some(TypeId::M_ME_TA_1),
+			InformationObjects::MMeNb1(_) => Some(TypeId::M_ME_NB_1),
+			InformationObjects::MMeTb1(_) => Some(TypeId::M_ME_TB_1),
+			InformationObjects::MMeNc1(_) => Some(TypeId::M_ME_NC_1),
+			InformationObjects::MMeTc1(_) => Some(TypeId::M_ME_TC_1),
+			InformationObjects::MItNa1(_) => Some(TypeId::M_IT_NA_1),
+			InformationObjects::MEpTa1(_) => Some(TypeId::M_EP_TA_1),
+			InformationObjects::MEpTb1(_) => Some(TypeId::M_EP_TB_1),
+			InformationObjects::MEpTc1(_) => Some(TypeId::M_EP_TC_1),
+			InformationObjects::MPsNa1(_) => Some(TypeId::M_PS_NA_1),
+			InformationObjects::MMeNd1(_) => Some(TypeId::M_ME_ND_1),
+			InformationObjects::MSpTb1(_) => Some(TypeId::M_SP_TB_1),
+			InformationObjects::MDpTb1(_) => Some(TypeId::M_DP_TB_1),
+			InformationObjects::MStTb1(_) => Some(TypeId::M_ST_TB_1),
+			InformationObjects::MBoTb1(_) => Some(TypeId::M_BO_TB_1),
+			InformationObjects::MMeTd1(_) => Some(TypeId::M_ME_TD_1),
+			InformationObjects::MMeTe1(_) => Some(TypeId::M_ME_TE_1),
+			InformationObjects::MMeTf1(_) => Some(TypeId::M_ME_TF_1),
+			InformationObjects::MItTb1(_) => Some(TypeId::M_IT_TB_1),
+			InformationObjects::MEpTd1(_) => Some(TypeId::M_EP_TD_1),
+			InformationObjects::MEpTe1(_) => Some(TypeId::M_EP_TE_1),
+			InformationObjects::MEpTf1(_) => Some(TypeId::M_EP_TF_1),
+			InformationObjects::MEiNa1(_) => Some(TypeId::M_EI_NA_1),
+			InformationObjects::CScNa1(_) => Some(TypeId::C_SC_NA_1),
+			InformationObjects::CdcNa1(_) => Some(TypeId::C_DC_NA_1),
+			InformationObjects::CrcNa1(_) => Some(TypeId::C_RC_NA_1),
+			InformationObjects::CSeNa1(_) => Some(TypeId::C_SE_NA_1),
+			InformationObjects::CSeNb1(_) => Some(TypeId::C_SE_NB_1),
+			InformationObjects::CSeNc1(_) => Some(TypeId::C_SE_NC_1),
+			InformationObjects::CBoNa1(_) => Some(TypeId::C_BO_NA_1),
+			InformationObjects::CScTa1(_) => Some(TypeId::C_SC_TA_1),
+			InformationObjects::CdcTa1(_) => Some(TypeId::C_DC_TA_1),
+			InformationObjects::CrcTa1(_) => Some(TypeId::C_RC_TA_1),
+			InformationObjects::CSeTa1(_) => Some(TypeId::C_SE_TA_1),
+			InformationObjects::CSeTb1(_) => Some(TypeId::C_SE_TB_1),
+			InformationObjects::CSeTc1(_) => Some(TypeId::C_SE_TC_1),
+			InformationObjects::CBoTa1(_) => Some(TypeId::C_BO_TA_1),
+			InformationObjects::CIcNa1(_) => Some(TypeId::C_IC_NA_1),
+			InformationObjects::CCiNa1(_) => Some(TypeId::C_CI_NA_1),
+			InformationObjects::CRdNa1(_) => Some(TypeId::C_RD_NA_1),
+			InformationObjects::CCsNa1(_) => Some(TypeId::C_CS_NA_1),
+			InformationObjects::CTsNa1(_) => Some(TypeId::C_TS_NA_1),
+			InformationObjects::CRpNa1(_) => Some(TypeId::C_RP_NA_1),
+			InformationObjects::CCdNa1(_) => Some(TypeId::C_CD_NA_1),
+			InformationObjects::CTsTa1(_) => Some(TypeId::C_TS_TA_1),
+			InformationObjects::PMeNa1(_) => Some(TypeId::P_ME_NA_1),
+			InformationObjects::PMeNb1(_) => Some(TypeId::P_ME_NB_1),
+			InformationObjects::PMeNc1(_) => Some(TypeId::P_ME_NC_1),
+			InformationObjects::PAcNa1(_) => Some(TypeId::P_AC_NA_1),
+			InformationObjects::FFrNa1(_) => Some(TypeId::F_FR_NA_1),
+			InformationObjects::FSrNa1(_) => Some(TypeId::F_SR_NA_1),
+			InformationObjects::FScNa1(_) => Some(TypeId::F_SC_NA_1),
+			InformationObjects::FLsNa1(_) => Some(TypeId::F_LS_NA_1),
+			InformationObjects::FFaNa1(_) => Some(TypeId::F_FA_NA_1),
+			InformationObjects::FSgNa1(_) => Some(TypeId::F_SG_NA_1),
+			InformationObjects::FDrTa1(_) => Some(TypeId::F_DR_TA_1),
+			InformationObjects::Raw(_) => None,
+		}
+	}
+
+	/// The information object addresses of all the objects, in order.
+	#[must_use]
+	pub fn addresses(&self) -> Vec<u32> {
+		match self {
+			InformationObjects::MSpNa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::MSpTa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::MDpNa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::MDpTa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::MStNa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::MStTa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::MBoNa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::MMeNa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::MMeTa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::MMeNb1(objs) => Self::collect_addresses(objs),
+			InformationObjects::MMeTb1(objs) => Self::collect_addresses(objs),
+			InformationObjects::MMeNc1(objs) => Self::collect_addresses(objs),
+			InformationObjects::MMeTc1(objs) => Self::collect_addresses(objs),
+			InformationObjects::MItNa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::MEpTa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::MEpTb1(objs) => Self::collect_addresses(objs),
+			InformationObjects::MEpTc1(objs) => Self::collect_addresses(objs),
+			InformationObjects::MPsNa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::MMeNd1(objs) => Self::collect_addresses(objs),
+			InformationObjects::MSpTb1(objs) => Self::collect_addresses(objs),
+			InformationObjects::MDpTb1(objs) => Self::collect_addresses(objs),
+			InformationObjects::MStTb1(objs) => Self::collect_addresses(objs),
+			InformationObjects::MBoTb1(objs) => Self::collect_addresses(objs),
+			InformationObjects::MMeTd1(objs) => Self::collect_addresses(objs),
+			InformationObjects::MMeTe1(objs) => Self::collect_addresses(objs),
+			InformationObjects::MMeTf1(objs) => Self::collect_addresses(objs),
+			InformationObjects::MItTb1(objs) => Self::collect_addresses(objs),
+			InformationObjects::MEpTd1(objs) => Self::collect_addresses(objs),
+			InformationObjects::MEpTe1(objs) => Self::collect_addresses(objs),
+			InformationObjects::MEpTf1(objs) => Self::collect_addresses(objs),
+			InformationObjects::MEiNa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::CScNa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::CdcNa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::CrcNa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::CSeNa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::CSeNb1(objs) => Self::collect_addresses(objs),
+			InformationObjects::CSeNc1(objs) => Self::collect_addresses(objs),
+			InformationObjects::CBoNa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::CScTa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::CdcTa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::CrcTa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::CSeTa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::CSeTb1(objs) => Self::collect_addresses(objs),
+			InformationObjects::CSeTc1(objs) => Self::collect_addresses(objs),
+			InformationObjects::CBoTa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::CIcNa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::CCiNa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::CRdNa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::CCsNa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::CTsNa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::CRpNa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::CCdNa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::CTsTa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::PMeNa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::PMeNb1(objs) => Self::collect_addresses(objs),
+			InformationObjects::PMeNc1(objs) => Self::collect_addresses(objs),
+			InformationObjects::PAcNa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::FFrNa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::FSrNa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::FScNa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::FLsNa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::FFaNa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::FSgNa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::FDrTa1(objs) => Self::collect_addresses(objs),
+			InformationObjects::Raw(objs) => Self::collect_addresses(objs),
+		}
+	}
+
+	/// The CP24Time2a time tag of every object, in order. `None` for the
+	/// objects without a CP24Time2a time tag.
+	#[must_use]
+	pub fn cp24_times(&self) -> Vec<Option<Cp24Time2a>> {
+		match self {
+			InformationObjects::MSpTa1(objs) => objs.iter().map(|o| Some(o.object.time)).collect(),
+			InformationObjects::MDpTa1(objs) => objs.iter().map(|o| Some(o.object.time)).collect(),
+			InformationObjects::MStTa1(objs) => objs.iter().map(|o| Some(o.object.time)).collect(),
+			InformationObjects::MMeTa1(objs) => objs.iter().map(|o| Some(o.object.time)).collect(),
+			InformationObjects::MMeTb1(objs) => objs.iter().map(|o| Some(o.object.time)).collect(),
+			InformationObjects::MMeTc1(objs) => objs.iter().map(|o| Some(o.object.time)).collect(),
+			InformationObjects::MEpTa1(objs) => objs.iter().map(|o| Some(o.object.time)).collect(),
+			InformationObjects::MEpTb1(objs) => objs.iter().map(|o| Some(o.object.time)).collect(),
+			InformationObjects::MEpTc1(objs) => objs.iter().map(|o| Some(o.object.time)).collect(),
+			_ => vec![None; self.len()],
+		}
+	}
+
+	/// Splits the objects in chunks of at most `chunk_size` objects of the same
+	/// type.
+	///
+	/// # Panics
+	///
+	/// Panics if `chunk_size` is 0.
+	#[must_use]
+	#[allow(clippy::too_many_lines)]
+	pub fn chunks(&self, chunk_size: usize) -> Vec<Self> {
+		match self {
+			InformationObjects::MSpNa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::MSpNa1(c.to_vec())).collect()
+			}
+			InformationObjects::MSpTa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::MSpTa1(c.to_vec())).collect()
+			}
+			InformationObjects::MDpNa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::MDpNa1(c.to_vec())).collect()
+			}
+			InformationObjects::MDpTa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::MDpTa1(c.to_vec())).collect()
+			}
+			InformationObjects::MStNa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::MStNa1(c.to_vec())).collect()
+			}
+			InformationObjects::MStTa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::MStTa1(c.to_vec())).collect()
+			}
+			InformationObjects::MBoNa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::MBoNa1(c.to_vec())).collect()
+			}
+			InformationObjects::MMeNa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::MMeNa1(c.to_vec())).collect()
+			}
+			InformationObjects::MMeTa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::MMeTa1(c.to_vec())).collect()
+			}
+			InformationObjects::MMeNb1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::MMeNb1(c.to_vec())).collect()
+			}
+			InformationObjects::MMeTb1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::MMeTb1(c.to_vec())).collect()
+			}
+			InformationObjects::MMeNc1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::MMeNc1(c.to_vec())).collect()
+			}
+			InformationObjects::MMeTc1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::MMeTc1(c.to_vec())).collect()
+			}
+			InformationObjects::MItNa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::MItNa1(c.to_vec())).collect()
+			}
+			InformationObjects::MEpTa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::MEpTa1(c.to_vec())).collect()
+			}
+			InformationObjects::MEpTb1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::MEpTb1(c.to_vec())).collect()
+			}
+			InformationObjects::MEpTc1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::MEpTc1(c.to_vec())).collect()
+			}
+			InformationObjects::MPsNa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::MPsNa1(c.to_vec())).collect()
+			}
+			InformationObjects::MMeNd1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::MMeNd1(c.to_vec())).collect()
+			}
+			InformationObjects::MSpTb1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::MSpTb1(c.to_vec())).collect()
+			}
+			InformationObjects::MDpTb1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::MDpTb1(c.to_vec())).collect()
+			}
+			InformationObjects::MStTb1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::MStTb1(c.to_vec())).collect()
+			}
+			InformationObjects::MBoTb1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::MBoTb1(c.to_vec())).collect()
+			}
+			InformationObjects::MMeTd1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::MMeTd1(c.to_vec())).collect()
+			}
+			InformationObjects::MMeTe1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::MMeTe1(c.to_vec())).collect()
+			}
+			InformationObjects::MMeTf1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::MMeTf1(c.to_vec())).collect()
+			}
+			InformationObjects::MItTb1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::MItTb1(c.to_vec())).collect()
+			}
+			InformationObjects::MEpTd1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::MEpTd1(c.to_vec())).collect()
+			}
+			InformationObjects::MEpTe1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::MEpTe1(c.to_vec())).collect()
+			}
+			InformationObjects::MEpTf1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::MEpTf1(c.to_vec())).collect()
+			}
+			InformationObjects::MEiNa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::MEiNa1(c.to_vec())).collect()
+			}
+			InformationObjects::CScNa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::CScNa1(c.to_vec())).collect()
+			}
+			InformationObjects::CdcNa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::CdcNa1(c.to_vec())).collect()
+			}
+			InformationObjects::CrcNa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::CrcNa1(c.to_vec())).collect()
+			}
+			InformationObjects::CSeNa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::CSeNa1(c.to_vec())).collect()
+			}
+			InformationObjects::CSeNb1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::CSeNb1(c.to_vec())).collect()
+			}
+			InformationObjects::CSeNc1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::CSeNc1(c.to_vec())).collect()
+			}
+			InformationObjects::CBoNa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::CBoNa1(c.to_vec())).collect()
+			}
+			InformationObjects::CScTa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::CScTa1(c.to_vec())).collect()
+			}
+			InformationObjects::CdcTa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::CdcTa1(c.to_vec())).collect()
+			}
+			InformationObjects::CrcTa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::CrcTa1(c.to_vec())).collect()
+			}
+			InformationObjects::CSeTa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::CSeTa1(c.to_vec())).collect()
+			}
+			InformationObjects::CSeTb1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::CSeTb1(c.to_vec())).collect()
+			}
+			InformationObjects::CSeTc1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::CSeTc1(c.to_vec())).collect()
+			}
+			InformationObjects::CBoTa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::CBoTa1(c.to_vec())).collect()
+			}
+			InformationObjects::CIcNa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::CIcNa1(c.to_vec())).collect()
+			}
+			InformationObjects::CCiNa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::CCiNa1(c.to_vec())).collect()
+			}
+			InformationObjects::CRdNa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::CRdNa1(c.to_vec())).collect()
+			}
+			InformationObjects::CCsNa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::CCsNa1(c.to_vec())).collect()
+			}
+			InformationObjects::CTsNa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::CTsNa1(c.to_vec())).collect()
+			}
+			InformationObjects::CRpNa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::CRpNa1(c.to_vec())).collect()
+			}
+			InformationObjects::CCdNa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::CCdNa1(c.to_vec())).collect()
+			}
+			InformationObjects::CTsTa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::CTsTa1(c.to_vec())).collect()
+			}
+			InformationObjects::PMeNa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::PMeNa1(c.to_vec())).collect()
+			}
+			InformationObjects::PMeNb1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::PMeNb1(c.to_vec())).collect()
+			}
+			InformationObjects::PMeNc1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::PMeNc1(c.to_vec())).collect()
+			}
+			InformationObjects::PAcNa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::PAcNa1(c.to_vec())).collect()
+			}
+			InformationObjects::FFrNa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::FFrNa1(c.to_vec())).collect()
+			}
+			InformationObjects::FSrNa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::FSrNa1(c.to_vec())).collect()
+			}
+			InformationObjects::FScNa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::FScNa1(c.to_vec())).collect()
+			}
+			InformationObjects::FLsNa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::FLsNa1(c.to_vec())).collect()
+			}
+			InformationObjects::FFaNa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::FFaNa1(c.to_vec())).collect()
+			}
+			InformationObjects::FSgNa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::FSgNa1(c.to_vec())).collect()
+			}
+			InformationObjects::FDrTa1(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::FDrTa1(c.to_vec())).collect()
+			}
+			InformationObjects::Raw(objs) => {
+				objs.chunks(chunk_size).map(|c| InformationObjects::Raw(c.to_vec())).collect()
+			}
+		}
+	}
+
+	/// Moves the objects of `other` to the end of `self`. If the objects are
+	/// not of the same type `other` is given back.
+	#[allow(clippy::too_many_lines)]
+	pub fn append(&mut self, other: Self) -> Result<(), Self> {
+		match (self, other) {
+			(InformationObjects::MSpNa1(objs), InformationObjects::MSpNa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::MSpTa1(objs), InformationObjects::MSpTa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::MDpNa1(objs), InformationObjects::MDpNa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::MDpTa1(objs), InformationObjects::MDpTa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::MStNa1(objs), InformationObjects::MStNa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::MStTa1(objs), InformationObjects::MStTa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::MBoNa1(objs), InformationObjects::MBoNa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::MMeNa1(objs), InformationObjects::MMeNa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::MMeTa1(objs), InformationObjects::MMeTa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::MMeNb1(objs), InformationObjects::MMeNb1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::MMeTb1(objs), InformationObjects::MMeTb1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::MMeNc1(objs), InformationObjects::MMeNc1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::MMeTc1(objs), InformationObjects::MMeTc1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::MItNa1(objs), InformationObjects::MItNa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::MEpTa1(objs), InformationObjects::MEpTa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::MEpTb1(objs), InformationObjects::MEpTb1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::MEpTc1(objs), InformationObjects::MEpTc1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::MPsNa1(objs), InformationObjects::MPsNa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::MMeNd1(objs), InformationObjects::MMeNd1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::MSpTb1(objs), InformationObjects::MSpTb1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::MDpTb1(objs), InformationObjects::MDpTb1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::MStTb1(objs), InformationObjects::MStTb1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::MBoTb1(objs), InformationObjects::MBoTb1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::MMeTd1(objs), InformationObjects::MMeTd1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::MMeTe1(objs), InformationObjects::MMeTe1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::MMeTf1(objs), InformationObjects::MMeTf1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::MItTb1(objs), InformationObjects::MItTb1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::MEpTd1(objs), InformationObjects::MEpTd1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::MEpTe1(objs), InformationObjects::MEpTe1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::MEpTf1(objs), InformationObjects::MEpTf1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::MEiNa1(objs), InformationObjects::MEiNa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::CScNa1(objs), InformationObjects::CScNa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::CdcNa1(objs), InformationObjects::CdcNa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::CrcNa1(objs), InformationObjects::CrcNa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::CSeNa1(objs), InformationObjects::CSeNa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::CSeNb1(objs), InformationObjects::CSeNb1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::CSeNc1(objs), InformationObjects::CSeNc1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::CBoNa1(objs), InformationObjects::CBoNa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::CScTa1(objs), InformationObjects::CScTa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::CdcTa1(objs), InformationObjects::CdcTa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::CrcTa1(objs), InformationObjects::CrcTa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::CSeTa1(objs), InformationObjects::CSeTa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::CSeTb1(objs), InformationObjects::CSeTb1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::CSeTc1(objs), InformationObjects::CSeTc1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::CBoTa1(objs), InformationObjects::CBoTa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::CIcNa1(objs), InformationObjects::CIcNa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::CCiNa1(objs), InformationObjects::CCiNa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::CRdNa1(objs), InformationObjects::CRdNa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::CCsNa1(objs), InformationObjects::CCsNa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::CTsNa1(objs), InformationObjects::CTsNa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::CRpNa1(objs), InformationObjects::CRpNa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::CCdNa1(objs), InformationObjects::CCdNa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::CTsTa1(objs), InformationObjects::CTsTa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::PMeNa1(objs), InformationObjects::PMeNa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::PMeNb1(objs), InformationObjects::PMeNb1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::PMeNc1(objs), InformationObjects::PMeNc1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::PAcNa1(objs), InformationObjects::PAcNa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::FFrNa1(objs), InformationObjects::FFrNa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::FSrNa1(objs), InformationObjects::FSrNa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::FScNa1(objs), InformationObjects::FScNa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::FLsNa1(objs), InformationObjects::FLsNa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::FFaNa1(objs), InformationObjects::FFaNa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::FSgNa1(objs), InformationObjects::FSgNa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::FDrTa1(objs), InformationObjects::FDrTa1(mut other)) => {
+				objs.append(&mut other);
+			}
+			(InformationObjects::Raw(objs), InformationObjects::Raw(mut other)) => {
+				objs.append(&mut other);
+			}
+			(_, other) => return Err(other),
+		}
+		Ok(())
+	}
+
+	/// Sets the select/execute qualifier of every command. Returns `false` if
+	/// the type has no select/execute qualifier.
+	pub fn set_select_execute(&mut self, se: SelectExecute) -> bool {
 		match self {
-			InformationObjects::MSpNa1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::MSpTa1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::MDpNa1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::MDpTa1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::MStNa1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::MStTa1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::MBoNa1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::MMeNa1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::MMeTa1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::MMeNb1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::MMeTb1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::MMeNc1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::MMeTc1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::MItNa1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::MEpTa1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::MEpTb1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::MEpTc1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::MPsNa1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::MMeNd1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::MSpTb1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::MDpTb1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::MStTb1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::MBoTb1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::MMeTd1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::MMeTe1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::MMeTf1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::MItTb1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::MEpTd1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::MEpTe1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::MEpTf1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::MEiNa1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::CScNa1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::CdcNa1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::CrcNa1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::CSeNa1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::CSeNb1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::CSeNc1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::CBoNa1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::CScTa1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::CdcTa1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::CrcTa1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::CSeTa1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::CSeTb1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::CSeTc1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::CBoTa1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::CIcNa1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::CCiNa1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::CRdNa1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::CCsNa1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::CTsNa1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::CRpNa1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::CCdNa1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::CTsTa1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::PMeNa1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::PMeNb1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::PMeNc1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::PAcNa1(objs) => Self::serialize_objects(objs, buffer),
-			InformationObjects::Raw(objs) => Self::serialize_objects(objs, buffer),
+			InformationObjects::CScNa1(objs) => {
+				objs.iter_mut().for_each(|obj| obj.object.sco.se = se);
+			}
+			InformationObjects::CScTa1(objs) => {
+				objs.iter_mut().for_each(|obj| obj.object.sco.se = se);
+			}
+			InformationObjects::CdcNa1(objs) => {
+				objs.iter_mut().for_each(|obj| obj.object.dco.se = se);
+			}
+			InformationObjects::CdcTa1(objs) => {
+				objs.iter_mut().for_each(|obj| obj.object.dco.se = se);
+			}
+			InformationObjects::CrcNa1(objs) => {
+				objs.iter_mut().for_each(|obj| obj.object.rco.se = se);
+			}
+			InformationObjects::CrcTa1(objs) => {
+				objs.iter_mut().for_each(|obj| obj.object.rco.se = se);
+			}
+			InformationObjects::CSeNa1(objs) => {
+				objs.iter_mut().for_each(|obj| obj.object.qos.se = se);
+			}
+			InformationObjects::CSeNb1(objs) => {
+				objs.iter_mut().for_each(|obj| obj.object.qos.se = se);
+			}
+			InformationObjects::CSeNc1(objs) => {
+				objs.iter_mut().for_each(|obj| obj.object.qos.se = se);
+			}
+			InformationObjects::CSeTa1(objs) => {
+				objs.iter_mut().for_each(|obj| obj.object.qos.se = se);
+			}
+			InformationObjects::CSeTb1(objs) => {
+				objs.iter_mut().for_each(|obj| obj.object.qos.se = se);
+			}
+			InformationObjects::CSeTc1(objs) => {
+				objs.iter_mut().for_each(|obj| obj.object.qos.se = se);
+			}
+			_ => return false,
 		}
+		true
+	}
+
+	fn collect_addresses<T: FromBytes + ToBytes + Default>(
+		objects: &[GenericObject<T>],
+	) -> Vec<u32> {
+		objects.iter().map(|obj| obj.address).collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn scaled(address: u32, sva: u16) -> GenericObject<MMeNb1> {
+		GenericObject { address, object: MMeNb1 { sva, ..Default::default() } }
+	}
+
+	#[test]
+	fn test_objects_with_addresses() -> Result<(), ParseError> {
+		let objects = InformationObjects::MMeNb1(vec![scaled(10, 0x0102), scaled(20, 0x0304)]);
+		let bytes = [0x0A, 0x00, 0x00, 0x02, 0x01, 0x00, 0x14, 0x00, 0x00, 0x04, 0x03, 0x00];
+
+		let mut buffer = Vec::new();
+		objects.to_bytes(&mut buffer)?;
+		assert_eq!(buffer, bytes);
+		assert_eq!(
+			InformationObjects::from_bytes(TypeId::M_ME_NB_1, false, 2, ADDRESS_SIZE, &bytes)?,
+			objects
+		);
+		Ok(())
+	}
+
+	#[test]
+	fn test_sequence_of_objects() -> Result<(), ParseError> {
+		let objects = InformationObjects::MMeNb1(vec![
+			scaled(0x01_0000, 1),
+			scaled(0x01_0001, 2),
+			scaled(0x01_0002, 3),
+		]);
+		// Only the first object carries its address
+		let bytes = [0x00, 0x00, 0x01, 0x01, 0x00, 0x00, 0x02, 0x00, 0x00, 0x03, 0x00, 0x00];
+
+		let mut buffer = Vec::new();
+		objects.to_bytes_with_layout(true, ADDRESS_SIZE, &mut buffer)?;
+		assert_eq!(buffer, bytes);
+		assert_eq!(
+			InformationObjects::from_bytes(TypeId::M_ME_NB_1, true, 3, ADDRESS_SIZE, &bytes)?,
+			objects
+		);
+
+		// With 2 byte addresses
+		let objects = InformationObjects::MMeNb1(vec![scaled(0x0100, 1), scaled(0x0101, 2)]);
+		let bytes = [0x00, 0x01, 0x01, 0x00, 0x00, 0x02, 0x00, 0x00];
+		let mut buffer = Vec::new();
+		objects.to_bytes_with_layout(true, 2, &mut buffer)?;
+		assert_eq!(buffer, bytes);
+		assert_eq!(InformationObjects::from_bytes(TypeId::M_ME_NB_1, true, 2, 2, &bytes)?, objects);
+		Ok(())
+	}
+
+	#[test]
+	fn test_sequence_not_sequential() {
+		let objects = InformationObjects::MMeNb1(vec![scaled(10, 1), scaled(12, 2)]);
+		let mut buffer = Vec::new();
+		assert!(matches!(
+			objects.to_bytes_with_layout(true, ADDRESS_SIZE, &mut buffer),
+			Err(ParseError::NotSequential { address: 12, .. })
+		));
 	}
 }
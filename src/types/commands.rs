@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use snafu::{OptionExt as _, ResultExt as _};
 use tracing::instrument;
 
@@ -177,7 +178,8 @@ impl Rco {
 }
 
 /// Qualifier of interrogation
-#[derive(Debug, Clone, Eq, PartialEq, Default, Copy)]
+#[derive(Debug, Clone, Eq, PartialEq, Default, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 #[repr(u8)]
 pub enum Qoi {
 	#[default]
@@ -0,0 +1,578 @@
+use snafu::{OptionExt as _, ResultExt as _};
+use tracing::instrument;
+
+use crate::types::{
+	FromBytes, NotEnoughBytes, ParseError, ParseTimeTag, SegmentTooLong, SizedSlice, ToBytes,
+	time::Cp56Time2a,
+};
+
+/// Reads the 3 bytes long length of file or section.
+fn lof_from_bytes(bytes: &[u8]) -> Result<u32, ParseError> {
+	let bytes: &[u8; 3] = bytes.try_into().context(SizedSlice)?;
+	Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]))
+}
+
+fn lof_to_bytes(lof: u32, buffer: &mut Vec<u8>) {
+	buffer.extend_from_slice(&lof.to_le_bytes()[..3]);
+}
+
+fn nof_from_bytes(bytes: &[u8]) -> Result<u16, ParseError> {
+	Ok(u16::from_le_bytes(*bytes.first_chunk::<2>().context(NotEnoughBytes)?))
+}
+
+/// Error cause of the select and call qualifier and of the acknowledge file
+/// or section qualifier
+#[derive(Debug, Clone, Eq, PartialEq, Default, Copy)]
+#[repr(u8)]
+pub enum FileError {
+	#[default]
+	/// Default
+	Default,
+	/// Requested memory space not available
+	MemoryNotAvailable,
+	/// Checksum failed
+	ChecksumFailed,
+	/// Unexpected communication service
+	UnexpectedCommunicationService,
+	/// Unexpected name of file
+	UnexpectedNameOfFile,
+	/// Unexpected name of section
+	UnexpectedNameOfSection,
+	/// Other (custom)
+	Other(u8),
+}
+
+impl FileError {
+	#[must_use]
+	pub const fn from_byte(byte: u8) -> Self {
+		match byte {
+			0 => FileError::Default,
+			1 => FileError::MemoryNotAvailable,
+			2 => FileError::ChecksumFailed,
+			3 => FileError::UnexpectedCommunicationService,
+			4 => FileError::UnexpectedNameOfFile,
+			5 => FileError::UnexpectedNameOfSection,
+			_ => FileError::Other(byte),
+		}
+	}
+
+	#[must_use]
+	pub const fn to_byte(self) -> u8 {
+		match self {
+			FileError::Default => 0,
+			FileError::MemoryNotAvailable => 1,
+			FileError::ChecksumFailed => 2,
+			FileError::UnexpectedCommunicationService => 3,
+			FileError::UnexpectedNameOfFile => 4,
+			FileError::UnexpectedNameOfSection => 5,
+			FileError::Other(byte) => byte,
+		}
+	}
+}
+
+/// File ready qualifier
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct Frq {
+	/// Qualifier value (0-127)
+	pub value: u8,
+	/// Negative confirmation of select, request, deactivate or delete
+	pub negative: bool,
+}
+
+impl Frq {
+	#[must_use]
+	pub const fn from_byte(byte: u8) -> Self {
+		Self { value: byte & 0b0111_1111, negative: byte & 0b1000_0000 != 0 }
+	}
+
+	#[must_use]
+	pub const fn to_byte(&self) -> u8 {
+		(self.value & 0b0111_1111) | ((self.negative as u8) << 7)
+	}
+}
+
+/// Section ready qualifier
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct Srq {
+	/// Qualifier value (0-127)
+	pub value: u8,
+	/// Section not ready to load
+	pub not_ready: bool,
+}
+
+impl Srq {
+	#[must_use]
+	pub const fn from_byte(byte: u8) -> Self {
+		Self { value: byte & 0b0111_1111, not_ready: byte & 0b1000_0000 != 0 }
+	}
+
+	#[must_use]
+	pub const fn to_byte(&self) -> u8 {
+		(self.value & 0b0111_1111) | ((self.not_ready as u8) << 7)
+	}
+}
+
+/// Select and call qualifier action
+#[derive(Debug, Clone, Eq, PartialEq, Default, Copy)]
+#[repr(u8)]
+pub enum ScqAction {
+	#[default]
+	/// Default. Used to call the directory
+	Default,
+	/// Select file
+	SelectFile,
+	/// Request file
+	RequestFile,
+	/// Deactivate file
+	DeactivateFile,
+	/// Delete file
+	DeleteFile,
+	/// Select section
+	SelectSection,
+	/// Request section
+	RequestSection,
+	/// Deactivate section
+	DeactivateSection,
+	/// Other (custom)
+	Other(u8),
+}
+
+impl ScqAction {
+	#[must_use]
+	pub const fn from_byte(byte: u8) -> Self {
+		match byte {
+			0 => ScqAction::Default,
+			1 => ScqAction::SelectFile,
+			2 => ScqAction::RequestFile,
+			3 => ScqAction::DeactivateFile,
+			4 => ScqAction::DeleteFile,
+			5 => ScqAction::SelectSection,
+			6 => ScqAction::RequestSection,
+			7 => ScqAction::DeactivateSection,
+			_ => ScqAction::Other(byte),
+		}
+	}
+
+	#[must_use]
+	pub const fn to_byte(self) -> u8 {
+		match self {
+			ScqAction::Default => 0,
+			ScqAction::SelectFile => 1,
+			ScqAction::RequestFile => 2,
+			ScqAction::DeactivateFile => 3,
+			ScqAction::DeleteFile => 4,
+			ScqAction::SelectSection => 5,
+			ScqAction::RequestSection => 6,
+			ScqAction::DeactivateSection => 7,
+			ScqAction::Other(byte) => byte,
+		}
+	}
+}
+
+/// Select and call qualifier
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct Scq {
+	/// Requested action
+	pub action: ScqAction,
+	/// Error cause
+	pub error: FileError,
+}
+
+impl Scq {
+	#[must_use]
+	pub const fn from_byte(byte: u8) -> Self {
+		Self {
+			action: ScqAction::from_byte(byte & 0b0000_1111),
+			error: FileError::from_byte(byte >> 4),
+		}
+	}
+
+	#[must_use]
+	pub const fn to_byte(&self) -> u8 {
+		(self.action.to_byte() & 0b0000_1111) | (self.error.to_byte() << 4)
+	}
+}
+
+/// Last section or segment qualifier
+#[derive(Debug, Clone, Eq, PartialEq, Default, Copy)]
+#[repr(u8)]
+pub enum Lsq {
+	#[default]
+	/// Not used
+	Unused,
+	/// File transfer without deactivation
+	FileTransferWithoutDeactivation,
+	/// File transfer with deactivation
+	FileTransferWithDeactivation,
+	/// Section transfer without deactivation
+	SectionTransferWithoutDeactivation,
+	/// Section transfer with deactivation
+	SectionTransferWithDeactivation,
+	/// Other (custom)
+	Other(u8),
+}
+
+impl Lsq {
+	#[must_use]
+	pub const fn from_byte(byte: u8) -> Self {
+		match byte {
+			0 => Lsq::Unused,
+			1 => Lsq::FileTransferWithoutDeactivation,
+			2 => Lsq::FileTransferWithDeactivation,
+			3 => Lsq::SectionTransferWithoutDeactivation,
+			4 => Lsq::SectionTransferWithDeactivation,
+			_ => Lsq::Other(byte),
+		}
+	}
+
+	#[must_use]
+	pub const fn to_byte(self) -> u8 {
+		match self {
+			Lsq::Unused => 0,
+			Lsq::FileTransferWithoutDeactivation => 1,
+			Lsq::FileTransferWithDeactivation => 2,
+			Lsq::SectionTransferWithoutDeactivation => 3,
+			Lsq::SectionTransferWithDeactivation => 4,
+			Lsq::Other(byte) => byte,
+		}
+	}
+}
+
+/// Acknowledge file or section qualifier action
+#[derive(Debug, Clone, Eq, PartialEq, Default, Copy)]
+#[repr(u8)]
+pub enum AfqAction {
+	#[default]
+	/// Not used
+	Unused,
+	/// Positive acknowledge of file transfer
+	FilePositive,
+	/// Negative acknowledge of file transfer
+	FileNegative,
+	/// Positive acknowledge of section transfer
+	SectionPositive,
+	/// Negative acknowledge of section transfer
+	SectionNegative,
+	/// Other (custom)
+	Other(u8),
+}
+
+impl AfqAction {
+	#[must_use]
+	pub const fn from_byte(byte: u8) -> Self {
+		match byte {
+			0 => AfqAction::Unused,
+			1 => AfqAction::FilePositive,
+			2 => AfqAction::FileNegative,
+			3 => AfqAction::SectionPositive,
+			4 => AfqAction::SectionNegative,
+			_ => AfqAction::Other(byte),
+		}
+	}
+
+	#[must_use]
+	pub const fn to_byte(self) -> u8 {
+		match self {
+			AfqAction::Unused => 0,
+			AfqAction::FilePositive => 1,
+			AfqAction::FileNegative => 2,
+			AfqAction::SectionPositive => 3,
+			AfqAction::SectionNegative => 4,
+			AfqAction::Other(byte) => byte,
+		}
+	}
+}
+
+/// Acknowledge file or section qualifier
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct Afq {
+	/// Acknowledge
+	pub action: AfqAction,
+	/// Error cause
+	pub error: FileError,
+}
+
+impl Afq {
+	#[must_use]
+	pub const fn from_byte(byte: u8) -> Self {
+		Self {
+			action: AfqAction::from_byte(byte & 0b0000_1111),
+			error: FileError::from_byte(byte >> 4),
+		}
+	}
+
+	#[must_use]
+	pub const fn to_byte(&self) -> u8 {
+		(self.action.to_byte() & 0b0000_1111) | (self.error.to_byte() << 4)
+	}
+}
+
+/// Status of file
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct Sof {
+	/// Status (0-31)
+	pub status: u8,
+	/// Last file of the directory
+	pub lfd: bool,
+	/// The name defines a subdirectory
+	pub for_: bool,
+	/// File transfer is active
+	pub fa: bool,
+}
+
+impl Sof {
+	#[must_use]
+	pub const fn from_byte(byte: u8) -> Self {
+		Self {
+			status: byte & 0b0001_1111,
+			lfd: byte & 0b0010_0000 != 0,
+			for_: byte & 0b0100_0000 != 0,
+			fa: byte & 0b1000_0000 != 0,
+		}
+	}
+
+	#[must_use]
+	pub const fn to_byte(&self) -> u8 {
+		(self.status & 0b0001_1111)
+			| ((self.lfd as u8) << 5)
+			| ((self.for_ as u8) << 6)
+			| ((self.fa as u8) << 7)
+	}
+}
+
+/// File ready
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct FFrNa1 {
+	/// Name of file
+	pub nof: u16,
+	/// Length of file
+	pub lof: u32,
+	/// File ready qualifier
+	pub frq: Frq,
+}
+
+impl FromBytes for FFrNa1 {
+	#[instrument]
+	fn from_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+		let nof = nof_from_bytes(bytes)?;
+		let lof = lof_from_bytes(bytes.get(2..5).context(NotEnoughBytes)?)?;
+		let frq = Frq::from_byte(*bytes.get(5).context(NotEnoughBytes)?);
+		Ok(Self { nof, lof, frq })
+	}
+}
+
+impl ToBytes for FFrNa1 {
+	#[instrument]
+	fn to_bytes(&self, buffer: &mut Vec<u8>) -> Result<(), ParseError> {
+		buffer.extend_from_slice(&self.nof.to_le_bytes());
+		lof_to_bytes(self.lof, buffer);
+		buffer.push(self.frq.to_byte());
+		Ok(())
+	}
+}
+
+/// Section ready
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct FSrNa1 {
+	/// Name of file
+	pub nof: u16,
+	/// Name of section
+	pub nos: u8,
+	/// Length of section
+	pub lof: u32,
+	/// Section ready qualifier
+	pub srq: Srq,
+}
+
+impl FromBytes for FSrNa1 {
+	#[instrument]
+	fn from_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+		let nof = nof_from_bytes(bytes)?;
+		let nos = *bytes.get(2).context(NotEnoughBytes)?;
+		let lof = lof_from_bytes(bytes.get(3..6).context(NotEnoughBytes)?)?;
+		let srq = Srq::from_byte(*bytes.get(6).context(NotEnoughBytes)?);
+		Ok(Self { nof, nos, lof, srq })
+	}
+}
+
+impl ToBytes for FSrNa1 {
+	#[instrument]
+	fn to_bytes(&self, buffer: &mut Vec<u8>) -> Result<(), ParseError> {
+		buffer.extend_from_slice(&self.nof.to_le_bytes());
+		buffer.push(self.nos);
+		lof_to_bytes(self.lof, buffer);
+		buffer.push(self.srq.to_byte());
+		Ok(())
+	}
+}
+
+/// Call directory, select file, call file, call section
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct FScNa1 {
+	/// Name of file
+	pub nof: u16,
+	/// Name of section
+	pub nos: u8,
+	/// Select and call qualifier
+	pub scq: Scq,
+}
+
+impl FromBytes for FScNa1 {
+	#[instrument]
+	fn from_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+		let nof = nof_from_bytes(bytes)?;
+		let nos = *bytes.get(2).context(NotEnoughBytes)?;
+		let scq = Scq::from_byte(*bytes.get(3).context(NotEnoughBytes)?);
+		Ok(Self { nof, nos, scq })
+	}
+}
+
+impl ToBytes for FScNa1 {
+	#[instrument]
+	fn to_bytes(&self, buffer: &mut Vec<u8>) -> Result<(), ParseError> {
+		buffer.extend_from_slice(&self.nof.to_le_bytes());
+		buffer.push(self.nos);
+		buffer.push(self.scq.to_byte());
+		Ok(())
+	}
+}
+
+/// Last section, last segment
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct FLsNa1 {
+	/// Name of file
+	pub nof: u16,
+	/// Name of section
+	pub nos: u8,
+	/// Last section or segment qualifier
+	pub lsq: Lsq,
+	/// Checksum
+	pub chs: u8,
+}
+
+impl FromBytes for FLsNa1 {
+	#[instrument]
+	fn from_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+		let nof = nof_from_bytes(bytes)?;
+		let nos = *bytes.get(2).context(NotEnoughBytes)?;
+		let lsq = Lsq::from_byte(*bytes.get(3).context(NotEnoughBytes)?);
+		let chs = *bytes.get(4).context(NotEnoughBytes)?;
+		Ok(Self { nof, nos, lsq, chs })
+	}
+}
+
+impl ToBytes for FLsNa1 {
+	#[instrument]
+	fn to_bytes(&self, buffer: &mut Vec<u8>) -> Result<(), ParseError> {
+		buffer.extend_from_slice(&self.nof.to_le_bytes());
+		buffer.push(self.nos);
+		buffer.push(self.lsq.to_byte());
+		buffer.push(self.chs);
+		Ok(())
+	}
+}
+
+/// Acknowledge file, acknowledge section
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct FFaNa1 {
+	/// Name of file
+	pub nof: u16,
+	/// Name of section
+	pub nos: u8,
+	/// Acknowledge file or section qualifier
+	pub afq: Afq,
+}
+
+impl FromBytes for FFaNa1 {
+	#[instrument]
+	fn from_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+		let nof = nof_from_bytes(bytes)?;
+		let nos = *bytes.get(2).context(NotEnoughBytes)?;
+		let afq = Afq::from_byte(*bytes.get(3).context(NotEnoughBytes)?);
+		Ok(Self { nof, nos, afq })
+	}
+}
+
+impl ToBytes for FFaNa1 {
+	#[instrument]
+	fn to_bytes(&self, buffer: &mut Vec<u8>) -> Result<(), ParseError> {
+		buffer.extend_from_slice(&self.nof.to_le_bytes());
+		buffer.push(self.nos);
+		buffer.push(self.afq.to_byte());
+		Ok(())
+	}
+}
+
+/// Segment
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct FSgNa1 {
+	/// Name of file
+	pub nof: u16,
+	/// Name of section
+	pub nos: u8,
+	/// Segment data. The length of segment is the length of the data
+	pub segment: Vec<u8>,
+}
+
+impl FromBytes for FSgNa1 {
+	#[instrument]
+	fn from_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+		let nof = nof_from_bytes(bytes)?;
+		let nos = *bytes.get(2).context(NotEnoughBytes)?;
+		let los = *bytes.get(3).context(NotEnoughBytes)? as usize;
+		let segment = bytes.get(4..4 + los).context(NotEnoughBytes)?.to_vec();
+		Ok(Self { nof, nos, segment })
+	}
+}
+
+impl ToBytes for FSgNa1 {
+	#[instrument]
+	fn to_bytes(&self, buffer: &mut Vec<u8>) -> Result<(), ParseError> {
+		let los = u8::try_from(self.segment.len())
+			.ok()
+			.context(SegmentTooLong { len: self.segment.len() })?;
+		buffer.extend_from_slice(&self.nof.to_le_bytes());
+		buffer.push(self.nos);
+		buffer.push(los);
+		buffer.extend_from_slice(&self.segment);
+		Ok(())
+	}
+}
+
+/// Directory entry
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct FDrTa1 {
+	/// Name of file or subdirectory
+	pub nof: u16,
+	/// Length of file
+	pub lof: u32,
+	/// Status of file
+	pub sof: Sof,
+	/// Creation time of the file
+	pub time: Cp56Time2a,
+}
+
+impl FromBytes for FDrTa1 {
+	#[instrument]
+	fn from_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+		let nof = nof_from_bytes(bytes)?;
+		let lof = lof_from_bytes(bytes.get(2..5).context(NotEnoughBytes)?)?;
+		let sof = Sof::from_byte(*bytes.get(5).context(NotEnoughBytes)?);
+		let time = Cp56Time2a::from_bytes(
+			bytes.get(6..13).context(NotEnoughBytes)?.try_into().context(SizedSlice)?,
+		)
+		.context(ParseTimeTag)?;
+		Ok(Self { nof, lof, sof, time })
+	}
+}
+
+impl ToBytes for FDrTa1 {
+	#[instrument]
+	fn to_bytes(&self, buffer: &mut Vec<u8>) -> Result<(), ParseError> {
+		buffer.extend_from_slice(&self.nof.to_le_bytes());
+		lof_to_bytes(self.lof, buffer);
+		buffer.push(self.sof.to_byte());
+		buffer.extend_from_slice(&self.time.to_bytes());
+		Ok(())
+	}
+}
@@ -1,4 +1,10 @@
-use snafu::Snafu;
+use std::time::SystemTime;
+
+use ::time::{
+	Date, Month, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset,
+	error::{ComponentRange, IndeterminateOffset},
+};
+use snafu::{ResultExt as _, Snafu};
 use tracing::instrument;
 
 use crate::error::SpanTraceWrapper;
@@ -38,6 +44,35 @@ impl Cp24Time2a {
 		bytes[2] = (self.min & 0b0011_1111) | (u8::from(self.iv) << 7);
 		bytes
 	}
+
+	/// Expands the time tag into the absolute time closest to `reference`,
+	/// usually the reception time or the station clock estimated from the last
+	/// clock synchronization.
+	///
+	/// The time tag only carries the minutes and milliseconds, so the result
+	/// is within half an hour of the reference: a time tag of minute 59
+	/// received at 11:00:10 expands to 10:59 and a time tag of minute 0
+	/// referenced at 10:59:50 expands to 11:00.
+	pub fn expand(self, reference: OffsetDateTime) -> Result<OffsetDateTime, ParseTimeError> {
+		if self.ms > 59999 {
+			return MillisecondsError.fail();
+		}
+		if self.min > 59 {
+			return MinutesError.fail();
+		}
+		let same_hour = reference
+			.replace_time(Time::from_hms(reference.hour(), 0, 0).context(InvalidDateError)?)
+			+ ::time::Duration::minutes(i64::from(self.min))
+			+ ::time::Duration::milliseconds(i64::from(self.ms));
+		let half_hour = ::time::Duration::minutes(30);
+		Ok(if same_hour - reference > half_hour {
+			same_hour - ::time::Duration::HOUR
+		} else if reference - same_hour > half_hour {
+			same_hour + ::time::Duration::HOUR
+		} else {
+			same_hour
+		})
+	}
 }
 
 /// CP16Time2a time type
@@ -64,7 +99,11 @@ impl Cp16Time2a {
 }
 
 /// CP56Time2a time type
-#[derive(Debug, Clone, Eq, PartialEq, Default)]
+///
+/// The year is relative to 2000. The default value is 2000-01-01 00:00:00
+/// rather than all fields zero, since day 0 of month 0 isn't a valid date and
+/// would be rejected by [`Cp56Time2a::from_bytes`].
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Cp56Time2a {
 	/// Milliseconds (0-59999)
 	pub ms: u16,
@@ -76,7 +115,7 @@ pub struct Cp56Time2a {
 	pub summer_time: bool,
 	/// Hours (0-23)
 	pub hour: u8,
-	/// Weekday
+	/// Weekday (1-7, monday being 1). 0 if not used
 	pub weekday: u8,
 	/// Day of month (1-31)
 	pub day: u8,
@@ -108,15 +147,15 @@ impl Cp56Time2a {
 		if hour > 23 {
 			return HoursError.fail();
 		}
-		if day > 31 {
-			return DaysError.fail();
-		}
-		if month > 12 {
-			return MonthsError.fail();
-		}
 		if year > 99 {
 			return YearsError.fail();
 		}
+		let Ok(calendar_month) = Month::try_from(month) else {
+			return MonthsError.fail();
+		};
+		if day == 0 || day > calendar_month.length(2000 + i32::from(year)) {
+			return DaysError.fail();
+		}
 		Ok(Self { ms, iv, min, summer_time, hour, weekday, day, month, year })
 	}
 
@@ -135,6 +174,182 @@ impl Cp56Time2a {
 	}
 }
 
+impl Default for Cp56Time2a {
+	fn default() -> Self {
+		Self {
+			ms: 0,
+			iv: false,
+			min: 0,
+			summer_time: false,
+			hour: 0,
+			weekday: 0,
+			day: 1,
+			month: 1,
+			year: 0,
+		}
+	}
+}
+
+/// How the date and time of a [`Cp56Time2a`], which carries no offset, relate
+/// to UTC.
+///
+/// The summer time flag of the time selects the summer offset of the zone. The
+/// summer offset of [`TimeZone::Utc`] and [`TimeZone::Fixed`] is one hour
+/// ahead of their standard offset, the one of [`TimeZone::Local`] is the
+/// largest of the year.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum TimeZone {
+	/// The time is in UTC.
+	#[default]
+	Utc,
+	/// The time is in the local offset of the system at that date.
+	Local,
+	/// The time is in a fixed offset.
+	Fixed(UtcOffset),
+}
+
+impl TimeZone {
+	/// The offset of the time zone at the given instant.
+	fn offset_at(self, datetime: OffsetDateTime) -> Result<UtcOffset, ParseTimeError> {
+		match self {
+			TimeZone::Utc => Ok(UtcOffset::UTC),
+			TimeZone::Local => UtcOffset::local_offset_at(datetime).context(LocalOffsetError),
+			TimeZone::Fixed(offset) => Ok(offset),
+		}
+	}
+
+	/// The standard and summer offsets of the time zone in the year of the
+	/// instant.
+	fn offsets_in_year(
+		self,
+		datetime: OffsetDateTime,
+	) -> Result<(UtcOffset, UtcOffset), ParseTimeError> {
+		let offset_on = |month| -> Result<UtcOffset, ParseTimeError> {
+			let date =
+				Date::from_calendar_date(datetime.year(), month, 1).context(InvalidDateError)?;
+			self.offset_at(date.midnight().assume_utc())
+		};
+		let (january, july) = (offset_on(Month::January)?, offset_on(Month::July)?);
+		let standard = january.min(july);
+		let summer = if january == july {
+			UtcOffset::from_whole_seconds(standard.whole_seconds() + 3600)
+				.context(InvalidDateError)?
+		} else {
+			january.max(july)
+		};
+		Ok((standard, summer))
+	}
+}
+
+impl Cp56Time2a {
+	/// The instant of the time, interpreting it in the given time zone with
+	/// its standard or summer offset depending on the summer time flag. The
+	/// invalid flag is ignored.
+	pub fn to_offset_date_time(&self, zone: TimeZone) -> Result<OffsetDateTime, ParseTimeError> {
+		let datetime = PrimitiveDateTime::try_from(self.clone())?;
+		let (standard, summer) = zone.offsets_in_year(datetime.assume_utc())?;
+		Ok(datetime.assume_offset(if self.summer_time { summer } else { standard }))
+	}
+
+	/// The time of the instant in the given time zone. The summer time flag is
+	/// set if the offset of the zone at that instant isn't its standard one.
+	pub fn from_offset_date_time(
+		datetime: OffsetDateTime,
+		zone: TimeZone,
+	) -> Result<Self, ParseTimeError> {
+		let offset = zone.offset_at(datetime)?;
+		let (standard, _) = zone.offsets_in_year(datetime)?;
+		let datetime = datetime.to_offset(offset);
+		Ok(Self {
+			summer_time: offset != standard,
+			..Self::try_from(PrimitiveDateTime::new(datetime.date(), datetime.time()))?
+		})
+	}
+}
+
+impl TryFrom<PrimitiveDateTime> for Cp56Time2a {
+	type Error = ParseTimeError;
+
+	/// Fails if the year is not between 2000 and 2099. The summer time flag
+	/// isn't set since the value doesn't tell whether it's summer time, use
+	/// [`Cp56Time2a::from_offset_date_time`] to set it from a time zone.
+	fn try_from(value: PrimitiveDateTime) -> Result<Self, Self::Error> {
+		let Some(year) = value.year().checked_sub(2000).and_then(|year| u8::try_from(year).ok())
+		else {
+			return YearsError.fail();
+		};
+		if year > 99 {
+			return YearsError.fail();
+		}
+		Ok(Self {
+			ms: u16::from(value.second()) * 1000 + value.millisecond(),
+			iv: false,
+			min: value.minute(),
+			summer_time: false,
+			hour: value.hour(),
+			weekday: value.weekday().number_from_monday(),
+			day: value.day(),
+			month: u8::from(value.month()),
+			year,
+		})
+	}
+}
+
+impl TryFrom<OffsetDateTime> for Cp56Time2a {
+	type Error = ParseTimeError;
+
+	/// The time in UTC, whatever the offset of the value, without the summer
+	/// time flag. Use [`Cp56Time2a::from_offset_date_time`] to convert to
+	/// another time zone.
+	fn try_from(value: OffsetDateTime) -> Result<Self, Self::Error> {
+		let value = value.to_offset(UtcOffset::UTC);
+		Self::try_from(PrimitiveDateTime::new(value.date(), value.time()))
+	}
+}
+
+impl TryFrom<SystemTime> for Cp56Time2a {
+	type Error = ParseTimeError;
+
+	/// The time in UTC.
+	fn try_from(value: SystemTime) -> Result<Self, Self::Error> {
+		Self::try_from(OffsetDateTime::from(value))
+	}
+}
+
+impl TryFrom<Cp56Time2a> for PrimitiveDateTime {
+	type Error = ParseTimeError;
+
+	fn try_from(value: Cp56Time2a) -> Result<Self, Self::Error> {
+		let month = Month::try_from(value.month).context(InvalidDateError)?;
+		let date = Date::from_calendar_date(2000 + i32::from(value.year), month, value.day)
+			.context(InvalidDateError)?;
+		let time =
+			Time::from_hms_milli(value.hour, value.min, (value.ms / 1000) as u8, value.ms % 1000)
+				.context(InvalidDateError)?;
+		Ok(PrimitiveDateTime::new(date, time))
+	}
+}
+
+impl TryFrom<Cp56Time2a> for OffsetDateTime {
+	type Error = ParseTimeError;
+
+	/// Interprets the time as UTC, one hour ahead if the summer time flag is
+	/// set. Use [`Cp56Time2a::to_offset_date_time`] for other time zones.
+	fn try_from(value: Cp56Time2a) -> Result<Self, Self::Error> {
+		value.to_offset_date_time(TimeZone::Utc)
+	}
+}
+
+impl TryFrom<Cp56Time2a> for SystemTime {
+	type Error = ParseTimeError;
+
+	/// Interprets the time as UTC, one hour ahead if the summer time flag is
+	/// set.
+	fn try_from(value: Cp56Time2a) -> Result<Self, Self::Error> {
+		Ok(OffsetDateTime::try_from(value)?.into())
+	}
+}
+
 #[derive(Debug, Snafu)]
 #[snafu(visibility(pub), context(suffix(Error)))]
 pub enum ParseTimeError {
@@ -168,4 +383,100 @@ pub enum ParseTimeError {
 		#[snafu(implicit)]
 		context: Box<SpanTraceWrapper>,
 	},
+	#[snafu(display("Invalid date or time"))]
+	InvalidDate {
+		source: ComponentRange,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+	#[snafu(display("The local offset can't be determined"))]
+	LocalOffset {
+		source: IndeterminateOffset,
+		#[snafu(implicit)]
+		context: Box<SpanTraceWrapper>,
+	},
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_date_validation() {
+		// 2024-02-29 is a leap day, 2023-02-29 doesn't exist
+		assert!(Cp56Time2a::from_bytes(&[0, 0, 0, 0, 29, 2, 24]).is_ok());
+		assert!(matches!(
+			Cp56Time2a::from_bytes(&[0, 0, 0, 0, 29, 2, 23]),
+			Err(ParseTimeError::Days { .. })
+		));
+		assert!(matches!(
+			Cp56Time2a::from_bytes(&[0, 0, 0, 0, 30, 2, 24]),
+			Err(ParseTimeError::Days { .. })
+		));
+		assert!(matches!(
+			Cp56Time2a::from_bytes(&[0, 0, 0, 0, 1, 0, 24]),
+			Err(ParseTimeError::Months { .. })
+		));
+		assert!(Cp56Time2a::from_bytes(&Cp56Time2a::default().to_bytes()).is_ok());
+	}
+
+	#[test]
+	fn test_cp24_expansion() {
+		// 2023-11-14 22:13:20 UTC
+		let reference = OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+		let expand = |min, ms| Cp24Time2a { ms, min, iv: false }.expand(reference).unwrap();
+
+		assert_eq!(expand(13, 15_000), reference - ::time::Duration::seconds(5));
+		// Tags from the end of the previous hour and the start of the next one
+		assert_eq!(expand(50, 0).hour(), 21);
+		assert_eq!(expand(40, 0).hour(), 22);
+		let next_hour = Cp24Time2a { ms: 0, min: 5, iv: false }
+			.expand(reference + ::time::Duration::minutes(45))
+			.unwrap();
+		assert_eq!((next_hour.day(), next_hour.hour(), next_hour.minute()), (14, 23, 5));
+		let next_day = Cp24Time2a { ms: 0, min: 5, iv: false }
+			.expand(reference + ::time::Duration::minutes(105))
+			.unwrap();
+		assert_eq!((next_day.day(), next_day.hour(), next_day.minute()), (15, 0, 5));
+
+		assert!(Cp24Time2a { ms: 60_000, min: 0, iv: false }.expand(reference).is_err());
+	}
+
+	#[test]
+	fn test_date_time_conversions() {
+		// 2023-11-14 22:13:20.250 UTC, a tuesday
+		let datetime =
+			OffsetDateTime::from_unix_timestamp_nanos(1_700_000_000_250_000_000).unwrap();
+		let cp56 = Cp56Time2a::try_from(datetime).unwrap();
+		assert_eq!((cp56.year, cp56.month, cp56.day, cp56.weekday), (23, 11, 14, 2));
+		assert_eq!((cp56.hour, cp56.min, cp56.ms), (22, 13, 20_250));
+		assert_eq!(OffsetDateTime::try_from(cp56.clone()).unwrap(), datetime);
+		assert_eq!(SystemTime::try_from(cp56.clone()).unwrap(), SystemTime::from(datetime));
+		assert_eq!(Cp56Time2a::try_from(SystemTime::from(datetime)).unwrap(), cp56);
+		// The offset of the value doesn't change the instant
+		let offset = datetime.to_offset(UtcOffset::from_hms(-5, 0, 0).unwrap());
+		assert_eq!(Cp56Time2a::try_from(offset).unwrap(), cp56);
+		assert_eq!(
+			OffsetDateTime::try_from(Cp56Time2a::try_from(offset).unwrap()).unwrap(),
+			offset
+		);
+
+		let zone = TimeZone::Fixed(UtcOffset::from_hms(2, 0, 0).unwrap());
+		let shifted = Cp56Time2a::from_offset_date_time(datetime, zone).unwrap();
+		assert_eq!((shifted.day, shifted.hour, shifted.weekday), (15, 0, 3));
+		assert_eq!(shifted.to_offset_date_time(zone).unwrap(), datetime);
+
+		// The same instant in summer time is one hour ahead of the standard time
+		let summer = Cp56Time2a { summer_time: true, hour: 23, ..cp56.clone() };
+		assert_eq!(OffsetDateTime::try_from(summer.clone()).unwrap(), datetime);
+		// 23:13:20 in the summer offset of UTC+2 is 20:13:20 UTC
+		assert_eq!(
+			summer.to_offset_date_time(zone).unwrap(),
+			datetime - ::time::Duration::hours(2)
+		);
+		assert!(!shifted.summer_time);
+
+		let before_2000 = OffsetDateTime::from_unix_timestamp(0).unwrap();
+		assert!(matches!(Cp56Time2a::try_from(before_2000), Err(ParseTimeError::Years { .. })));
+	}
 }
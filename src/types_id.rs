@@ -649,17 +649,24 @@ impl TypeId {
 			TypeId::P_ME_NB_1 => 3,
 			TypeId::P_ME_NC_1 => 5,
 			TypeId::P_AC_NA_1 => 1,
-			//TODO: Check if these are correct
 			TypeId::F_FR_NA_1 => 6,
-			TypeId::F_SR_NA_1 => 6,
-			TypeId::F_SC_NA_1 => 6,
-			TypeId::F_LS_NA_1 => 6,
-			TypeId::F_FA_NA_1 => 6,
-			TypeId::F_SG_NA_1 => 6,
-			TypeId::F_DR_TA_1 => 6,
+			TypeId::F_SR_NA_1 => 7,
+			TypeId::F_SC_NA_1 => 4,
+			TypeId::F_LS_NA_1 => 5,
+			TypeId::F_FA_NA_1 => 4,
+			// Only the fixed part. The segment itself has a variable length
+			TypeId::F_SG_NA_1 => 4,
+			TypeId::F_DR_TA_1 => 13,
 			_ => 0,
 		}
 	}
+	/// Returns `false` for the types whose objects have a variable size. Only
+	/// one object of these types fits in an ASDU.
+	#[must_use]
+	pub const fn has_fixed_size(self) -> bool {
+		!matches!(self, TypeId::F_SG_NA_1)
+	}
+
 	/// Returns `true` if the type is standard, returns `false` if it is custom.
 	#[must_use]
 	pub const fn is_standard(self) -> bool {